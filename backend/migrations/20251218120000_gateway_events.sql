@@ -0,0 +1,7 @@
+CREATE TABLE gateway_events (
+  id BIGSERIAL PRIMARY KEY,
+  payload JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_gateway_events_created_at ON gateway_events(created_at);
@@ -0,0 +1,14 @@
+CREATE TYPE dm_privacy AS ENUM (
+  'everyone',
+  'server_members',
+  'friends'
+);
+
+ALTER TABLE profiles
+ADD COLUMN dm_privacy dm_privacy NOT NULL DEFAULT 'everyone';
+
+UPDATE profiles
+SET dm_privacy = 'friends'
+WHERE allow_dms = FALSE;
+
+ALTER TABLE profiles DROP COLUMN allow_dms;
@@ -0,0 +1,27 @@
+ALTER TABLE dm_channels
+ADD COLUMN owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+ADD COLUMN icon_url TEXT;
+
+UPDATE dm_channels dc
+SET owner_id = (
+  SELECT dp.user_id
+  FROM dm_participants dp
+  WHERE dp.dm_channel_id = dc.id
+  ORDER BY dp.joined_at ASC
+  LIMIT 1
+)
+FROM channels c
+WHERE c.id = dc.channel_id
+  AND c.channel_type = 'group_dm';
+
+CREATE TYPE message_type AS ENUM (
+  'default',
+  'recipient_add',
+  'recipient_remove',
+  'group_rename',
+  'group_icon_change',
+  'group_owner_change'
+);
+
+ALTER TABLE messages
+ADD COLUMN message_type message_type NOT NULL DEFAULT 'default';
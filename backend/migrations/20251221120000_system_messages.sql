@@ -0,0 +1,12 @@
+ALTER TYPE message_type ADD VALUE IF NOT EXISTS 'member_join';
+ALTER TYPE message_type ADD VALUE IF NOT EXISTS 'member_leave';
+ALTER TYPE message_type ADD VALUE IF NOT EXISTS 'channel_pinned';
+
+ALTER TABLE messages
+ALTER COLUMN user_id DROP NOT NULL,
+ADD COLUMN system_data JSONB;
+
+-- System messages are no longer attributed to the acting user
+UPDATE messages
+SET user_id = NULL
+WHERE message_type <> 'default';
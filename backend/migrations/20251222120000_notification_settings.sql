@@ -0,0 +1,23 @@
+CREATE TYPE notification_level AS ENUM (
+  'all_messages',
+  'only_mentions',
+  'nothing'
+);
+
+ALTER TABLE server_organization
+ADD COLUMN notification_level notification_level NOT NULL DEFAULT 'all_messages',
+ADD COLUMN muted_until TIMESTAMPTZ,
+ADD COLUMN suppress_everyone BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Applies to server channels and DMs, a NULL level inherits from the server
+CREATE TABLE channel_notification_settings (
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  channel_id UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+  notification_level notification_level,
+  muted_until TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (user_id, channel_id)
+);
+
+CREATE INDEX idx_channel_notification_settings_channel_id ON channel_notification_settings(channel_id);
@@ -0,0 +1,22 @@
+-- Bumping token_version invalidates every JWT issued to the user
+ALTER TABLE users
+ADD COLUMN email_verified_at TIMESTAMPTZ,
+ADD COLUMN token_version INT NOT NULL DEFAULT 0;
+
+CREATE TYPE user_token_kind AS ENUM (
+  'email_verification',
+  'password_reset'
+);
+
+-- One-time tokens, only an HMAC of the token is stored
+CREATE TABLE user_tokens (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  kind user_token_kind NOT NULL,
+  token_hash TEXT NOT NULL UNIQUE,
+  expires_at TIMESTAMPTZ NOT NULL,
+  used_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_tokens_user_id ON user_tokens(user_id);
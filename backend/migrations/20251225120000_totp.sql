@@ -0,0 +1,17 @@
+-- A secret without totp_enabled_at is an enrollment awaiting its first code
+ALTER TABLE users
+ADD COLUMN totp_secret TEXT,
+ADD COLUMN totp_enabled_at TIMESTAMPTZ,
+ADD COLUMN totp_last_used_step BIGINT;
+
+CREATE TABLE mfa_backup_codes (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  code_hash TEXT NOT NULL,
+  used_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_mfa_backup_codes_user_id ON mfa_backup_codes(user_id);
+
+ALTER TYPE user_token_kind ADD VALUE 'mfa_ticket';
@@ -0,0 +1,4 @@
+-- Deleted accounts keep their row so authored messages stay attributed to an anonymous user
+ALTER TABLE users
+ADD COLUMN username_changed_at TIMESTAMPTZ,
+ADD COLUMN deleted_at TIMESTAMPTZ;
@@ -0,0 +1,12 @@
+ALTER TABLE channels
+ADD COLUMN slowmode_seconds INT NOT NULL DEFAULT 0
+  CHECK (slowmode_seconds >= 0 AND slowmode_seconds <= 21600);
+
+-- Fixed-window counters shared by every instance when RATE_LIMIT_BACKEND=postgres
+CREATE UNLOGGED TABLE rate_limits (
+  key TEXT PRIMARY KEY,
+  window_start TIMESTAMPTZ NOT NULL,
+  count INT NOT NULL
+);
+
+CREATE INDEX idx_rate_limits_window_start ON rate_limits(window_start);
@@ -0,0 +1,11 @@
+ALTER TABLE users
+ADD COLUMN failed_login_attempts INT NOT NULL DEFAULT 0,
+ADD COLUMN last_failed_login_at TIMESTAMPTZ,
+ADD COLUMN locked_until TIMESTAMPTZ;
+
+CREATE UNLOGGED TABLE login_ip_failures (
+  ip TEXT PRIMARY KEY,
+  failures INT NOT NULL,
+  last_failed_at TIMESTAMPTZ NOT NULL,
+  locked_until TIMESTAMPTZ
+);
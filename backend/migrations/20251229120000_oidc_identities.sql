@@ -0,0 +1,35 @@
+-- Accounts created through a provider get a random password until the user sets one
+ALTER TABLE users
+ADD COLUMN password_set BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE TABLE user_identities (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  provider TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  email TEXT,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_login_at TIMESTAMPTZ,
+  UNIQUE (provider, subject),
+  UNIQUE (user_id, provider)
+);
+
+-- In-flight authorization requests, user_id is set when connecting a provider to an account
+CREATE TABLE oidc_auth_requests (
+  state_hash TEXT PRIMARY KEY,
+  provider TEXT NOT NULL,
+  code_verifier TEXT NOT NULL,
+  nonce TEXT NOT NULL,
+  user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+  expires_at TIMESTAMPTZ NOT NULL
+);
+
+-- Verified external identities waiting for the user to pick a username
+CREATE TABLE oidc_pending_signups (
+  token_hash TEXT PRIMARY KEY,
+  provider TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  email TEXT,
+  email_verified BOOLEAN NOT NULL,
+  expires_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,19 @@
+ALTER TABLE users
+ADD COLUMN bot BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Each application owns exactly one bot user, its token is stored as an HMAC like user tokens
+CREATE TABLE applications (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  bot_user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  description TEXT,
+  -- Private bots can only be added to servers by their owner
+  public BOOLEAN NOT NULL DEFAULT FALSE,
+  allow_dms BOOLEAN NOT NULL DEFAULT FALSE,
+  bot_token_hash TEXT NOT NULL UNIQUE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_applications_owner_id ON applications(owner_id);
@@ -0,0 +1,18 @@
+CREATE TABLE webhooks (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  channel_id UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+  creator_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  avatar_url TEXT,
+  token_hash TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhooks_channel_id ON webhooks(channel_id);
+
+-- Webhook messages have no user, the name and avatar they were posted with are kept on the message
+ALTER TABLE messages
+ADD COLUMN webhook_id UUID REFERENCES webhooks(id) ON DELETE SET NULL,
+ADD COLUMN author_name TEXT,
+ADD COLUMN author_avatar_url TEXT;
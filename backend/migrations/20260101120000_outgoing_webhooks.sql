@@ -0,0 +1,55 @@
+CREATE TYPE outgoing_webhook_event AS ENUM (
+  'message_created',
+  'message_updated',
+  'message_deleted',
+  'member_joined',
+  'member_left'
+);
+
+-- The secret signs every delivery, so unlike tokens it is kept as is
+CREATE TABLE outgoing_webhooks (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+  creator_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  url TEXT NOT NULL,
+  secret TEXT NOT NULL,
+  events outgoing_webhook_event[] NOT NULL,
+  enabled BOOLEAN NOT NULL DEFAULT TRUE,
+  consecutive_failures INT NOT NULL DEFAULT 0,
+  disabled_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_outgoing_webhooks_server_id ON outgoing_webhooks(server_id);
+
+-- Pending deliveries, drained by the outgoing webhook worker
+CREATE TABLE outgoing_webhook_outbox (
+  id BIGSERIAL PRIMARY KEY,
+  webhook_id UUID NOT NULL REFERENCES outgoing_webhooks(id) ON DELETE CASCADE,
+  delivery_id UUID NOT NULL DEFAULT gen_random_uuid(),
+  event outgoing_webhook_event NOT NULL,
+  payload JSONB NOT NULL,
+  attempts INT NOT NULL DEFAULT 0,
+  next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_error TEXT,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_outgoing_webhook_outbox_next_attempt_at ON outgoing_webhook_outbox(next_attempt_at);
+
+-- One row per attempt, shown to the server owner
+CREATE TABLE outgoing_webhook_deliveries (
+  id BIGSERIAL PRIMARY KEY,
+  webhook_id UUID NOT NULL REFERENCES outgoing_webhooks(id) ON DELETE CASCADE,
+  delivery_id UUID NOT NULL,
+  event outgoing_webhook_event NOT NULL,
+  attempt INT NOT NULL,
+  success BOOLEAN NOT NULL,
+  status_code INT,
+  error TEXT,
+  duration_ms INT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_outgoing_webhook_deliveries_webhook_id ON outgoing_webhook_deliveries(webhook_id, created_at DESC);
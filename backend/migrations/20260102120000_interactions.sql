@@ -0,0 +1,37 @@
+-- Applications that receive interactions over HTTP instead of the gateway
+ALTER TABLE applications
+ADD COLUMN interactions_url TEXT,
+ADD COLUMN interactions_secret TEXT;
+
+-- Global commands have no server, server commands are only offered in that server
+CREATE TABLE application_commands (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  application_id UUID NOT NULL REFERENCES applications(id) ON DELETE CASCADE,
+  server_id UUID REFERENCES servers(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  description TEXT NOT NULL,
+  options JSONB NOT NULL DEFAULT '[]',
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE NULLS NOT DISTINCT (application_id, server_id, name)
+);
+
+CREATE INDEX idx_application_commands_server_id ON application_commands(server_id);
+
+CREATE TYPE interaction_state AS ENUM ('pending', 'deferred', 'completed');
+
+-- A command invocation, the bot answers it with the token it was sent
+CREATE TABLE interactions (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  application_id UUID NOT NULL REFERENCES applications(id) ON DELETE CASCADE,
+  command_id UUID REFERENCES application_commands(id) ON DELETE SET NULL,
+  channel_id UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  token_hash TEXT NOT NULL,
+  state interaction_state NOT NULL DEFAULT 'pending',
+  ephemeral BOOLEAN NOT NULL DEFAULT FALSE,
+  expires_at TIMESTAMPTZ NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_interactions_expires_at ON interactions(expires_at);
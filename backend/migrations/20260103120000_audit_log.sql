@@ -0,0 +1,29 @@
+CREATE TYPE audit_log_action AS ENUM (
+  'server_update',
+  'server_ownership_transfer',
+  'channel_create',
+  'channel_update',
+  'channel_delete',
+  'bot_add',
+  'webhook_create',
+  'webhook_delete',
+  'outgoing_webhook_create',
+  'outgoing_webhook_update',
+  'outgoing_webhook_delete',
+  'message_delete'
+);
+
+-- Entries outlive the actor and target, they are only removed with the server
+CREATE TABLE audit_log_entries (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+  actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  action audit_log_action NOT NULL,
+  target_id UUID,
+  -- [{ key, old_value, new_value }]
+  changes JSONB NOT NULL DEFAULT '[]',
+  reason TEXT,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_entries_server_created ON audit_log_entries(server_id, created_at DESC);
@@ -0,0 +1,16 @@
+-- Images are small and capped in size, they are kept with the emoji rather than in separate storage
+CREATE TABLE emojis (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+  creator_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  name TEXT NOT NULL,
+  content_type TEXT NOT NULL,
+  image BYTEA NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (server_id, name)
+);
+
+ALTER TYPE audit_log_action ADD VALUE 'emoji_create';
+ALTER TYPE audit_log_action ADD VALUE 'emoji_update';
+ALTER TYPE audit_log_action ADD VALUE 'emoji_delete';
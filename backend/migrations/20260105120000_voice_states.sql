@@ -0,0 +1,19 @@
+-- Server mute is kept on the membership so leaving and rejoining voice does not lift it
+ALTER TABLE server_members
+ADD COLUMN voice_muted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- A user is in at most one voice channel, through the socket that joined it
+CREATE TABLE voice_states (
+  user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+  channel_id UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+  session_id UUID NOT NULL,
+  self_mute BOOLEAN NOT NULL DEFAULT FALSE,
+  self_deaf BOOLEAN NOT NULL DEFAULT FALSE,
+  joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_voice_states_channel_id ON voice_states(channel_id);
+CREATE INDEX idx_voice_states_server_id ON voice_states(server_id);
+
+ALTER TYPE audit_log_action ADD VALUE 'member_voice_update';
@@ -0,0 +1,48 @@
+-- Public servers are listed in discovery and can be joined without an invite
+ALTER TABLE servers
+ADD COLUMN public BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN description TEXT,
+ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}',
+ADD COLUMN member_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('simple', name), 'A') ||
+  setweight(to_tsvector('simple', COALESCE(description, '')), 'B')
+) STORED;
+
+CREATE INDEX idx_servers_public_member_count ON servers(member_count DESC) WHERE public;
+CREATE INDEX idx_servers_search_vector ON servers USING GIN(search_vector);
+CREATE INDEX idx_servers_tags ON servers USING GIN(tags);
+
+UPDATE servers s
+SET member_count = (SELECT COUNT(*) FROM server_members sm WHERE sm.server_id = s.id);
+
+-- Kept on the server so discovery can sort by it without counting every membership
+CREATE OR REPLACE FUNCTION update_server_member_count()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    UPDATE servers SET member_count = member_count + 1 WHERE id = NEW.server_id;
+  ELSE
+    UPDATE servers SET member_count = member_count - 1 WHERE id = OLD.server_id;
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_update_server_member_count
+AFTER INSERT OR DELETE ON server_members
+FOR EACH ROW
+EXECUTE FUNCTION update_server_member_count();
+
+-- Bans outlive the membership so a banned user cannot join the server again
+CREATE TABLE server_bans (
+  server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  reason TEXT,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (server_id, user_id)
+);
+
+ALTER TYPE audit_log_action ADD VALUE 'member_ban';
+ALTER TYPE audit_log_action ADD VALUE 'member_unban';
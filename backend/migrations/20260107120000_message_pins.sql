@@ -0,0 +1,5 @@
+ALTER TABLE messages
+ADD COLUMN pinned_at TIMESTAMPTZ,
+ADD COLUMN pinned_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_messages_pinned ON messages(channel_id, pinned_at DESC) WHERE pinned_at IS NOT NULL;
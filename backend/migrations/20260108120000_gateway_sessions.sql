@@ -0,0 +1,17 @@
+-- Every node heartbeats while it runs, one that stops is removed with its sockets by the others
+CREATE TABLE gateway_nodes (
+  id UUID PRIMARY KEY,
+  started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One row per open socket, a user is online while any of theirs is on a live node
+CREATE TABLE gateway_sessions (
+  id UUID PRIMARY KEY,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  node_id UUID NOT NULL REFERENCES gateway_nodes(id) ON DELETE CASCADE,
+  connected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_gateway_sessions_user_id ON gateway_sessions(user_id);
+CREATE INDEX idx_gateway_sessions_node_id ON gateway_sessions(node_id);
@@ -0,0 +1,5 @@
+-- Proof of a fresh provider login, for accounts that never set a password
+ALTER TYPE user_token_kind ADD VALUE 'reauthentication';
+
+ALTER TABLE oidc_auth_requests
+ADD COLUMN reauthenticate BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,8 @@
+-- Password failures counted per normalized email or username, whether or not an account
+-- has it, so a lockout does not reveal which accounts exist
+CREATE UNLOGGED TABLE login_identifier_failures (
+  identifier TEXT PRIMARY KEY,
+  failures INT NOT NULL,
+  last_failed_at TIMESTAMPTZ NOT NULL,
+  locked_until TIMESTAMPTZ
+);
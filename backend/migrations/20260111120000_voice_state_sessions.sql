@@ -0,0 +1,7 @@
+-- Voice states go with the socket that joined, also when its node stops heartbeating.
+-- Older rows have no session to belong to and are dropped
+DELETE FROM voice_states;
+
+ALTER TABLE voice_states
+ADD CONSTRAINT voice_states_session_id_fkey
+FOREIGN KEY (session_id) REFERENCES gateway_sessions(id) ON DELETE CASCADE;
@@ -0,0 +1,6 @@
+-- Pins are not part of the API yet, so the columns added for them go again
+DROP INDEX IF EXISTS idx_messages_pinned;
+
+ALTER TABLE messages
+DROP COLUMN IF EXISTS pinned_at,
+DROP COLUMN IF EXISTS pinned_by;
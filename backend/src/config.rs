@@ -0,0 +1,742 @@
+// backend/src/config.rs
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::jwt::{JwtKeyError, JwtKeys};
+
+// Shortest secret accepted for signing or hashing tokens
+const MIN_JWT_SECRET_LENGTH: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+  #[error("failed to read config file {path}: {source}")]
+  Read {
+    path: String,
+    source: std::io::Error,
+  },
+  #[error("failed to parse config file {path}: {source}")]
+  Parse {
+    path: String,
+    source: toml::de::Error,
+  },
+  #[error("environment variable {name} has invalid value '{value}'")]
+  InvalidEnv { name: &'static str, value: String },
+  #[error("{0}")]
+  Invalid(String),
+  #[error(transparent)]
+  JwtKey(#[from] JwtKeyError),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+  pub host: IpAddr,
+  pub port: u16,
+  // Take the client address from X-Forwarded-For, only enable behind a reverse proxy
+  pub trust_proxy: bool,
+}
+
+impl Default for ServerConfig {
+  fn default() -> Self {
+    Self {
+      host: IpAddr::from([0, 0, 0, 0]),
+      port: 8000,
+      trust_proxy: false,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+  pub url: String,
+  pub max_connections: u32,
+  pub min_connections: u32,
+  pub acquire_timeout_secs: u64,
+}
+
+impl Default for DatabaseConfig {
+  fn default() -> Self {
+    Self {
+      url: String::new(),
+      max_connections: 5,
+      min_connections: 0,
+      acquire_timeout_secs: 30,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerificationKeyConfig {
+  pub key_id: String,
+  pub public_key_file: String,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtConfig {
+  // Signs JWTs with HS256 when no private key is configured
+  pub secret: String,
+  // Keys the HMAC of stored tokens and codes, changing it invalidates every one of them
+  pub token_hash_secret: String,
+  pub expiration_secs: i64,
+  pub issuer: String,
+  pub audience: String,
+  // Ed25519 or RSA key in PEM format, tokens are signed with EdDSA or RS256 accordingly
+  pub private_key_file: Option<String>,
+  pub key_id: String,
+  // Extra public keys still accepted, e.g. the previous signing key during rotation
+  pub verification_keys: Vec<VerificationKeyConfig>,
+  #[serde(skip)]
+  pub keys: JwtKeys,
+}
+
+impl Default for JwtConfig {
+  fn default() -> Self {
+    Self {
+      secret: String::new(),
+      token_hash_secret: String::new(),
+      expiration_secs: 86400,
+      issuer: "harmony".to_string(),
+      audience: "harmony".to_string(),
+      private_key_file: None,
+      key_id: "default".to_string(),
+      verification_keys: Vec::new(),
+      keys: JwtKeys::default(),
+    }
+  }
+}
+
+// Keeps the secret out of startup logs
+impl std::fmt::Debug for JwtConfig {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("JwtConfig")
+      .field("secret", &"<redacted>")
+      .field("token_hash_secret", &"<redacted>")
+      .field("expiration_secs", &self.expiration_secs)
+      .field("issuer", &self.issuer)
+      .field("audience", &self.audience)
+      .field("private_key_file", &self.private_key_file)
+      .field("key_id", &self.key_id)
+      .field("verification_keys", &self.verification_keys)
+      .finish()
+  }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+  // Ignored in debug builds, which allow any origin
+  pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+  pub max_message_length: usize,
+  // Largest request body accepted, uploads included
+  pub max_body_bytes: usize,
+  // Custom emoji image size after decoding, and how many a server may have
+  pub max_emoji_bytes: usize,
+  pub max_emojis_per_server: i64,
+}
+
+impl Default for LimitsConfig {
+  fn default() -> Self {
+    Self {
+      max_message_length: 2000,
+      max_body_bytes: 8 * 1024 * 1024,
+      max_emoji_bytes: 256 * 1024,
+      max_emojis_per_server: 50,
+    }
+  }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OidcProviderConfig {
+  pub id: String,
+  pub name: String,
+  pub issuer: String,
+  pub client_id: String,
+  // Public clients rely on PKCE alone
+  #[serde(default)]
+  pub client_secret: Option<String>,
+  #[serde(default = "default_oidc_scopes")]
+  pub scopes: Vec<String>,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+  ["openid", "email", "profile"]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+impl std::fmt::Debug for OidcProviderConfig {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("OidcProviderConfig")
+      .field("id", &self.id)
+      .field("name", &self.name)
+      .field("issuer", &self.issuer)
+      .field("client_id", &self.client_id)
+      .field(
+        "client_secret",
+        &self.client_secret.as_ref().map(|_| "<redacted>"),
+      )
+      .field("scopes", &self.scopes)
+      .finish()
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhooksConfig {
+  // Allows http and private addresses for outgoing webhooks, for local receivers only
+  pub allow_insecure_urls: bool,
+  pub timeout_secs: u64,
+}
+
+impl Default for WebhooksConfig {
+  fn default() -> Self {
+    Self {
+      allow_insecure_urls: false,
+      timeout_secs: 10,
+    }
+  }
+}
+
+// Where state shared between nodes lives, memory only works for a single node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+  #[default]
+  Memory,
+  Postgres,
+}
+
+impl FromStr for Backend {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "memory" => Ok(Backend::Memory),
+      "postgres" => Ok(Backend::Postgres),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PubSubConfig {
+  pub backend: Backend,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+  pub backend: Backend,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushTransportKind {
+  #[default]
+  Http,
+  Log,
+}
+
+impl FromStr for PushTransportKind {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "http" => Ok(PushTransportKind::Http),
+      "log" => Ok(PushTransportKind::Log),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PushConfig {
+  // Push notifications are disabled unless a VAPID key is set
+  pub vapid_private_key: Option<String>,
+  pub vapid_subject: Option<String>,
+  pub transport: PushTransportKind,
+  // Accepts plain http and local endpoints, for a local mock push service only
+  pub allow_insecure_endpoints: bool,
+}
+
+impl std::fmt::Debug for PushConfig {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PushConfig")
+      .field(
+        "vapid_private_key",
+        &self.vapid_private_key.as_ref().map(|_| "<redacted>"),
+      )
+      .field("vapid_subject", &self.vapid_subject)
+      .field("transport", &self.transport)
+      .field("allow_insecure_endpoints", &self.allow_insecure_endpoints)
+      .finish()
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransportKind {
+  #[default]
+  Log,
+  File,
+  Smtp,
+}
+
+impl FromStr for MailTransportKind {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "log" => Ok(MailTransportKind::Log),
+      "file" => Ok(MailTransportKind::File),
+      "smtp" => Ok(MailTransportKind::Smtp),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+  #[default]
+  None,
+  Tls,
+  Starttls,
+}
+
+impl FromStr for SmtpTls {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "none" => Ok(SmtpTls::None),
+      "tls" => Ok(SmtpTls::Tls),
+      "starttls" => Ok(SmtpTls::Starttls),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpConfig {
+  pub host: String,
+  // The transport's default for the TLS mode when unset
+  pub port: Option<u16>,
+  pub tls: SmtpTls,
+  pub username: Option<String>,
+  pub password: Option<String>,
+}
+
+impl Default for SmtpConfig {
+  fn default() -> Self {
+    Self {
+      host: "localhost".to_string(),
+      port: None,
+      tls: SmtpTls::None,
+      username: None,
+      password: None,
+    }
+  }
+}
+
+impl std::fmt::Debug for SmtpConfig {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SmtpConfig")
+      .field("host", &self.host)
+      .field("port", &self.port)
+      .field("tls", &self.tls)
+      .field("username", &self.username)
+      .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+      .finish()
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+  pub transport: MailTransportKind,
+  pub from: String,
+  // Frontend URL that emailed links point to
+  pub app_url: String,
+  // Where the file transport writes .eml files
+  pub dir: String,
+  pub smtp: SmtpConfig,
+}
+
+impl Default for MailConfig {
+  fn default() -> Self {
+    Self {
+      transport: MailTransportKind::Log,
+      from: "Harmony <no-reply@localhost>".to_string(),
+      app_url: "http://localhost:5173".to_string(),
+      dir: "mail".to_string(),
+      smtp: SmtpConfig::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcConfig {
+  // Frontend page providers send the user back to, it posts the code to the API
+  pub redirect_uri: String,
+  pub providers: Vec<OidcProviderConfig>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub server: ServerConfig,
+  pub database: DatabaseConfig,
+  pub jwt: JwtConfig,
+  pub cors: CorsConfig,
+  pub limits: LimitsConfig,
+  pub oidc: OidcConfig,
+  pub webhooks: WebhooksConfig,
+  pub pubsub: PubSubConfig,
+  pub rate_limit: RateLimitConfig,
+  pub push: PushConfig,
+  pub mail: MailConfig,
+}
+
+impl Config {
+  // Defaults, then the TOML file from CONFIG_FILE if set, then environment variables
+  pub fn load() -> Result<Self, ConfigError> {
+    let mut config = match env::var("CONFIG_FILE") {
+      Ok(path) if !path.is_empty() => Self::from_file(Path::new(&path))?,
+      _ => Self::default(),
+    };
+
+    config.apply_env()?;
+    config.validate()?;
+    config.jwt.keys = JwtKeys::load(&config.jwt)?;
+
+    Ok(config)
+  }
+
+  fn from_file(path: &Path) -> Result<Self, ConfigError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+      path: path.display().to_string(),
+      source,
+    })?;
+
+    toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+      path: path.display().to_string(),
+      source,
+    })
+  }
+
+  fn apply_env(&mut self) -> Result<(), ConfigError> {
+    override_from_env("HOST", &mut self.server.host)?;
+    override_from_env("PORT", &mut self.server.port)?;
+    override_from_env("TRUST_PROXY", &mut self.server.trust_proxy)?;
+    override_from_env("DATABASE_URL", &mut self.database.url)?;
+    override_from_env(
+      "DATABASE_MAX_CONNECTIONS",
+      &mut self.database.max_connections,
+    )?;
+    override_from_env(
+      "DATABASE_MIN_CONNECTIONS",
+      &mut self.database.min_connections,
+    )?;
+    override_from_env(
+      "DATABASE_ACQUIRE_TIMEOUT",
+      &mut self.database.acquire_timeout_secs,
+    )?;
+    override_from_env("JWT_SECRET", &mut self.jwt.secret)?;
+    override_from_env("TOKEN_HASH_SECRET", &mut self.jwt.token_hash_secret)?;
+    override_from_env("JWT_EXPIRATION", &mut self.jwt.expiration_secs)?;
+    override_from_env("JWT_ISSUER", &mut self.jwt.issuer)?;
+    override_from_env("JWT_AUDIENCE", &mut self.jwt.audience)?;
+    override_from_env("JWT_KEY_ID", &mut self.jwt.key_id)?;
+    if let Ok(path) = env::var("JWT_PRIVATE_KEY_FILE")
+      && !path.is_empty()
+    {
+      self.jwt.private_key_file = Some(path);
+    }
+    // Comma separated key_id=path pairs
+    if let Ok(keys) = env::var("JWT_VERIFICATION_KEYS") {
+      self.jwt.verification_keys = keys
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+          entry
+            .split_once('=')
+            .map(|(key_id, path)| VerificationKeyConfig {
+              key_id: key_id.trim().to_string(),
+              public_key_file: path.trim().to_string(),
+            })
+            .ok_or_else(|| ConfigError::InvalidEnv {
+              name: "JWT_VERIFICATION_KEYS",
+              value: keys.clone(),
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    }
+    override_from_env("MAX_MESSAGE_LENGTH", &mut self.limits.max_message_length)?;
+    override_from_env("MAX_BODY_BYTES", &mut self.limits.max_body_bytes)?;
+    override_from_env("MAX_EMOJI_BYTES", &mut self.limits.max_emoji_bytes)?;
+    override_from_env(
+      "MAX_EMOJIS_PER_SERVER",
+      &mut self.limits.max_emojis_per_server,
+    )?;
+
+    override_from_env(
+      "WEBHOOKS_ALLOW_INSECURE_URLS",
+      &mut self.webhooks.allow_insecure_urls,
+    )?;
+    override_from_env("WEBHOOKS_TIMEOUT", &mut self.webhooks.timeout_secs)?;
+
+    override_from_env("PUBSUB_BACKEND", &mut self.pubsub.backend)?;
+    override_from_env("RATE_LIMIT_BACKEND", &mut self.rate_limit.backend)?;
+
+    override_optional_from_env("VAPID_PRIVATE_KEY", &mut self.push.vapid_private_key);
+    override_optional_from_env("VAPID_SUBJECT", &mut self.push.vapid_subject);
+    override_from_env("PUSH_TRANSPORT", &mut self.push.transport)?;
+    override_from_env(
+      "PUSH_ALLOW_INSECURE_ENDPOINTS",
+      &mut self.push.allow_insecure_endpoints,
+    )?;
+
+    override_from_env("MAIL_TRANSPORT", &mut self.mail.transport)?;
+    override_from_env("MAIL_FROM", &mut self.mail.from)?;
+    override_from_env("APP_URL", &mut self.mail.app_url)?;
+    override_from_env("MAIL_DIR", &mut self.mail.dir)?;
+    override_from_env("SMTP_HOST", &mut self.mail.smtp.host)?;
+    if let Ok(port) = env::var("SMTP_PORT")
+      && !port.is_empty()
+    {
+      self.mail.smtp.port = Some(port.parse().map_err(|_| ConfigError::InvalidEnv {
+        name: "SMTP_PORT",
+        value: port.clone(),
+      })?);
+    }
+    override_from_env("SMTP_TLS", &mut self.mail.smtp.tls)?;
+    override_optional_from_env("SMTP_USERNAME", &mut self.mail.smtp.username);
+    override_optional_from_env("SMTP_PASSWORD", &mut self.mail.smtp.password);
+
+    override_from_env("OIDC_REDIRECT_URI", &mut self.oidc.redirect_uri)?;
+    // Providers from the environment replace those in the file, each configured with
+    // OIDC_<ID>_ISSUER, OIDC_<ID>_CLIENT_ID and optionally _CLIENT_SECRET, _NAME and _SCOPES
+    if let Ok(ids) = env::var("OIDC_PROVIDERS") {
+      self.oidc.providers = ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(oidc_provider_from_env)
+        .collect();
+    }
+
+    if let Ok(origins) = env::var("ALLOWED_ORIGINS") {
+      self.cors.allowed_origins = origins
+        .split(',')
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect();
+    }
+
+    Ok(())
+  }
+
+  fn validate(&self) -> Result<(), ConfigError> {
+    if self.database.url.is_empty() {
+      return Err(ConfigError::Invalid(
+        "DATABASE_URL (database.url) must be set".to_string(),
+      ));
+    }
+    if self.database.max_connections == 0 {
+      return Err(ConfigError::Invalid(
+        "database.max_connections must be at least 1".to_string(),
+      ));
+    }
+    if self.database.min_connections > self.database.max_connections {
+      return Err(ConfigError::Invalid(
+        "database.min_connections cannot exceed database.max_connections".to_string(),
+      ));
+    }
+    if self.jwt.secret.len() < MIN_JWT_SECRET_LENGTH {
+      return Err(ConfigError::Invalid(format!(
+        "JWT_SECRET (jwt.secret) must be at least {} characters",
+        MIN_JWT_SECRET_LENGTH
+      )));
+    }
+    if self.jwt.token_hash_secret.len() < MIN_JWT_SECRET_LENGTH {
+      return Err(ConfigError::Invalid(format!(
+        "TOKEN_HASH_SECRET (jwt.token_hash_secret) must be at least {} characters",
+        MIN_JWT_SECRET_LENGTH
+      )));
+    }
+    if self.jwt.token_hash_secret == self.jwt.secret {
+      return Err(ConfigError::Invalid(
+        "TOKEN_HASH_SECRET (jwt.token_hash_secret) must differ from JWT_SECRET".to_string(),
+      ));
+    }
+    if self.jwt.issuer.is_empty() || self.jwt.audience.is_empty() {
+      return Err(ConfigError::Invalid(
+        "jwt.issuer and jwt.audience cannot be empty".to_string(),
+      ));
+    }
+    if self.jwt.key_id.is_empty() {
+      return Err(ConfigError::Invalid(
+        "jwt.key_id cannot be empty".to_string(),
+      ));
+    }
+    if self.jwt.expiration_secs <= 0 {
+      return Err(ConfigError::Invalid(
+        "jwt.expiration_secs must be positive".to_string(),
+      ));
+    }
+    if self.limits.max_message_length == 0 {
+      return Err(ConfigError::Invalid(
+        "limits.max_message_length must be at least 1".to_string(),
+      ));
+    }
+    if self.webhooks.timeout_secs == 0 {
+      return Err(ConfigError::Invalid(
+        "webhooks.timeout_secs must be at least 1".to_string(),
+      ));
+    }
+    self.cors.origins()?;
+
+    if self.push.vapid_private_key.is_some() && self.push.vapid_subject.is_none() {
+      return Err(ConfigError::Invalid(
+        "VAPID_SUBJECT (push.vapid_subject) must be set when a VAPID key is".to_string(),
+      ));
+    }
+    if self.mail.from.parse::<lettre::message::Mailbox>().is_err() {
+      return Err(ConfigError::Invalid(format!(
+        "MAIL_FROM (mail.from) '{}' is not a valid address",
+        self.mail.from
+      )));
+    }
+    if self.mail.app_url.is_empty() {
+      return Err(ConfigError::Invalid(
+        "APP_URL (mail.app_url) cannot be empty".to_string(),
+      ));
+    }
+    if self.mail.smtp.username.is_some() != self.mail.smtp.password.is_some() {
+      return Err(ConfigError::Invalid(
+        "SMTP_USERNAME and SMTP_PASSWORD (mail.smtp) must be set together".to_string(),
+      ));
+    }
+
+    if !self.oidc.providers.is_empty() && self.oidc.redirect_uri.is_empty() {
+      return Err(ConfigError::Invalid(
+        "OIDC_REDIRECT_URI (oidc.redirect_uri) must be set when OIDC providers are configured"
+          .to_string(),
+      ));
+    }
+    for (i, provider) in self.oidc.providers.iter().enumerate() {
+      if provider.id.is_empty()
+        || !provider
+          .id
+          .chars()
+          .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+      {
+        return Err(ConfigError::Invalid(format!(
+          "OIDC provider id '{}' must be lowercase letters, digits or dashes",
+          provider.id
+        )));
+      }
+      if self.oidc.providers[..i].iter().any(|p| p.id == provider.id) {
+        return Err(ConfigError::Invalid(format!(
+          "OIDC provider '{}' is configured more than once",
+          provider.id
+        )));
+      }
+      if provider.issuer.is_empty() || provider.client_id.is_empty() {
+        return Err(ConfigError::Invalid(format!(
+          "OIDC provider '{}' needs an issuer and client_id",
+          provider.id
+        )));
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn bind_address(&self) -> SocketAddr {
+    SocketAddr::new(self.server.host, self.server.port)
+  }
+}
+
+impl DatabaseConfig {
+  pub fn acquire_timeout(&self) -> Duration {
+    Duration::from_secs(self.acquire_timeout_secs)
+  }
+}
+
+impl CorsConfig {
+  pub fn origins(&self) -> Result<Vec<HeaderValue>, ConfigError> {
+    self
+      .allowed_origins
+      .iter()
+      .map(|origin| {
+        HeaderValue::from_str(origin)
+          .ok()
+          .filter(|_| origin.starts_with("http://") || origin.starts_with("https://"))
+          .ok_or_else(|| ConfigError::Invalid(format!("Invalid CORS origin '{}'", origin)))
+      })
+      .collect()
+  }
+}
+
+fn oidc_provider_from_env(id: &str) -> OidcProviderConfig {
+  let prefix = format!("OIDC_{}", id.to_uppercase().replace('-', "_"));
+  let var = |suffix: &str| {
+    env::var(format!("{}_{}", prefix, suffix))
+      .ok()
+      .filter(|value| !value.is_empty())
+  };
+
+  OidcProviderConfig {
+    id: id.to_string(),
+    name: var("NAME").unwrap_or_else(|| id.to_string()),
+    issuer: var("ISSUER").unwrap_or_default(),
+    client_id: var("CLIENT_ID").unwrap_or_default(),
+    client_secret: var("CLIENT_SECRET"),
+    scopes: var("SCOPES")
+      .map(|scopes| scopes.split_whitespace().map(String::from).collect())
+      .unwrap_or_else(default_oidc_scopes),
+  }
+}
+
+fn override_optional_from_env(name: &'static str, target: &mut Option<String>) {
+  if let Ok(value) = env::var(name)
+    && !value.is_empty()
+  {
+    *target = Some(value);
+  }
+}
+
+fn override_from_env<T: FromStr>(name: &'static str, target: &mut T) -> Result<(), ConfigError> {
+  match env::var(name) {
+    Ok(value) if !value.is_empty() => {
+      *target = value
+        .parse()
+        .map_err(|_| ConfigError::InvalidEnv { name, value })?;
+      Ok(())
+    }
+    _ => Ok(()),
+  }
+}
@@ -0,0 +1,127 @@
+// backend/src/handlers/account.rs
+use crate::AppState;
+use crate::handlers::auth::{AuthResponse, send_verification_email};
+use crate::handlers::dm::announce_group_dm_departure;
+use crate::handlers::mfa::mfa_code;
+use crate::handlers::outgoing_webhook::{dispatch_member_left, member_departure};
+use crate::middleware::CurrentUser;
+use crate::models::{
+  ChangePasswordRequest, DeleteAccountRequest, UpdateAccountRequest, UserResponse,
+};
+use crate::services::{
+  AccountService, ApplicationService, AuthService, ChannelService, MfaService,
+};
+use crate::utils::AppResult;
+use crate::ws::WsMessage;
+use axum::{Extension, Json, extract::State, http::HeaderMap};
+use uuid::Uuid;
+
+pub async fn update_account(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Json(req): Json<UpdateAccountRequest>,
+) -> AppResult<Json<UserResponse>> {
+  let (account, email_changed) =
+    AccountService::update_account(&state.db, &state.config.jwt, user.id, req).await?;
+
+  if email_changed {
+    send_verification_email(&state, &account).await;
+  }
+
+  Ok(Json(account.into()))
+}
+
+// Other sessions are signed out, the caller gets a fresh token
+pub async fn change_password(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  headers: HeaderMap,
+  Json(req): Json<ChangePasswordRequest>,
+) -> AppResult<Json<AuthResponse>> {
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
+
+  let account = AccountService::change_password(&state.db, &state.config.jwt, user.id, req).await?;
+  let token = AuthService::generate_token(&state.config.jwt, &account)?;
+
+  invalidate_sessions(&state, user.id).await;
+
+  Ok(Json(AuthResponse {
+    user: account.into(),
+    token,
+  }))
+}
+
+pub async fn delete_account(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  headers: HeaderMap,
+  Json(req): Json<DeleteAccountRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+  // Checked first, verifying consumes a reauthentication token
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
+  AccountService::verify_current_password(
+    &state.db,
+    &state.config.jwt,
+    user.id,
+    req.password.as_deref(),
+    req.reauthentication_token.as_deref(),
+  )
+  .await?;
+
+  // Gathered up front, events are only sent once the deletion has committed
+  let mut group_dms = Vec::new();
+  for channel_id in AccountService::get_group_dm_channel_ids(&state.db, user.id).await? {
+    group_dms.push(ChannelService::get_dm_channel(&state.db, channel_id).await?);
+  }
+
+  let mut departures = Vec::new();
+  for application in ApplicationService::get_applications(&state.db, user.id).await? {
+    departures.push(member_departure(&state, application.bot_user_id).await?);
+  }
+  departures.push(member_departure(&state, user.id).await?);
+
+  let deletion = AccountService::delete_account(&state.db, user.id).await?;
+
+  for (channel_id, remains) in deletion.group_dms {
+    let Some(previous) = group_dms.iter().find(|dm| dm.channel_id == channel_id) else {
+      continue;
+    };
+
+    let dm_channel = if remains {
+      match ChannelService::get_dm_channel(&state.db, channel_id).await {
+        Ok(dm_channel) => Some(dm_channel),
+        Err(e) => {
+          tracing::error!("Failed to load group DM after account deletion: {}", e);
+          continue;
+        }
+      }
+    } else {
+      None
+    };
+
+    announce_group_dm_departure(&state, previous, dm_channel, user.id).await;
+  }
+
+  for bot_user_id in deletion.bot_user_ids {
+    invalidate_sessions(&state, bot_user_id).await;
+  }
+  invalidate_sessions(&state, user.id).await;
+
+  for departure in departures {
+    dispatch_member_left(&state, departure).await;
+  }
+
+  Ok(Json(
+    serde_json::json!({"message": "Account deleted successfully"}),
+  ))
+}
+
+pub async fn invalidate_sessions(state: &AppState, user_id: Uuid) {
+  if let Err(e) = state
+    .connections
+    .send_to_user(user_id, WsMessage::SessionInvalidated)
+    .await
+  {
+    tracing::error!("Failed to close sessions: {}", e);
+  }
+}
@@ -0,0 +1,170 @@
+// backend/src/handlers/application.rs
+use crate::AppState;
+use crate::handlers::account::invalidate_sessions;
+use crate::handlers::audit_log::audit_reason;
+use crate::handlers::mfa::mfa_code;
+use crate::handlers::outgoing_webhook::{dispatch_event, dispatch_member_left, member_departure};
+use crate::middleware::CurrentUser;
+use crate::models::{
+  Application, ApplicationWithTokenResponse, AuthorizeBotRequest, BotTokenResponse,
+  CreateApplicationRequest, InteractionsEndpointResponse, OutgoingWebhookEvent, ServerResponse,
+  SetInteractionsEndpointRequest, UpdateApplicationRequest,
+};
+use crate::services::{ApplicationService, MfaService, OutgoingWebhookService};
+use crate::utils::AppResult;
+use crate::ws::WsMessage;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+  http::HeaderMap,
+};
+use uuid::Uuid;
+
+pub async fn create_application(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Json(req): Json<CreateApplicationRequest>,
+) -> AppResult<Json<ApplicationWithTokenResponse>> {
+  let (application, token) =
+    ApplicationService::create_application(&state.db, &state.config.jwt, user.id, req).await?;
+  Ok(Json(ApplicationWithTokenResponse { application, token }))
+}
+
+pub async fn get_applications(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+) -> AppResult<Json<Vec<Application>>> {
+  let applications = ApplicationService::get_applications(&state.db, user.id).await?;
+  Ok(Json(applications))
+}
+
+pub async fn get_application(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+) -> AppResult<Json<Application>> {
+  let application =
+    ApplicationService::get_owned_application(&state.db, application_id, user.id).await?;
+  Ok(Json(application))
+}
+
+pub async fn update_application(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+  Json(req): Json<UpdateApplicationRequest>,
+) -> AppResult<Json<Application>> {
+  let application =
+    ApplicationService::update_application(&state.db, application_id, user.id, req).await?;
+  Ok(Json(application))
+}
+
+pub async fn delete_application(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
+
+  let application =
+    ApplicationService::get_owned_application(&state.db, application_id, user.id).await?;
+  let departure = member_departure(&state, application.bot_user_id).await?;
+
+  ApplicationService::delete_application(&state.db, application_id, user.id).await?;
+  invalidate_sessions(&state, application.bot_user_id).await;
+  dispatch_member_left(&state, departure).await;
+
+  Ok(Json(
+    serde_json::json!({"message": "Application deleted successfully"}),
+  ))
+}
+
+pub async fn set_interactions_endpoint(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+  Json(req): Json<SetInteractionsEndpointRequest>,
+) -> AppResult<Json<InteractionsEndpointResponse>> {
+  let (url, secret) = ApplicationService::set_interactions_endpoint(
+    &state.db,
+    &state.outgoing_webhooks,
+    application_id,
+    user.id,
+    &req.url,
+  )
+  .await?;
+  Ok(Json(InteractionsEndpointResponse { url, secret }))
+}
+
+pub async fn clear_interactions_endpoint(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+  ApplicationService::clear_interactions_endpoint(&state.db, application_id, user.id).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Interactions endpoint removed"}),
+  ))
+}
+
+// Connected gateway sessions of the bot are closed along with the old token
+pub async fn reset_bot_token(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+  headers: HeaderMap,
+) -> AppResult<Json<BotTokenResponse>> {
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
+
+  let (application, token) =
+    ApplicationService::reset_bot_token(&state.db, &state.config.jwt, application_id, user.id)
+      .await?;
+  invalidate_sessions(&state, application.bot_user_id).await;
+
+  Ok(Json(BotTokenResponse { token }))
+}
+
+pub async fn authorize_bot(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+  headers: HeaderMap,
+  Json(req): Json<AuthorizeBotRequest>,
+) -> AppResult<Json<ServerResponse>> {
+  let reason = audit_reason(&headers)?;
+  let (application, server) = ApplicationService::authorize_bot(
+    &state.db,
+    application_id,
+    user.id,
+    req.server_id,
+    reason.as_deref(),
+  )
+  .await?;
+
+  let ws_message = WsMessage::ServerJoined {
+    server: server.to_response(application.bot_user_id),
+  };
+  if let Err(e) = state
+    .connections
+    .send_to_user(application.bot_user_id, ws_message)
+    .await
+  {
+    tracing::error!("Failed to send server joined event: {}", e);
+  }
+
+  match OutgoingWebhookService::get_member(&state.db, application.bot_user_id).await {
+    Ok(member) => {
+      dispatch_event(
+        &state,
+        server.id,
+        OutgoingWebhookEvent::MemberJoined,
+        &member,
+      )
+      .await
+    }
+    Err(e) => tracing::error!("Failed to load bot for webhook event: {}", e),
+  }
+
+  Ok(Json(server.to_response(user.id)))
+}
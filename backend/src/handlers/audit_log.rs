@@ -0,0 +1,40 @@
+// backend/src/handlers/audit_log.rs
+use crate::AppState;
+use crate::middleware::CurrentUser;
+use crate::models::{AuditLogEntry, AuditLogQuery, PaginatedResponse};
+use crate::services::AuditLogService;
+use crate::utils::AppResult;
+use axum::{
+  Extension, Json,
+  extract::{Path, Query, State},
+  http::HeaderMap,
+};
+use uuid::Uuid;
+
+// Administrative actions can explain themselves in this header, it ends up in the audit log
+pub const AUDIT_LOG_REASON_HEADER: &str = "x-audit-log-reason";
+
+// A blank reason is the same as none
+pub fn audit_reason(headers: &HeaderMap) -> AppResult<Option<String>> {
+  let Some(value) = headers.get(AUDIT_LOG_REASON_HEADER) else {
+    return Ok(None);
+  };
+
+  let reason = String::from_utf8_lossy(value.as_bytes()).trim().to_string();
+  if reason.is_empty() {
+    return Ok(None);
+  }
+  AuditLogService::validate_reason(&reason)?;
+
+  Ok(Some(reason))
+}
+
+pub async fn get_audit_log(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+  Query(query): Query<AuditLogQuery>,
+) -> AppResult<Json<PaginatedResponse<AuditLogEntry>>> {
+  let entries = AuditLogService::get_entries(&state.db, server_id, user.id, query).await?;
+  Ok(Json(entries))
+}
@@ -1,11 +1,17 @@
-use axum::{Json, extract::State};
+use axum::{Json, extract::State, http::header, response::IntoResponse};
+use chrono::Duration;
 use serde::Serialize;
 
 use crate::{
   AppState,
-  models::{CreateUserRequest, LoginRequest, UserResponse},
-  services::AuthService,
+  middleware::ClientIp,
+  models::{
+    CreateUserRequest, ForgotPasswordRequest, LoginRequest, MfaLoginRequest, ResetPasswordRequest,
+    User, UserResponse, UserTokenKind, VerifyEmailRequest,
+  },
+  services::{AuthService, MfaService},
   utils::AppResult,
+  ws::WsMessage,
 };
 
 #[derive(Serialize)]
@@ -14,6 +20,14 @@ pub struct AuthResponse {
   pub token: String,
 }
 
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+  Authenticated(AuthResponse),
+  // Exchanged for a token at /api/auth/login/mfa along with a two-factor code
+  MfaRequired { mfa_required: bool, ticket: String },
+}
+
 pub async fn register(
   State(state): State<AppState>,
   Json(req): Json<CreateUserRequest>,
@@ -21,7 +35,9 @@ pub async fn register(
   tracing::info!("Registering new user: {}", req.email);
 
   let user = AuthService::create_user(&state.db, req).await?;
-  let token = AuthService::generate_token(user.id)?;
+  let token = AuthService::generate_token(&state.config.jwt, &user)?;
+
+  send_verification_email(&state, &user).await;
 
   Ok(Json(AuthResponse {
     user: user.into(),
@@ -31,19 +47,152 @@ pub async fn register(
 
 pub async fn login(
   State(state): State<AppState>,
+  ClientIp(ip): ClientIp,
   Json(req): Json<LoginRequest>,
-) -> AppResult<Json<AuthResponse>> {
+) -> AppResult<Json<LoginResponse>> {
   if let Some(email) = &req.email {
     tracing::info!("User login attempt: {}", email);
   } else if let Some(username) = &req.username {
     tracing::info!("User login attempt: {}", username);
   }
 
-  let user = AuthService::authenticate_user(&state.db, req).await?;
-  let token = AuthService::generate_token(user.id)?;
+  let user = AuthService::authenticate_user(&state.db, req, ip).await?;
+
+  if user.totp_enabled_at.is_some() {
+    let ticket = MfaService::create_login_ticket(&state.db, &state.config.jwt, user.id).await?;
+    return Ok(Json(LoginResponse::MfaRequired {
+      mfa_required: true,
+      ticket,
+    }));
+  }
+
+  let token = AuthService::generate_token(&state.config.jwt, &user)?;
+
+  Ok(Json(LoginResponse::Authenticated(AuthResponse {
+    user: user.into(),
+    token,
+  })))
+}
+
+pub async fn login_mfa(
+  State(state): State<AppState>,
+  ClientIp(ip): ClientIp,
+  Json(req): Json<MfaLoginRequest>,
+) -> AppResult<Json<AuthResponse>> {
+  let user =
+    MfaService::complete_login(&state.db, &state.config.jwt, &req.ticket, &req.code, ip).await?;
+  let token = AuthService::generate_token(&state.config.jwt, &user)?;
 
   Ok(Json(AuthResponse {
     user: user.into(),
     token,
   }))
 }
+
+pub async fn verify_email(
+  State(state): State<AppState>,
+  Json(req): Json<VerifyEmailRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+  AuthService::verify_email(&state.db, &state.config.jwt, &req.token).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Email verified successfully"}),
+  ))
+}
+
+pub async fn forgot_password(
+  State(state): State<AppState>,
+  Json(req): Json<ForgotPasswordRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+  // Looked up after responding so timing does not reveal whether the address has an account
+  tokio::spawn(async move {
+    if let Err(e) = send_password_reset_email(&state, &req.email).await {
+      tracing::error!("Failed to issue password reset: {}", e);
+    }
+  });
+
+  Ok(Json(serde_json::json!({
+    "message": "If an account exists for that email, a reset link has been sent"
+  })))
+}
+
+async fn send_password_reset_email(state: &AppState, email: &str) -> AppResult<()> {
+  let Some(user) = AuthService::get_user_by_email(&state.db, email).await? else {
+    return Ok(());
+  };
+
+  let token = AuthService::issue_user_token(
+    &state.db,
+    &state.config.jwt,
+    user.id,
+    UserTokenKind::PasswordReset,
+    Duration::hours(1),
+  )
+  .await?;
+
+  let body = format!(
+    "Hi {},\n\nUse the link below to choose a new password. It expires in one hour.\n\n{}\n\nIf you did not ask for a password reset you can ignore this email.\n",
+    user.username,
+    state.mailer.link("/reset-password", &token)
+  );
+  state
+    .mailer
+    .send_in_background(user.email, "Reset your Harmony password", body);
+
+  Ok(())
+}
+
+pub async fn reset_password(
+  State(state): State<AppState>,
+  Json(req): Json<ResetPasswordRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+  let user_id =
+    AuthService::reset_password(&state.db, &state.config.jwt, &req.token, &req.password).await?;
+
+  if let Err(e) = state
+    .connections
+    .send_to_user(user_id, WsMessage::SessionInvalidated)
+    .await
+  {
+    tracing::error!("Failed to close sessions: {}", e);
+  }
+
+  Ok(Json(
+    serde_json::json!({"message": "Password reset successfully"}),
+  ))
+}
+
+// Called once the account change is committed, so a failure here must not fail the request
+pub async fn send_verification_email(state: &AppState, user: &User) {
+  let token = match AuthService::issue_user_token(
+    &state.db,
+    &state.config.jwt,
+    user.id,
+    UserTokenKind::EmailVerification,
+    Duration::hours(24),
+  )
+  .await
+  {
+    Ok(token) => token,
+    Err(e) => {
+      tracing::error!("Failed to issue verification token for {}: {}", user.id, e);
+      return;
+    }
+  };
+
+  let body = format!(
+    "Hi {},\n\nConfirm your email address by opening the link below. It expires in 24 hours.\n\n{}\n",
+    user.username,
+    state.mailer.link("/verify-email", &token)
+  );
+  state
+    .mailer
+    .send_in_background(user.email.clone(), "Verify your Harmony email", body);
+}
+
+// Public keys for services that validate our tokens, cacheable so they can poll during rotation
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+  (
+    [(header::CACHE_CONTROL, "public, max-age=300")],
+    Json(state.config.jwt.keys.jwks.as_ref().clone()),
+  )
+}
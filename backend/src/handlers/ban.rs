@@ -0,0 +1,104 @@
+// backend/src/handlers/ban.rs
+use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
+use crate::handlers::message::post_system_message;
+use crate::handlers::outgoing_webhook::dispatch_event;
+use crate::middleware::CurrentUser;
+use crate::models::{OutgoingWebhookEvent, ServerBan, SystemMessageData, SystemMessageUser};
+use crate::services::{BanService, ChannelService, OutgoingWebhookService, ServerService};
+use crate::utils::AppResult;
+use crate::ws::WsMessage;
+use crate::ws::voice::broadcast_voice_state;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+  http::HeaderMap,
+};
+use uuid::Uuid;
+
+pub async fn get_bans(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+) -> AppResult<Json<Vec<ServerBan>>> {
+  let bans = BanService::get_bans(&state.db, server_id, user.id).await?;
+  Ok(Json(bans))
+}
+
+// The audit log reason is also kept as the ban's reason
+pub async fn ban_member(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((server_id, member_id)): Path<(Uuid, Uuid)>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  let reason = audit_reason(&headers)?;
+  let (was_member, voice_state) =
+    BanService::ban(&state.db, server_id, member_id, user.id, reason.as_deref()).await?;
+
+  if let Some(voice_state) = voice_state {
+    broadcast_voice_state(&state.db, &state.connections, voice_state).await;
+  }
+
+  if was_member {
+    let ws_message = WsMessage::ServerLeft { server_id };
+    if let Err(e) = state.connections.send_to_user(member_id, ws_message).await {
+      tracing::error!("Failed to send server left event: {}", e);
+    }
+
+    // Sockets keep their channel subscriptions until told otherwise
+    match ChannelService::get_server_channels(&state.db, server_id).await {
+      Ok(channels) => {
+        for channel in channels {
+          if let Err(e) = state
+            .connections
+            .unsubscribe_user(member_id, channel.id)
+            .await
+          {
+            tracing::error!("Failed to unsubscribe banned user: {}", e);
+          }
+        }
+      }
+      Err(e) => tracing::error!("Failed to load channels for banned user: {}", e),
+    }
+
+    match OutgoingWebhookService::get_member(&state.db, member_id).await {
+      Ok(member) => {
+        match ServerService::get_server_by_id(&state.db, server_id).await {
+          Ok(server) => {
+            if let Some(channel_id) = server.main_channel_id {
+              let data = SystemMessageData::MemberLeave {
+                user: SystemMessageUser {
+                  user_id: member.user_id,
+                  username: member.username.clone(),
+                },
+              };
+              post_system_message(&state, channel_id, data).await;
+            }
+          }
+          Err(e) => tracing::error!("Failed to load server for leave message: {}", e),
+        }
+
+        dispatch_event(&state, server_id, OutgoingWebhookEvent::MemberLeft, &member).await
+      }
+      Err(e) => tracing::error!("Failed to load member for leave events: {}", e),
+    }
+  }
+
+  Ok(Json(
+    serde_json::json!({"message": "User banned successfully"}),
+  ))
+}
+
+pub async fn unban_member(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((server_id, member_id)): Path<(Uuid, Uuid)>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  let reason = audit_reason(&headers)?;
+  BanService::unban(&state.db, server_id, member_id, user.id, reason.as_deref()).await?;
+  Ok(Json(
+    serde_json::json!({"message": "User unbanned successfully"}),
+  ))
+}
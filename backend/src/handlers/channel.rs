@@ -1,11 +1,13 @@
 use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
 use crate::middleware::CurrentUser;
-use crate::models::{ChannelResponse, CreateChannelRequest};
+use crate::models::{ChannelResponse, CreateChannelRequest, UpdateChannelRequest};
 use crate::services::{ChannelService, ServerService};
 use crate::utils::AppResult;
 use axum::{
   Extension, Json,
   extract::{Path, State},
+  http::HeaderMap,
 };
 use uuid::Uuid;
 
@@ -13,9 +15,12 @@ pub async fn create_channel(
   State(state): State<AppState>,
   Extension(user): Extension<CurrentUser>,
   Path(server_id): Path<Uuid>,
+  headers: HeaderMap,
   Json(req): Json<CreateChannelRequest>,
 ) -> AppResult<Json<ChannelResponse>> {
-  let channel = ChannelService::create_channel(&state.db, server_id, user.id, req).await?;
+  let reason = audit_reason(&headers)?;
+  let channel =
+    ChannelService::create_channel(&state.db, server_id, user.id, req, reason.as_deref()).await?;
   Ok(Json(channel.to_response()))
 }
 
@@ -26,7 +31,8 @@ pub async fn get_server_channels(
 ) -> AppResult<Json<Vec<ChannelResponse>>> {
   // Check if user is a member
   if !ServerService::is_member(&state.db, server_id, user.id).await? {
-    return Err(crate::utils::AppError::Unauthorized(
+    return Err(crate::utils::AppError::Forbidden(
+      crate::utils::ErrorCode::MissingAccess,
       "You are not a member of this server".to_string(),
     ));
   }
@@ -36,12 +42,27 @@ pub async fn get_server_channels(
   Ok(Json(responses))
 }
 
+pub async fn update_channel(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(channel_id): Path<Uuid>,
+  headers: HeaderMap,
+  Json(req): Json<UpdateChannelRequest>,
+) -> AppResult<Json<ChannelResponse>> {
+  let reason = audit_reason(&headers)?;
+  let channel =
+    ChannelService::update_channel(&state.db, channel_id, user.id, req, reason.as_deref()).await?;
+  Ok(Json(channel.to_response()))
+}
+
 pub async fn delete_channel(
   State(state): State<AppState>,
   Extension(user): Extension<CurrentUser>,
   Path(channel_id): Path<Uuid>,
+  headers: HeaderMap,
 ) -> AppResult<Json<serde_json::Value>> {
-  ChannelService::delete_channel(&state.db, channel_id, user.id).await?;
+  let reason = audit_reason(&headers)?;
+  ChannelService::delete_channel(&state.db, channel_id, user.id, reason.as_deref()).await?;
   Ok(Json(
     serde_json::json!({"message": "Channel deleted successfully"}),
   ))
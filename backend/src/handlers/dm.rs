@@ -1,10 +1,19 @@
 // backend/src/handlers/dm.rs
 use crate::AppState;
+use crate::handlers::message::post_system_message;
 use crate::middleware::CurrentUser;
-use crate::models::{CreateDmRequest, CreateGroupDmRequest, DmChannelResponse};
+use crate::models::{
+  CreateDmRequest, CreateGroupDmRequest, DmChannelResponse, SystemMessageData, SystemMessageUser,
+  UpdateGroupDmRequest,
+};
 use crate::services::ChannelService;
 use crate::utils::AppResult;
-use axum::{Extension, Json, extract::State};
+use crate::ws::WsMessage;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+};
+use uuid::Uuid;
 
 pub async fn create_dm(
   State(state): State<AppState>,
@@ -12,6 +21,7 @@ pub async fn create_dm(
   Json(req): Json<CreateDmRequest>,
 ) -> AppResult<Json<DmChannelResponse>> {
   let dm_channel = ChannelService::create_dm_channel(&state.db, user.id, req).await?;
+  notify_dm_created(&state, &dm_channel).await;
   Ok(Json(dm_channel))
 }
 
@@ -21,6 +31,7 @@ pub async fn create_group_dm(
   Json(req): Json<CreateGroupDmRequest>,
 ) -> AppResult<Json<DmChannelResponse>> {
   let dm_channel = ChannelService::create_group_dm_channel(&state.db, user.id, req).await?;
+  notify_dm_created(&state, &dm_channel).await;
   Ok(Json(dm_channel))
 }
 
@@ -31,3 +42,264 @@ pub async fn get_user_dms(
   let dms = ChannelService::get_user_dm_channels(&state.db, user.id).await?;
   Ok(Json(dms))
 }
+
+async fn notify_dm_created(state: &AppState, dm_channel: &DmChannelResponse) {
+  let participant_ids = dm_channel.participants.iter().map(|p| p.user_id).collect();
+
+  let ws_message = WsMessage::DmCreated {
+    dm: dm_channel.clone(),
+  };
+
+  if let Err(e) = state
+    .connections
+    .send_to_users(participant_ids, ws_message)
+    .await
+  {
+    tracing::error!("Failed to send DM created event: {}", e);
+  }
+}
+
+pub async fn add_group_dm_recipient(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((channel_id, recipient_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<DmChannelResponse>> {
+  let dm_channel =
+    ChannelService::add_group_dm_recipient(&state.db, channel_id, user.id, recipient_id).await?;
+
+  let other_ids = dm_channel
+    .participants
+    .iter()
+    .map(|p| p.user_id)
+    .filter(|id| *id != recipient_id)
+    .collect();
+
+  if let Some(recipient) = dm_channel
+    .participants
+    .iter()
+    .find(|p| p.user_id == recipient_id)
+  {
+    send_dm_event(
+      &state,
+      other_ids,
+      WsMessage::DmRecipientAdded {
+        channel_id,
+        user: recipient.clone(),
+      },
+    )
+    .await;
+  }
+
+  send_dm_event(
+    &state,
+    vec![recipient_id],
+    WsMessage::DmCreated {
+      dm: dm_channel.clone(),
+    },
+  )
+  .await;
+
+  post_system_message(
+    &state,
+    channel_id,
+    SystemMessageData::RecipientAdd {
+      user: participant(&dm_channel, user.id),
+      recipient: participant(&dm_channel, recipient_id),
+    },
+  )
+  .await;
+
+  Ok(Json(dm_channel))
+}
+
+pub async fn remove_group_dm_recipient(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((channel_id, recipient_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<DmChannelResponse>> {
+  let previous = ChannelService::get_dm_channel(&state.db, channel_id).await?;
+  let dm_channel =
+    ChannelService::remove_group_dm_recipient(&state.db, channel_id, user.id, recipient_id).await?;
+
+  let mut user_ids: Vec<Uuid> = dm_channel.participants.iter().map(|p| p.user_id).collect();
+  user_ids.push(recipient_id);
+
+  send_dm_event(
+    &state,
+    user_ids,
+    WsMessage::DmRecipientRemoved {
+      channel_id,
+      user_id: recipient_id,
+    },
+  )
+  .await;
+  unsubscribe(&state, recipient_id, channel_id).await;
+
+  post_system_message(
+    &state,
+    channel_id,
+    SystemMessageData::RecipientRemove {
+      user: participant(&previous, user.id),
+      recipient: participant(&previous, recipient_id),
+    },
+  )
+  .await;
+
+  Ok(Json(dm_channel))
+}
+
+pub async fn update_group_dm(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(channel_id): Path<Uuid>,
+  Json(req): Json<UpdateGroupDmRequest>,
+) -> AppResult<Json<DmChannelResponse>> {
+  let previous = ChannelService::get_dm_channel(&state.db, channel_id).await?;
+  let dm_channel = ChannelService::update_group_dm(&state.db, channel_id, user.id, req).await?;
+
+  send_dm_event(
+    &state,
+    dm_channel.participants.iter().map(|p| p.user_id).collect(),
+    WsMessage::DmUpdated {
+      dm: dm_channel.clone(),
+    },
+  )
+  .await;
+
+  if dm_channel.name != previous.name {
+    post_system_message(
+      &state,
+      channel_id,
+      SystemMessageData::GroupRename {
+        user: participant(&dm_channel, user.id),
+        name: dm_channel.name.clone(),
+      },
+    )
+    .await;
+  }
+
+  if dm_channel.icon_url != previous.icon_url {
+    post_system_message(
+      &state,
+      channel_id,
+      SystemMessageData::GroupIconChange {
+        user: participant(&dm_channel, user.id),
+        icon_url: dm_channel.icon_url.clone(),
+      },
+    )
+    .await;
+  }
+
+  Ok(Json(dm_channel))
+}
+
+pub async fn leave_group_dm(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(channel_id): Path<Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+  remove_from_group_dm(&state, channel_id, user.id).await?;
+  Ok(Json(serde_json::json!({"message": "Left group DM"})))
+}
+
+// Removes a user from a group DM and tells the remaining participants
+pub async fn remove_from_group_dm(
+  state: &AppState,
+  channel_id: Uuid,
+  user_id: Uuid,
+) -> AppResult<()> {
+  let previous = ChannelService::get_dm_channel(&state.db, channel_id).await?;
+  let dm_channel = ChannelService::leave_group_dm(&state.db, channel_id, user_id).await?;
+  announce_group_dm_departure(state, &previous, dm_channel, user_id).await;
+
+  Ok(())
+}
+
+// Tells the group about a member who left, `previous` still lists them for their name
+pub async fn announce_group_dm_departure(
+  state: &AppState,
+  previous: &DmChannelResponse,
+  dm_channel: Option<DmChannelResponse>,
+  user_id: Uuid,
+) {
+  let channel_id = previous.channel_id;
+
+  let mut user_ids: Vec<Uuid> = dm_channel
+    .iter()
+    .flat_map(|dm| dm.participants.iter().map(|p| p.user_id))
+    .collect();
+  user_ids.push(user_id);
+
+  send_dm_event(
+    state,
+    user_ids,
+    WsMessage::DmRecipientRemoved {
+      channel_id,
+      user_id,
+    },
+  )
+  .await;
+  unsubscribe(state, user_id, channel_id).await;
+
+  if let Some(dm_channel) = dm_channel {
+    let leaver = participant(previous, user_id);
+    post_system_message(
+      state,
+      channel_id,
+      SystemMessageData::RecipientRemove {
+        user: leaver.clone(),
+        recipient: leaver,
+      },
+    )
+    .await;
+
+    if let Some(owner_id) = dm_channel.owner_id
+      && previous.owner_id != Some(owner_id)
+    {
+      send_dm_event(
+        state,
+        dm_channel.participants.iter().map(|p| p.user_id).collect(),
+        WsMessage::DmUpdated {
+          dm: dm_channel.clone(),
+        },
+      )
+      .await;
+
+      post_system_message(
+        state,
+        channel_id,
+        SystemMessageData::GroupOwnerChange {
+          user: participant(&dm_channel, owner_id),
+        },
+      )
+      .await;
+    }
+  }
+}
+
+async fn unsubscribe(state: &AppState, user_id: Uuid, channel_id: Uuid) {
+  if let Err(e) = state
+    .connections
+    .unsubscribe_user(user_id, channel_id)
+    .await
+  {
+    tracing::error!("Failed to unsubscribe user from group DM: {}", e);
+  }
+}
+
+fn participant(dm_channel: &DmChannelResponse, user_id: Uuid) -> SystemMessageUser {
+  let username = dm_channel
+    .participants
+    .iter()
+    .find(|p| p.user_id == user_id)
+    .map(|p| p.username.clone())
+    .unwrap_or_default();
+
+  SystemMessageUser { user_id, username }
+}
+
+async fn send_dm_event(state: &AppState, user_ids: Vec<Uuid>, ws_message: WsMessage) {
+  if let Err(e) = state.connections.send_to_users(user_ids, ws_message).await {
+    tracing::error!("Failed to send DM event: {}", e);
+  }
+}
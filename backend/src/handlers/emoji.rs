@@ -0,0 +1,96 @@
+// backend/src/handlers/emoji.rs
+use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
+use crate::middleware::CurrentUser;
+use crate::models::{CreateEmojiRequest, Emoji, UpdateEmojiRequest};
+use crate::services::EmojiService;
+use crate::utils::AppResult;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+  http::{HeaderMap, header},
+  response::IntoResponse,
+};
+use uuid::Uuid;
+
+pub async fn create_emoji(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+  headers: HeaderMap,
+  Json(req): Json<CreateEmojiRequest>,
+) -> AppResult<Json<Emoji>> {
+  let reason = audit_reason(&headers)?;
+  let emoji = EmojiService::create_emoji(
+    &state.db,
+    &state.config.limits,
+    server_id,
+    user.id,
+    req,
+    reason.as_deref(),
+  )
+  .await?;
+  Ok(Json(emoji))
+}
+
+pub async fn get_server_emojis(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+) -> AppResult<Json<Vec<Emoji>>> {
+  let emojis = EmojiService::get_server_emojis(&state.db, server_id, user.id).await?;
+  Ok(Json(emojis))
+}
+
+pub async fn update_emoji(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((server_id, emoji_id)): Path<(Uuid, Uuid)>,
+  headers: HeaderMap,
+  Json(req): Json<UpdateEmojiRequest>,
+) -> AppResult<Json<Emoji>> {
+  let reason = audit_reason(&headers)?;
+  let emoji = EmojiService::update_emoji(
+    &state.db,
+    server_id,
+    emoji_id,
+    user.id,
+    req,
+    reason.as_deref(),
+  )
+  .await?;
+  Ok(Json(emoji))
+}
+
+pub async fn delete_emoji(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((server_id, emoji_id)): Path<(Uuid, Uuid)>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  let reason = audit_reason(&headers)?;
+  EmojiService::delete_emoji(&state.db, server_id, emoji_id, user.id, reason.as_deref()).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Emoji deleted successfully"}),
+  ))
+}
+
+// Images never change, but only the browser of a member may keep them
+pub async fn get_emoji_image(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(emoji_id): Path<Uuid>,
+) -> AppResult<impl IntoResponse> {
+  let image = EmojiService::get_emoji_image(&state.db, emoji_id, user.id).await?;
+  Ok((
+    [
+      (header::CONTENT_TYPE, image.content_type),
+      (
+        header::CACHE_CONTROL,
+        "private, max-age=86400, immutable".to_string(),
+      ),
+      (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+    ],
+    image.image,
+  ))
+}
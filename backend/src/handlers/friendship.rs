@@ -1,8 +1,11 @@
 use crate::AppState;
 use crate::middleware::CurrentUser;
-use crate::models::{Friendship, FullProfile, PaginatedResponse, PaginationParams, Profile};
-use crate::services::FriendshipService;
+use crate::models::{
+  Friendship, FriendshipStatus, FullProfile, PaginatedResponse, PaginationParams, Profile,
+};
+use crate::services::{FriendshipService, ProfileService};
 use crate::utils::AppResult;
+use crate::ws::WsMessage;
 use axum::{
   Extension, Json,
   extract::{Path, Query, State},
@@ -24,6 +27,7 @@ pub async fn send_friend_request(
   let recipient = FriendshipService::get_user_by_username(&state.db, &body.username).await?;
   let friendship =
     FriendshipService::send_or_accept_request(&state.db, user.id, recipient.user_id).await?;
+  notify_friend_request(&state, user.id, recipient.user_id, &friendship).await;
   Ok(Json(friendship))
 }
 
@@ -34,6 +38,7 @@ pub async fn create_friend_request(
   Path(user_id): Path<Uuid>,
 ) -> AppResult<Json<Friendship>> {
   let friendship = FriendshipService::send_or_accept_request(&state.db, user.id, user_id).await?;
+  notify_friend_request(&state, user.id, user_id, &friendship).await;
   Ok(Json(friendship))
 }
 
@@ -43,6 +48,14 @@ pub async fn reject_friend_request(
   Path(user_id): Path<Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
   FriendshipService::reject_request(&state.db, user.id, user_id).await?;
+  notify_friendship_updated(
+    &state,
+    user.id,
+    user_id,
+    Some(FriendshipStatus::Rejected),
+    Some(FriendshipStatus::Rejected),
+  )
+  .await;
   Ok(Json(
     serde_json::json!({"message": "Friend request rejected"}),
   ))
@@ -54,6 +67,7 @@ pub async fn remove_friend(
   Path(user_id): Path<Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
   FriendshipService::remove_friend(&state.db, user.id, user_id).await?;
+  notify_friendship_updated(&state, user.id, user_id, None, None).await;
   Ok(Json(
     serde_json::json!({"message": "Friend removed successfully"}),
   ))
@@ -65,6 +79,15 @@ pub async fn block_user(
   Path(user_id): Path<Uuid>,
 ) -> AppResult<Json<Friendship>> {
   let friendship = FriendshipService::block_user(&state.db, user.id, user_id).await?;
+  // The blocked user only sees the relationship disappear
+  notify_friendship_updated(
+    &state,
+    user.id,
+    user_id,
+    Some(FriendshipStatus::Blocked),
+    None,
+  )
+  .await;
   Ok(Json(friendship))
 }
 
@@ -74,6 +97,7 @@ pub async fn unblock_user(
   Path(user_id): Path<Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
   FriendshipService::unblock_user(&state.db, user.id, user_id).await?;
+  send_friendship_updated(&state, user.id, user_id, None).await;
   Ok(Json(
     serde_json::json!({"message": "User unblocked successfully"}),
   ))
@@ -125,6 +149,64 @@ pub async fn get_blocked_users(
   Ok(Json(profiles))
 }
 
+async fn notify_friend_request(
+  state: &AppState,
+  user_id: Uuid,
+  other_id: Uuid,
+  friendship: &Friendship,
+) {
+  if friendship.status == FriendshipStatus::Pending && friendship.sender_id == user_id {
+    match ProfileService::get_full_profile(&state.db, user_id).await {
+      Ok(profile) => {
+        let ws_message = WsMessage::FriendRequestReceived { user: profile };
+        if let Err(e) = state.connections.send_to_user(other_id, ws_message).await {
+          tracing::error!("Failed to send friend request event: {}", e);
+        }
+      }
+      Err(e) => tracing::error!("Failed to load profile for friend request event: {}", e),
+    }
+
+    send_friendship_updated(state, user_id, other_id, Some(friendship.status)).await;
+  } else {
+    notify_friendship_updated(
+      state,
+      user_id,
+      other_id,
+      Some(friendship.status),
+      Some(friendship.status),
+    )
+    .await;
+  }
+}
+
+async fn notify_friendship_updated(
+  state: &AppState,
+  user_id: Uuid,
+  other_id: Uuid,
+  user_status: Option<FriendshipStatus>,
+  other_status: Option<FriendshipStatus>,
+) {
+  send_friendship_updated(state, user_id, other_id, user_status).await;
+  send_friendship_updated(state, other_id, user_id, other_status).await;
+}
+
+// Tells `recipient_id` that their relationship with `user_id` changed
+async fn send_friendship_updated(
+  state: &AppState,
+  recipient_id: Uuid,
+  user_id: Uuid,
+  status: Option<FriendshipStatus>,
+) {
+  let ws_message = WsMessage::FriendshipUpdated { user_id, status };
+  if let Err(e) = state
+    .connections
+    .send_to_user(recipient_id, ws_message)
+    .await
+  {
+    tracing::error!("Failed to send friendship update: {}", e);
+  }
+}
+
 #[derive(Deserialize)]
 pub struct SearchUsersQuery {
   username: Option<String>,
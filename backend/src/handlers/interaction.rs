@@ -0,0 +1,255 @@
+// backend/src/handlers/interaction.rs
+use crate::AppState;
+use crate::handlers::message::broadcast_message_created;
+use crate::middleware::CurrentUser;
+use crate::models::{
+  ApplicationCommand, CreateCommandRequest, CreateInteractionRequest, CreateInteractionResponse,
+  EditInteractionResponseRequest, Interaction, InteractionCallbackRequest, InteractionState,
+};
+use crate::services::{ApplicationService, InteractionService};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use crate::ws::WsMessage;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+};
+use uuid::Uuid;
+
+pub async fn create_global_command(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+  Json(req): Json<CreateCommandRequest>,
+) -> AppResult<Json<ApplicationCommand>> {
+  let command =
+    InteractionService::create_command(&state.db, application_id, None, user.id, req).await?;
+  Ok(Json(command))
+}
+
+pub async fn get_global_commands(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(application_id): Path<Uuid>,
+) -> AppResult<Json<Vec<ApplicationCommand>>> {
+  let commands = InteractionService::get_commands(&state.db, application_id, None, user.id).await?;
+  Ok(Json(commands))
+}
+
+pub async fn create_server_command(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((application_id, server_id)): Path<(Uuid, Uuid)>,
+  Json(req): Json<CreateCommandRequest>,
+) -> AppResult<Json<ApplicationCommand>> {
+  let command =
+    InteractionService::create_command(&state.db, application_id, Some(server_id), user.id, req)
+      .await?;
+  Ok(Json(command))
+}
+
+pub async fn get_server_application_commands(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((application_id, server_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<Vec<ApplicationCommand>>> {
+  let commands =
+    InteractionService::get_commands(&state.db, application_id, Some(server_id), user.id).await?;
+  Ok(Json(commands))
+}
+
+pub async fn delete_command(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((application_id, command_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<serde_json::Value>> {
+  InteractionService::delete_command(&state.db, application_id, command_id, user.id).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Command deleted successfully"}),
+  ))
+}
+
+// Commands a member can run in the server, for the client's command picker
+pub async fn get_server_commands(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+) -> AppResult<Json<Vec<ApplicationCommand>>> {
+  let commands = InteractionService::get_server_commands(&state.db, server_id, user.id).await?;
+  Ok(Json(commands))
+}
+
+fn interaction_failed() -> AppError {
+  AppError::BadRequest(
+    ErrorCode::InteractionFailed,
+    "The application did not respond".to_string(),
+  )
+}
+
+// Applications with an interactions URL answer in the HTTP response, others over the gateway
+pub async fn create_interaction(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Json(req): Json<CreateInteractionRequest>,
+) -> AppResult<Json<CreateInteractionResponse>> {
+  let (application, payload) =
+    InteractionService::create_interaction(&state.db, &state.config.jwt, user.id, req).await?;
+  let interaction_id = payload.id;
+
+  let Some((url, secret)) =
+    ApplicationService::get_interactions_endpoint(&state.db, application.id).await?
+  else {
+    let ws_message = WsMessage::InteractionCreated {
+      interaction: payload,
+    };
+    if let Err(e) = state
+      .connections
+      .send_to_user(application.bot_user_id, ws_message)
+      .await
+    {
+      tracing::error!("Failed to send interaction to bot: {}", e);
+      return Err(interaction_failed());
+    }
+
+    return Ok(Json(CreateInteractionResponse {
+      id: interaction_id,
+      state: InteractionState::Pending,
+    }));
+  };
+
+  let body = serde_json::to_vec(&payload)
+    .map_err(|e| AppError::InternalServerError(format!("Failed to encode interaction: {}", e)))?;
+
+  let response = state
+    .outgoing_webhooks
+    .post_interaction(&url, &secret, body)
+    .await
+    .map_err(|e| {
+      tracing::warn!("Interaction {} failed: {}", interaction_id, e);
+      interaction_failed()
+    })?;
+
+  let callback: InteractionCallbackRequest = serde_json::from_slice(&response).map_err(|e| {
+    tracing::warn!(
+      "Interaction {} got an invalid response: {}",
+      interaction_id,
+      e
+    );
+    interaction_failed()
+  })?;
+
+  let interaction = respond(&state, interaction_id, &payload.token, callback).await?;
+
+  Ok(Json(CreateInteractionResponse {
+    id: interaction.id,
+    state: interaction.state,
+  }))
+}
+
+// Public, authenticated by the interaction token
+pub async fn interaction_callback(
+  State(state): State<AppState>,
+  Path((interaction_id, token)): Path<(Uuid, String)>,
+  Json(req): Json<InteractionCallbackRequest>,
+) -> AppResult<Json<Interaction>> {
+  let interaction = respond(&state, interaction_id, &token, req).await?;
+  Ok(Json(interaction))
+}
+
+// Public, completes a deferred response
+pub async fn edit_interaction_response(
+  State(state): State<AppState>,
+  Path((interaction_id, token)): Path<(Uuid, String)>,
+  Json(req): Json<EditInteractionResponseRequest>,
+) -> AppResult<Json<Interaction>> {
+  let interaction = InteractionService::complete_deferred(
+    &state.db,
+    &state.config.jwt,
+    &state.config.limits,
+    interaction_id,
+    &token,
+    &req.content,
+  )
+  .await?;
+
+  deliver_response(&state, &interaction, req.content).await?;
+
+  Ok(Json(interaction))
+}
+
+async fn respond(
+  state: &AppState,
+  interaction_id: Uuid,
+  token: &str,
+  callback: InteractionCallbackRequest,
+) -> AppResult<Interaction> {
+  let interaction = InteractionService::acknowledge(
+    &state.db,
+    &state.config.jwt,
+    &state.config.limits,
+    interaction_id,
+    token,
+    &callback,
+  )
+  .await?;
+
+  match callback {
+    InteractionCallbackRequest::ChannelMessage { content, .. } => {
+      deliver_response(state, &interaction, content).await?;
+    }
+    InteractionCallbackRequest::Deferred { .. } => {
+      let ws_message = WsMessage::InteractionDeferred {
+        interaction_id: interaction.id,
+        application_id: interaction.application_id,
+        channel_id: interaction.channel_id,
+      };
+      if let Err(e) = state
+        .connections
+        .send_to_user(interaction.user_id, ws_message)
+        .await
+      {
+        tracing::error!("Failed to send interaction deferral: {}", e);
+      }
+    }
+  }
+
+  Ok(interaction)
+}
+
+// Ephemeral responses only reach the invoker and are never stored
+async fn deliver_response(
+  state: &AppState,
+  interaction: &Interaction,
+  content: String,
+) -> AppResult<()> {
+  if interaction.ephemeral {
+    let ws_message = WsMessage::EphemeralMessage {
+      interaction_id: interaction.id,
+      application_id: interaction.application_id,
+      channel_id: interaction.channel_id,
+      content,
+    };
+    if let Err(e) = state
+      .connections
+      .send_to_user(interaction.user_id, ws_message)
+      .await
+    {
+      tracing::error!("Failed to send ephemeral response: {}", e);
+    }
+
+    return Ok(());
+  }
+
+  let application =
+    ApplicationService::get_application(&state.db, interaction.application_id).await?;
+  let message = InteractionService::post_response_message(
+    &state.db,
+    interaction,
+    application.bot_user_id,
+    &content,
+  )
+  .await?;
+
+  broadcast_message_created(state, &message, None).await;
+
+  Ok(())
+}
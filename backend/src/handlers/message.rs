@@ -1,12 +1,20 @@
 use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
+use crate::handlers::outgoing_webhook::dispatch_channel_event;
 use crate::middleware::CurrentUser;
-use crate::models::{CreateMessageRequest, MessageResponse};
-use crate::services::{ChannelService, MessageService};
+use crate::models::{
+  CreateMessageRequest, MessageResponse, OutgoingWebhookEvent, PushPayload, SystemMessageData,
+  UpdateMessageRequest,
+};
+use crate::services::{
+  ChannelService, MessageService, NotificationService, PresenceService, PushService,
+};
 use crate::utils::AppResult;
 use crate::ws::{WsMessage, broadcast_to_channel};
 use axum::{
   Extension, Json,
   extract::{Path, Query, State},
+  http::HeaderMap,
 };
 use serde::Deserialize;
 use uuid::Uuid;
@@ -22,30 +30,237 @@ fn default_limit() -> i64 {
   50
 }
 
+// Keeps the encrypted payload well under the 4KB push message limit
+const PUSH_BODY_LIMIT: usize = 500;
+
 pub async fn create_message(
   State(state): State<AppState>,
   Extension(user): Extension<CurrentUser>,
   Path(channel_id): Path<Uuid>,
   Json(req): Json<CreateMessageRequest>,
 ) -> AppResult<Json<MessageResponse>> {
-  let message = MessageService::create_message(&state.db, channel_id, user.id, req).await?;
+  let message =
+    MessageService::create_message(&state.db, &state.config.limits, channel_id, user.id, req)
+      .await?;
+
+  broadcast_message_created(&state, &message, Some(user.id)).await;
+
+  Ok(Json(message))
+}
+
+pub async fn update_message(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
+  Json(req): Json<UpdateMessageRequest>,
+) -> AppResult<Json<MessageResponse>> {
+  let message = MessageService::update_message(
+    &state.db,
+    &state.config.limits,
+    channel_id,
+    message_id,
+    user.id,
+    req,
+  )
+  .await?;
+
+  let ws_message = WsMessage::MessageUpdated {
+    id: message.id,
+    channel_id: message.channel_id,
+    content: message.content.clone(),
+    updated_at: message.updated_at.to_rfc3339(),
+  };
+  if let Err(e) = broadcast_to_channel(&state.connections, channel_id, ws_message, None).await {
+    tracing::error!("Failed to broadcast message update: {}", e);
+  }
+
+  dispatch_channel_event(
+    &state,
+    channel_id,
+    OutgoingWebhookEvent::MessageUpdated,
+    &message,
+  )
+  .await;
+
+  Ok(Json(message))
+}
+
+pub async fn delete_message(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  let reason = audit_reason(&headers)?;
+  MessageService::delete_message(
+    &state.db,
+    channel_id,
+    message_id,
+    user.id,
+    reason.as_deref(),
+  )
+  .await?;
+
+  let ws_message = WsMessage::MessageDeleted {
+    id: message_id,
+    channel_id,
+  };
+  if let Err(e) = broadcast_to_channel(&state.connections, channel_id, ws_message, None).await {
+    tracing::error!("Failed to broadcast message deletion: {}", e);
+  }
+
+  dispatch_channel_event(
+    &state,
+    channel_id,
+    OutgoingWebhookEvent::MessageDeleted,
+    serde_json::json!({"id": message_id, "channel_id": channel_id}),
+  )
+  .await;
+
+  Ok(Json(
+    serde_json::json!({"message": "Message deleted successfully"}),
+  ))
+}
 
+pub async fn broadcast_message_created(
+  state: &AppState,
+  message: &MessageResponse,
+  exclude_user: Option<Uuid>,
+) {
   let ws_message = WsMessage::MessageCreated {
     id: message.id,
     channel_id: message.channel_id,
     user_id: message.user_id,
     username: message.username.clone(),
+    webhook_id: message.webhook_id,
+    avatar_url: message.avatar_url.clone(),
+    message_type: message.message_type,
     content: message.content.clone(),
+    system_data: message.system_data.as_ref().map(|data| data.0.clone()),
     created_at: message.created_at.to_rfc3339(),
   };
 
-  if let Err(e) =
-    broadcast_to_channel(&state.connections, channel_id, ws_message, Some(user.id)).await
+  if let Err(e) = broadcast_to_channel(
+    &state.connections,
+    message.channel_id,
+    ws_message,
+    exclude_user,
+  )
+  .await
   {
     tracing::error!("Failed to broadcast message: {}", e);
   }
 
-  Ok(Json(message))
+  notify_message_recipients(state, message).await;
+
+  dispatch_channel_event(
+    state,
+    message.channel_id,
+    OutgoingWebhookEvent::MessageCreated,
+    message,
+  )
+  .await;
+}
+
+async fn notify_message_recipients(state: &AppState, message: &MessageResponse) {
+  let targets = match NotificationService::get_message_targets(&state.db, message).await {
+    Ok(targets) => targets,
+    Err(e) => {
+      tracing::error!("Failed to resolve message notifications: {}", e);
+      return;
+    }
+  };
+
+  // Offline users are reached through Web Push for mentions and direct messages
+  let mut push_targets = targets.mentioned.clone();
+  if targets.server_id.is_none() {
+    push_targets.extend(&targets.unread);
+  }
+  push_offline_users(state, message, targets.server_id, push_targets).await;
+
+  if !targets.mentioned.is_empty()
+    && let Err(e) = state
+      .connections
+      .send_to_users(
+        targets.mentioned,
+        WsMessage::Mentioned {
+          channel_id: message.channel_id,
+          server_id: targets.server_id,
+          message_id: message.id,
+        },
+      )
+      .await
+  {
+    tracing::error!("Failed to send mention notifications: {}", e);
+  }
+
+  if !targets.unread.is_empty()
+    && let Err(e) = state
+      .connections
+      .send_to_users(
+        targets.unread,
+        WsMessage::UnreadMessage {
+          channel_id: message.channel_id,
+          server_id: targets.server_id,
+          message_id: message.id,
+        },
+      )
+      .await
+  {
+    tracing::error!("Failed to send unread notifications: {}", e);
+  }
+}
+
+pub async fn post_system_message(state: &AppState, channel_id: Uuid, data: SystemMessageData) {
+  match MessageService::create_system_message(&state.db, channel_id, data).await {
+    Ok(message) => broadcast_message_created(state, &message, None).await,
+    Err(e) => tracing::error!("Failed to create system message: {}", e),
+  }
+}
+
+async fn push_offline_users(
+  state: &AppState,
+  message: &MessageResponse,
+  server_id: Option<Uuid>,
+  user_ids: Vec<Uuid>,
+) {
+  let Some(web_push) = &state.push else {
+    return;
+  };
+
+  // Sockets may be held by any node, so ask the shared presence table
+  let online = match PresenceService::online_users(&state.db, &user_ids).await {
+    Ok(online) => online,
+    Err(e) => {
+      tracing::error!("Failed to look up online users: {}", e);
+      return;
+    }
+  };
+  let offline: Vec<Uuid> = user_ids
+    .into_iter()
+    .filter(|user_id| !online.contains(user_id))
+    .collect();
+
+  if offline.is_empty() {
+    return;
+  }
+
+  let payload = PushPayload {
+    title: message
+      .username
+      .clone()
+      .unwrap_or_else(|| "Harmony".to_string()),
+    body: message.content.chars().take(PUSH_BODY_LIMIT).collect(),
+    channel_id: message.channel_id,
+    server_id,
+    message_id: message.id,
+  };
+
+  match PushService::enqueue(&state.db, &offline, &payload).await {
+    Ok(0) => {}
+    Ok(_) => web_push.wake.notify(),
+    Err(e) => tracing::error!("Failed to queue push notifications: {}", e),
+  }
 }
 
 pub async fn get_messages(
@@ -56,7 +271,8 @@ pub async fn get_messages(
 ) -> AppResult<Json<Vec<MessageResponse>>> {
   // Verify user has access to the channel
   if !ChannelService::user_has_access_to_channel(&state.db, channel_id, user.id).await? {
-    return Err(crate::utils::AppError::Unauthorized(
+    return Err(crate::utils::AppError::Forbidden(
+      crate::utils::ErrorCode::MissingAccess,
       "You don't have access to this channel".to_string(),
     ));
   }
@@ -0,0 +1,57 @@
+// backend/src/handlers/mfa.rs
+use crate::AppState;
+use crate::middleware::CurrentUser;
+use crate::models::{BackupCodesResponse, MfaCodeRequest, TotpEnrollmentResponse};
+use crate::services::MfaService;
+use crate::utils::AppResult;
+use axum::{Extension, Json, extract::State, http::HeaderMap};
+
+// Sensitive actions carry a fresh two-factor code in this header
+pub const MFA_CODE_HEADER: &str = "x-mfa-code";
+
+pub fn mfa_code(headers: &HeaderMap) -> Option<&str> {
+  headers
+    .get(MFA_CODE_HEADER)
+    .and_then(|value| value.to_str().ok())
+}
+
+pub async fn enroll_totp(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+) -> AppResult<Json<TotpEnrollmentResponse>> {
+  let (secret, otpauth_uri) = MfaService::enroll(&state.db, user.id).await?;
+  Ok(Json(TotpEnrollmentResponse {
+    secret,
+    otpauth_uri,
+  }))
+}
+
+pub async fn confirm_totp(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Json(req): Json<MfaCodeRequest>,
+) -> AppResult<Json<BackupCodesResponse>> {
+  let backup_codes = MfaService::confirm(&state.db, &state.config.jwt, user.id, &req.code).await?;
+  Ok(Json(BackupCodesResponse { backup_codes }))
+}
+
+pub async fn disable_totp(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Json(req): Json<MfaCodeRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+  MfaService::disable(&state.db, &state.config.jwt, user.id, &req.code).await?;
+  Ok(Json(serde_json::json!({
+    "message": "Two-factor authentication disabled"
+  })))
+}
+
+pub async fn regenerate_backup_codes(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Json(req): Json<MfaCodeRequest>,
+) -> AppResult<Json<BackupCodesResponse>> {
+  let backup_codes =
+    MfaService::regenerate_backup_codes(&state.db, &state.config.jwt, user.id, &req.code).await?;
+  Ok(Json(BackupCodesResponse { backup_codes }))
+}
@@ -1,8 +1,21 @@
+pub mod account;
+pub mod application;
+pub mod audit_log;
 pub mod auth;
+pub mod ban;
 pub mod channel;
 pub mod dm;
+pub mod emoji;
 pub mod friendship;
+pub mod interaction;
 pub mod message;
+pub mod mfa;
+pub mod notification;
+pub mod oidc;
 pub mod organization;
+pub mod outgoing_webhook;
 pub mod profile;
+pub mod push;
 pub mod server;
+pub mod voice;
+pub mod webhook;
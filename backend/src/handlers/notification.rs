@@ -0,0 +1,44 @@
+// backend/src/handlers/notification.rs
+use crate::AppState;
+use crate::middleware::CurrentUser;
+use crate::models::{
+  ChannelNotificationSettings, NotificationSettingsResponse, ServerNotificationSettings,
+  UpdateChannelNotificationSettingsRequest, UpdateServerNotificationSettingsRequest,
+};
+use crate::services::NotificationService;
+use crate::utils::AppResult;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+};
+use uuid::Uuid;
+
+pub async fn get_notification_settings(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+) -> AppResult<Json<NotificationSettingsResponse>> {
+  let settings = NotificationService::get_settings(&state.db, user.id).await?;
+  Ok(Json(settings))
+}
+
+pub async fn update_server_notification_settings(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+  Json(req): Json<UpdateServerNotificationSettingsRequest>,
+) -> AppResult<Json<ServerNotificationSettings>> {
+  let settings =
+    NotificationService::update_server_settings(&state.db, server_id, user.id, req).await?;
+  Ok(Json(settings))
+}
+
+pub async fn update_channel_notification_settings(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(channel_id): Path<Uuid>,
+  Json(req): Json<UpdateChannelNotificationSettingsRequest>,
+) -> AppResult<Json<ChannelNotificationSettings>> {
+  let settings =
+    NotificationService::update_channel_settings(&state.db, channel_id, user.id, req).await?;
+  Ok(Json(settings))
+}
@@ -0,0 +1,249 @@
+// backend/src/handlers/oidc.rs
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+  http::HeaderMap,
+};
+use chrono::Duration;
+use serde::Serialize;
+
+use crate::{
+  AppState,
+  handlers::auth::{AuthResponse, LoginResponse, send_verification_email},
+  handlers::mfa::mfa_code,
+  middleware::CurrentUser,
+  models::{
+    OidcAuthorizationResponse, OidcCallbackOutcome, OidcCallbackRequest, OidcFlow,
+    OidcProviderResponse, OidcRegisterRequest, ReauthenticationResponse, UserIdentity,
+    UserTokenKind,
+  },
+  services::{AuthService, MfaService, OidcService},
+  utils::AppResult,
+};
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum OidcLoginResponse {
+  Login(LoginResponse),
+  // Finished at /api/auth/oidc/register once the user has picked a username
+  RegistrationRequired {
+    registration_required: bool,
+    registration_token: String,
+    email: Option<String>,
+    suggested_username: Option<String>,
+  },
+}
+
+pub async fn get_providers(State(state): State<AppState>) -> Json<Vec<OidcProviderResponse>> {
+  Json(
+    state
+      .oidc
+      .providers()
+      .into_iter()
+      .map(|(id, name)| OidcProviderResponse { id, name })
+      .collect(),
+  )
+}
+
+pub async fn authorize(
+  State(state): State<AppState>,
+  Path(provider): Path<String>,
+) -> AppResult<Json<OidcAuthorizationResponse>> {
+  let authorization_url = OidcService::start(
+    &state.db,
+    &state.config.jwt,
+    &state.oidc,
+    &provider,
+    OidcFlow::Login,
+  )
+  .await?;
+  Ok(Json(OidcAuthorizationResponse { authorization_url }))
+}
+
+pub async fn callback(
+  State(state): State<AppState>,
+  Path(provider): Path<String>,
+  Json(req): Json<OidcCallbackRequest>,
+) -> AppResult<Json<OidcLoginResponse>> {
+  let outcome = OidcService::handle_callback(
+    &state.db,
+    &state.config.jwt,
+    &state.oidc,
+    &provider,
+    &req.code,
+    &req.state,
+    OidcFlow::Login,
+  )
+  .await?;
+
+  match outcome {
+    OidcCallbackOutcome::SignedIn(user_id) => {
+      tracing::info!("User signed in with {}: {}", provider, user_id);
+      let user = AuthService::get_user_by_id(&state.db, user_id).await?;
+
+      if user.totp_enabled_at.is_some() {
+        let ticket = MfaService::create_login_ticket(&state.db, &state.config.jwt, user.id).await?;
+        return Ok(Json(OidcLoginResponse::Login(LoginResponse::MfaRequired {
+          mfa_required: true,
+          ticket,
+        })));
+      }
+
+      let token = AuthService::generate_token(&state.config.jwt, &user)?;
+      Ok(Json(OidcLoginResponse::Login(
+        LoginResponse::Authenticated(AuthResponse {
+          user: user.into(),
+          token,
+        }),
+      )))
+    }
+    OidcCallbackOutcome::RegistrationRequired {
+      registration_token,
+      email,
+      suggested_username,
+    } => Ok(Json(OidcLoginResponse::RegistrationRequired {
+      registration_required: true,
+      registration_token,
+      email,
+      suggested_username,
+    })),
+    OidcCallbackOutcome::Connected(_) | OidcCallbackOutcome::Reauthenticated(_) => {
+      unreachable!("a callback for a login never connects or reauthenticates")
+    }
+  }
+}
+
+pub async fn register(
+  State(state): State<AppState>,
+  Json(req): Json<OidcRegisterRequest>,
+) -> AppResult<Json<AuthResponse>> {
+  let (user, needs_verification) =
+    OidcService::complete_registration(&state.db, &state.config.jwt, req).await?;
+  tracing::info!("Registered new user through a provider: {}", user.email);
+
+  let token = AuthService::generate_token(&state.config.jwt, &user)?;
+
+  if needs_verification {
+    send_verification_email(&state, &user).await;
+  }
+
+  Ok(Json(AuthResponse {
+    user: user.into(),
+    token,
+  }))
+}
+
+pub async fn get_identities(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+) -> AppResult<Json<Vec<UserIdentity>>> {
+  let identities = OidcService::get_identities(&state.db, user.id).await?;
+  Ok(Json(identities))
+}
+
+pub async fn connect_identity(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(provider): Path<String>,
+  headers: HeaderMap,
+) -> AppResult<Json<OidcAuthorizationResponse>> {
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
+
+  let authorization_url = OidcService::start(
+    &state.db,
+    &state.config.jwt,
+    &state.oidc,
+    &provider,
+    OidcFlow::Connect(user.id),
+  )
+  .await?;
+  Ok(Json(OidcAuthorizationResponse { authorization_url }))
+}
+
+pub async fn connect_identity_callback(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(provider): Path<String>,
+  Json(req): Json<OidcCallbackRequest>,
+) -> AppResult<Json<UserIdentity>> {
+  let outcome = OidcService::handle_callback(
+    &state.db,
+    &state.config.jwt,
+    &state.oidc,
+    &provider,
+    &req.code,
+    &req.state,
+    OidcFlow::Connect(user.id),
+  )
+  .await?;
+
+  match outcome {
+    OidcCallbackOutcome::Connected(identity) => Ok(Json(identity)),
+    _ => unreachable!("a callback for a connect always connects"),
+  }
+}
+
+// For accounts without a password, a fresh provider login stands in for it
+pub async fn reauthenticate(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(provider): Path<String>,
+) -> AppResult<Json<OidcAuthorizationResponse>> {
+  let authorization_url = OidcService::start(
+    &state.db,
+    &state.config.jwt,
+    &state.oidc,
+    &provider,
+    OidcFlow::Reauthenticate(user.id),
+  )
+  .await?;
+  Ok(Json(OidcAuthorizationResponse { authorization_url }))
+}
+
+pub async fn reauthenticate_callback(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(provider): Path<String>,
+  Json(req): Json<OidcCallbackRequest>,
+) -> AppResult<Json<ReauthenticationResponse>> {
+  let outcome = OidcService::handle_callback(
+    &state.db,
+    &state.config.jwt,
+    &state.oidc,
+    &provider,
+    &req.code,
+    &req.state,
+    OidcFlow::Reauthenticate(user.id),
+  )
+  .await?;
+
+  let OidcCallbackOutcome::Reauthenticated(user_id) = outcome else {
+    unreachable!("a callback for reauthentication always reauthenticates")
+  };
+
+  let reauthentication_token = AuthService::issue_user_token(
+    &state.db,
+    &state.config.jwt,
+    user_id,
+    UserTokenKind::Reauthentication,
+    Duration::minutes(5),
+  )
+  .await?;
+  Ok(Json(ReauthenticationResponse {
+    reauthentication_token,
+  }))
+}
+
+pub async fn disconnect_identity(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(provider): Path<String>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
+
+  OidcService::disconnect(&state.db, user.id, &provider).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Provider disconnected"}),
+  ))
+}
@@ -7,6 +7,7 @@ use crate::models::{
 };
 use crate::services::OrganizationService;
 use crate::utils::AppResult;
+use crate::ws::WsMessage;
 use axum::{
   Extension, Json,
   extract::{Path, State},
@@ -20,6 +21,7 @@ pub async fn create_folder(
   Json(req): Json<CreateFolderRequest>,
 ) -> AppResult<Json<ServerFolder>> {
   let folder = OrganizationService::create_folder(&state.db, user.id, req).await?;
+  notify_organization_updated(&state, user.id).await;
   Ok(Json(folder))
 }
 
@@ -30,6 +32,7 @@ pub async fn update_folder(
   Json(req): Json<UpdateFolderRequest>,
 ) -> AppResult<Json<ServerFolder>> {
   let folder = OrganizationService::update_folder(&state.db, folder_id, user.id, req).await?;
+  notify_organization_updated(&state, user.id).await;
   Ok(Json(folder))
 }
 
@@ -39,6 +42,7 @@ pub async fn delete_folder(
   Path(folder_id): Path<Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
   OrganizationService::delete_folder(&state.db, folder_id, user.id).await?;
+  notify_organization_updated(&state, user.id).await;
   Ok(Json(
     serde_json::json!({"message": "Folder deleted successfully"}),
   ))
@@ -53,6 +57,7 @@ pub async fn update_server_organization(
 ) -> AppResult<Json<ServerOrganization>> {
   let org =
     OrganizationService::update_server_organization(&state.db, server_id, user.id, req).await?;
+  notify_organization_updated(&state, user.id).await;
   Ok(Json(org))
 }
 
@@ -62,6 +67,7 @@ pub async fn batch_update_positions(
   Json(req): Json<BatchUpdateServerPositionsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
   OrganizationService::batch_update_server_positions(&state.db, user.id, req).await?;
+  notify_organization_updated(&state, user.id).await;
   Ok(Json(
     serde_json::json!({"message": "Server positions updated"}),
   ))
@@ -74,3 +80,18 @@ pub async fn get_organized_servers(
   let response = OrganizationService::get_organized_servers(&state.db, user.id).await?;
   Ok(Json(response))
 }
+
+async fn notify_organization_updated(state: &AppState, user_id: Uuid) {
+  let organization = match OrganizationService::get_organized_servers(&state.db, user_id).await {
+    Ok(organization) => organization,
+    Err(e) => {
+      tracing::error!("Failed to load organization for update event: {}", e);
+      return;
+    }
+  };
+
+  let ws_message = WsMessage::OrganizationUpdated { organization };
+  if let Err(e) = state.connections.send_to_user(user_id, ws_message).await {
+    tracing::error!("Failed to send organization update: {}", e);
+  }
+}
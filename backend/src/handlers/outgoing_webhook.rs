@@ -0,0 +1,170 @@
+// backend/src/handlers/outgoing_webhook.rs
+use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
+use crate::middleware::CurrentUser;
+use crate::models::{
+  CreateOutgoingWebhookRequest, OutgoingWebhook, OutgoingWebhookDeliveryLog, OutgoingWebhookEvent,
+  OutgoingWebhookMember, OutgoingWebhookWithSecretResponse, UpdateOutgoingWebhookRequest,
+};
+use crate::services::{ChannelService, OutgoingWebhookService, ServerService};
+use crate::utils::AppResult;
+use axum::{
+  Extension, Json,
+  extract::{Path, Query, State},
+  http::HeaderMap,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct GetDeliveriesQuery {
+  #[serde(default = "default_limit")]
+  limit: i64,
+}
+
+fn default_limit() -> i64 {
+  50
+}
+
+pub async fn create_outgoing_webhook(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+  headers: HeaderMap,
+  Json(req): Json<CreateOutgoingWebhookRequest>,
+) -> AppResult<Json<OutgoingWebhookWithSecretResponse>> {
+  let reason = audit_reason(&headers)?;
+  let (webhook, secret) = OutgoingWebhookService::create_webhook(
+    &state.db,
+    &state.outgoing_webhooks,
+    server_id,
+    user.id,
+    req,
+    reason.as_deref(),
+  )
+  .await?;
+
+  Ok(Json(OutgoingWebhookWithSecretResponse { webhook, secret }))
+}
+
+pub async fn get_outgoing_webhooks(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+) -> AppResult<Json<Vec<OutgoingWebhook>>> {
+  let webhooks = OutgoingWebhookService::get_server_webhooks(&state.db, server_id, user.id).await?;
+  Ok(Json(webhooks))
+}
+
+pub async fn update_outgoing_webhook(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(webhook_id): Path<Uuid>,
+  headers: HeaderMap,
+  Json(req): Json<UpdateOutgoingWebhookRequest>,
+) -> AppResult<Json<OutgoingWebhook>> {
+  let reason = audit_reason(&headers)?;
+  let webhook = OutgoingWebhookService::update_webhook(
+    &state.db,
+    &state.outgoing_webhooks,
+    webhook_id,
+    user.id,
+    req,
+    reason.as_deref(),
+  )
+  .await?;
+  Ok(Json(webhook))
+}
+
+pub async fn delete_outgoing_webhook(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(webhook_id): Path<Uuid>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  let reason = audit_reason(&headers)?;
+  OutgoingWebhookService::delete_webhook(&state.db, webhook_id, user.id, reason.as_deref()).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Webhook deleted successfully"}),
+  ))
+}
+
+pub async fn get_outgoing_webhook_deliveries(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(webhook_id): Path<Uuid>,
+  Query(query): Query<GetDeliveriesQuery>,
+) -> AppResult<Json<Vec<OutgoingWebhookDeliveryLog>>> {
+  let limit = query.limit.clamp(1, 100);
+  let deliveries =
+    OutgoingWebhookService::get_deliveries(&state.db, webhook_id, user.id, limit).await?;
+  Ok(Json(deliveries))
+}
+
+// Queues the event for the server's webhooks, failures are logged and never reach the caller
+pub async fn dispatch_event(
+  state: &AppState,
+  server_id: Uuid,
+  event: OutgoingWebhookEvent,
+  data: impl Serialize,
+) {
+  let data = match serde_json::to_value(data) {
+    Ok(data) => data,
+    Err(e) => {
+      tracing::error!("Failed to encode {} webhook event: {}", event.as_str(), e);
+      return;
+    }
+  };
+
+  match OutgoingWebhookService::enqueue(&state.db, server_id, event, data).await {
+    Ok(0) => {}
+    Ok(_) => state.outgoing_webhooks.wake.notify(),
+    Err(e) => tracing::error!("Failed to queue {} webhook event: {}", event.as_str(), e),
+  }
+}
+
+// Only server channels have webhooks, events in direct messages are not sent anywhere
+pub async fn dispatch_channel_event(
+  state: &AppState,
+  channel_id: Uuid,
+  event: OutgoingWebhookEvent,
+  data: impl Serialize,
+) {
+  match ChannelService::get_channel_by_id(&state.db, channel_id).await {
+    Ok(channel) => {
+      if let Some(server_id) = channel.server_id {
+        dispatch_event(state, server_id, event, data).await;
+      }
+    }
+    Err(e) => tracing::error!("Failed to resolve channel for webhook event: {}", e),
+  }
+}
+
+// Read before an account is deleted, its memberships and username are gone afterwards
+pub struct MemberDeparture {
+  member: OutgoingWebhookMember,
+  server_ids: Vec<Uuid>,
+}
+
+pub async fn member_departure(state: &AppState, user_id: Uuid) -> AppResult<MemberDeparture> {
+  let member = OutgoingWebhookService::get_member(&state.db, user_id).await?;
+  let server_ids = ServerService::get_user_servers(&state.db, user_id)
+    .await?
+    .into_iter()
+    .map(|server| server.id)
+    .collect();
+
+  Ok(MemberDeparture { member, server_ids })
+}
+
+pub async fn dispatch_member_left(state: &AppState, departure: MemberDeparture) {
+  for server_id in departure.server_ids {
+    dispatch_event(
+      state,
+      server_id,
+      OutgoingWebhookEvent::MemberLeft,
+      &departure.member,
+    )
+    .await;
+  }
+}
@@ -0,0 +1,58 @@
+// backend/src/handlers/push.rs
+use crate::AppState;
+use crate::middleware::CurrentUser;
+use crate::models::{CreatePushSubscriptionRequest, PushSubscription, VapidPublicKeyResponse};
+use crate::push::WebPush;
+use crate::services::PushService;
+use crate::utils::{AppError, AppResult, ErrorCode};
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+};
+use uuid::Uuid;
+
+fn web_push(state: &AppState) -> AppResult<&WebPush> {
+  state.push.as_ref().ok_or_else(|| {
+    AppError::NotFound(
+      ErrorCode::PushDisabled,
+      "Push notifications are not enabled".to_string(),
+    )
+  })
+}
+
+pub async fn get_vapid_public_key(
+  State(state): State<AppState>,
+) -> AppResult<Json<VapidPublicKeyResponse>> {
+  let public_key = web_push(&state)?.vapid.public_key().to_string();
+  Ok(Json(VapidPublicKeyResponse { public_key }))
+}
+
+pub async fn create_push_subscription(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Json(req): Json<CreatePushSubscriptionRequest>,
+) -> AppResult<Json<PushSubscription>> {
+  web_push(&state)?;
+  let subscription =
+    PushService::create_subscription(&state.db, &state.config.push, user.id, req).await?;
+  Ok(Json(subscription))
+}
+
+pub async fn get_push_subscriptions(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+) -> AppResult<Json<Vec<PushSubscription>>> {
+  let subscriptions = PushService::get_user_subscriptions(&state.db, user.id).await?;
+  Ok(Json(subscriptions))
+}
+
+pub async fn delete_push_subscription(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(subscription_id): Path<Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+  PushService::delete_subscription(&state.db, subscription_id, user.id).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Push subscription deleted successfully"}),
+  ))
+}
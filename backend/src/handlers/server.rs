@@ -1,15 +1,22 @@
 use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
+use crate::handlers::message::post_system_message;
+use crate::handlers::mfa::mfa_code;
+use crate::handlers::outgoing_webhook::dispatch_event;
 use crate::middleware::CurrentUser;
 use crate::models::{
-  CreateServerRequest, FullProfile, PaginatedResponse, PaginationParams, ServerResponse,
-  UpdateServerRequest,
+  CreateServerRequest, DiscoverableServer, DiscoveryQuery, FullProfile, OutgoingWebhookEvent,
+  PaginatedResponse, PaginationParams, ServerDetailResponse, ServerResponse, SystemMessageData,
+  SystemMessageUser, TransferServerOwnershipRequest, UpdateServerRequest,
 };
-use crate::services::ServerService;
+use crate::services::{EmojiService, MfaService, OutgoingWebhookService, ServerService};
 use crate::utils::AppResult;
+use crate::ws::WsMessage;
 use axum::extract::Query;
 use axum::{
   Extension, Json,
   extract::{Path, State},
+  http::HeaderMap,
 };
 use uuid::Uuid;
 
@@ -19,7 +26,16 @@ pub async fn create_server(
   Json(req): Json<CreateServerRequest>,
 ) -> AppResult<Json<ServerResponse>> {
   let server = ServerService::create_server(&state.db, user.id, req).await?;
-  Ok(Json(server.to_response(user.id)))
+  let response = server.to_response(user.id);
+
+  let ws_message = WsMessage::ServerJoined {
+    server: response.clone(),
+  };
+  if let Err(e) = state.connections.send_to_user(user.id, ws_message).await {
+    tracing::error!("Failed to send server joined event: {}", e);
+  }
+
+  Ok(Json(response))
 }
 
 pub async fn get_user_servers(
@@ -38,23 +54,80 @@ pub async fn get_server(
   State(state): State<AppState>,
   Extension(user): Extension<CurrentUser>,
   Path(server_id): Path<Uuid>,
-) -> AppResult<Json<ServerResponse>> {
-  // Check if user is a member
+) -> AppResult<Json<ServerDetailResponse>> {
   if !ServerService::is_member(&state.db, server_id, user.id).await? {
-    return Err(crate::utils::AppError::Unauthorized(
+    return Err(crate::utils::AppError::Forbidden(
+      crate::utils::ErrorCode::MissingAccess,
       "You are not a member of this server".to_string(),
     ));
   }
 
+  let emojis = EmojiService::get_server_emojis(&state.db, server_id, user.id).await?;
   let server = ServerService::get_server_by_id(&state.db, server_id).await?;
-  Ok(Json(server.to_response(user.id)))
+  Ok(Json(ServerDetailResponse {
+    server: server.to_response(user.id),
+    emojis,
+  }))
+}
+
+pub async fn discover_servers(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Query(query): Query<DiscoveryQuery>,
+) -> AppResult<Json<PaginatedResponse<DiscoverableServer>>> {
+  let servers = ServerService::discover(&state.db, user.id, query).await?;
+  Ok(Json(servers))
+}
+
+pub async fn join_server(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+) -> AppResult<Json<ServerResponse>> {
+  let server = ServerService::join_public_server(&state.db, server_id, user.id).await?;
+  let response = server.to_response(user.id);
+
+  let ws_message = WsMessage::ServerJoined {
+    server: response.clone(),
+  };
+  if let Err(e) = state.connections.send_to_user(user.id, ws_message).await {
+    tracing::error!("Failed to send server joined event: {}", e);
+  }
+
+  match OutgoingWebhookService::get_member(&state.db, user.id).await {
+    Ok(member) => {
+      if let Some(channel_id) = server.main_channel_id {
+        let data = SystemMessageData::MemberJoin {
+          user: SystemMessageUser {
+            user_id: member.user_id,
+            username: member.username.clone(),
+          },
+        };
+        post_system_message(&state, channel_id, data).await;
+      }
+
+      dispatch_event(
+        &state,
+        server.id,
+        OutgoingWebhookEvent::MemberJoined,
+        &member,
+      )
+      .await
+    }
+    Err(e) => tracing::error!("Failed to load member for join events: {}", e),
+  }
+
+  Ok(Json(response))
 }
 
 pub async fn delete_server(
   State(state): State<AppState>,
   Extension(user): Extension<CurrentUser>,
   Path(server_id): Path<Uuid>,
+  headers: HeaderMap,
 ) -> AppResult<Json<serde_json::Value>> {
+  ServerService::require_owner(&state.db, server_id, user.id, "delete the server").await?;
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
   ServerService::delete_server(&state.db, server_id, user.id).await?;
   Ok(Json(
     serde_json::json!({"message": "Server deleted successfully"}),
@@ -65,9 +138,33 @@ pub async fn update_server(
   State(state): State<AppState>,
   Extension(user): Extension<CurrentUser>,
   Path(server_id): Path<Uuid>,
+  headers: HeaderMap,
   Json(req): Json<UpdateServerRequest>,
 ) -> AppResult<Json<ServerResponse>> {
-  let server = ServerService::update_server(&state.db, server_id, user.id, req).await?;
+  let reason = audit_reason(&headers)?;
+  let server =
+    ServerService::update_server(&state.db, server_id, user.id, req, reason.as_deref()).await?;
+  Ok(Json(server.to_response(user.id)))
+}
+
+pub async fn transfer_server_ownership(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+  headers: HeaderMap,
+  Json(req): Json<TransferServerOwnershipRequest>,
+) -> AppResult<Json<ServerResponse>> {
+  let reason = audit_reason(&headers)?;
+  ServerService::require_owner(&state.db, server_id, user.id, "transfer ownership").await?;
+  MfaService::require_fresh_code(&state.db, &state.config.jwt, user.id, mfa_code(&headers)).await?;
+  let server = ServerService::transfer_ownership(
+    &state.db,
+    server_id,
+    user.id,
+    req.user_id,
+    reason.as_deref(),
+  )
+  .await?;
   Ok(Json(server.to_response(user.id)))
 }
 
@@ -79,7 +176,8 @@ pub async fn get_server_members(
 ) -> AppResult<Json<PaginatedResponse<FullProfile>>> {
   // Check if user is a member
   if !ServerService::is_member(&state.db, server_id, user.id).await? {
-    return Err(crate::utils::AppError::Unauthorized(
+    return Err(crate::utils::AppError::Forbidden(
+      crate::utils::ErrorCode::MissingAccess,
       "You are not a member of this server".to_string(),
     ));
   }
@@ -0,0 +1,52 @@
+// backend/src/handlers/voice.rs
+use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
+use crate::middleware::CurrentUser;
+use crate::models::{UpdateMemberVoiceRequest, VoiceState};
+use crate::services::VoiceService;
+use crate::utils::AppResult;
+use crate::ws::voice::broadcast_voice_state;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+  http::HeaderMap,
+};
+use uuid::Uuid;
+
+// Who is in which voice channel, clients keep it current from voice_state_updated events
+pub async fn get_voice_states(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(server_id): Path<Uuid>,
+) -> AppResult<Json<Vec<VoiceState>>> {
+  let states = VoiceService::get_server_states(&state.db, server_id, user.id).await?;
+  Ok(Json(states))
+}
+
+pub async fn update_member_voice(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path((server_id, member_id)): Path<(Uuid, Uuid)>,
+  headers: HeaderMap,
+  Json(req): Json<UpdateMemberVoiceRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+  let reason = audit_reason(&headers)?;
+  let voice_state = VoiceService::set_server_mute(
+    &state.db,
+    server_id,
+    member_id,
+    user.id,
+    req.server_mute,
+    reason.as_deref(),
+  )
+  .await?;
+
+  if let Some(voice_state) = voice_state {
+    broadcast_voice_state(&state.db, &state.connections, voice_state).await;
+  }
+
+  Ok(Json(serde_json::json!({
+    "user_id": member_id,
+    "server_mute": req.server_mute,
+  })))
+}
@@ -0,0 +1,87 @@
+// backend/src/handlers/webhook.rs
+use crate::AppState;
+use crate::handlers::audit_log::audit_reason;
+use crate::handlers::message::broadcast_message_created;
+use crate::middleware::CurrentUser;
+use crate::models::{
+  CreateWebhookRequest, ExecuteWebhookRequest, MessageResponse, Webhook, WebhookWithTokenResponse,
+};
+use crate::services::WebhookService;
+use crate::utils::AppResult;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+  http::HeaderMap,
+};
+use uuid::Uuid;
+
+pub async fn create_webhook(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(channel_id): Path<Uuid>,
+  headers: HeaderMap,
+  Json(req): Json<CreateWebhookRequest>,
+) -> AppResult<Json<WebhookWithTokenResponse>> {
+  let reason = audit_reason(&headers)?;
+  let (webhook, token) = WebhookService::create_webhook(
+    &state.db,
+    &state.config.jwt,
+    channel_id,
+    user.id,
+    req,
+    reason.as_deref(),
+  )
+  .await?;
+
+  // Relative to the API origin, the token in it is all a caller needs to post
+  let url = format!("/api/webhooks/{}/{}", webhook.id, token);
+
+  Ok(Json(WebhookWithTokenResponse {
+    webhook,
+    token,
+    url,
+  }))
+}
+
+pub async fn get_channel_webhooks(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(channel_id): Path<Uuid>,
+) -> AppResult<Json<Vec<Webhook>>> {
+  let webhooks = WebhookService::get_channel_webhooks(&state.db, channel_id, user.id).await?;
+  Ok(Json(webhooks))
+}
+
+pub async fn delete_webhook(
+  State(state): State<AppState>,
+  Extension(user): Extension<CurrentUser>,
+  Path(webhook_id): Path<Uuid>,
+  headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+  let reason = audit_reason(&headers)?;
+  WebhookService::delete_webhook(&state.db, webhook_id, user.id, reason.as_deref()).await?;
+  Ok(Json(
+    serde_json::json!({"message": "Webhook deleted successfully"}),
+  ))
+}
+
+// Public, authenticated by the token in the path
+pub async fn execute_webhook(
+  State(state): State<AppState>,
+  Path((webhook_id, token)): Path<(Uuid, String)>,
+  Json(req): Json<ExecuteWebhookRequest>,
+) -> AppResult<Json<MessageResponse>> {
+  let message = WebhookService::execute_webhook(
+    &state.db,
+    &state.config.jwt,
+    &state.config.limits,
+    webhook_id,
+    &token,
+    req,
+  )
+  .await?;
+
+  broadcast_message_created(&state, &message, None).await;
+
+  Ok(Json(message))
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use rsa::{
+  RsaPrivateKey, RsaPublicKey,
+  pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey},
+  pkcs8::{DecodePrivateKey, DecodePublicKey},
+  traits::PublicKeyParts,
+};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::config::JwtConfig;
+
+#[derive(Debug, Error)]
+pub enum JwtKeyError {
+  #[error("failed to read key file {path}: {source}")]
+  Read {
+    path: String,
+    source: std::io::Error,
+  },
+  #[error("key file {0} is not an Ed25519 or RSA key in PEM format")]
+  UnsupportedKey(String),
+  #[error("invalid key {0}: {1}")]
+  Invalid(String, jsonwebtoken::errors::Error),
+  #[error("key id '{0}' is used more than once")]
+  DuplicateKeyId(String),
+}
+
+pub struct SigningKey {
+  pub kid: String,
+  pub algorithm: Algorithm,
+  pub key: EncodingKey,
+}
+
+pub struct VerifyingKey {
+  pub algorithm: Algorithm,
+  pub key: DecodingKey,
+}
+
+// Public half of a key as published in the JWKS document, RFC 7517 and RFC 8037
+#[derive(Debug, Clone, Serialize)]
+pub struct Jwk {
+  pub kid: String,
+  pub alg: &'static str,
+  #[serde(rename = "use")]
+  pub key_use: &'static str,
+  #[serde(flatten)]
+  pub params: JwkParams,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kty")]
+pub enum JwkParams {
+  #[serde(rename = "OKP")]
+  Okp { crv: &'static str, x: String },
+  #[serde(rename = "RSA")]
+  Rsa { n: String, e: String },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JwkSet {
+  pub keys: Vec<Jwk>,
+}
+
+// Without a private key file tokens fall back to HS256 with the shared secret, which other
+// services cannot verify, so the JWKS document is empty
+#[derive(Clone, Default)]
+pub struct JwtKeys {
+  pub signing: Option<Arc<SigningKey>>,
+  pub verifying: Arc<HashMap<String, VerifyingKey>>,
+  pub jwks: Arc<JwkSet>,
+}
+
+struct PublicKey {
+  algorithm: Algorithm,
+  key: DecodingKey,
+  params: JwkParams,
+}
+
+impl JwtKeys {
+  pub fn load(config: &JwtConfig) -> Result<Self, JwtKeyError> {
+    let mut verifying = HashMap::new();
+    let mut jwks = Vec::new();
+
+    let mut add_public = |kid: &str, public: PublicKey| {
+      if verifying.contains_key(kid) {
+        return Err(JwtKeyError::DuplicateKeyId(kid.to_string()));
+      }
+      jwks.push(Jwk {
+        kid: kid.to_string(),
+        alg: algorithm_name(public.algorithm),
+        key_use: "sig",
+        params: public.params,
+      });
+      verifying.insert(
+        kid.to_string(),
+        VerifyingKey {
+          algorithm: public.algorithm,
+          key: public.key,
+        },
+      );
+      Ok(())
+    };
+
+    let signing = match &config.private_key_file {
+      Some(path) => {
+        let (signing, public) = load_private_key(Path::new(path), &config.key_id)?;
+        add_public(&signing.kid, public)?;
+        Some(Arc::new(signing))
+      }
+      None => None,
+    };
+
+    // Retired keys stay here until every token they signed has expired
+    for key in &config.verification_keys {
+      let public = load_public_key(Path::new(&key.public_key_file))?;
+      add_public(&key.key_id, public)?;
+    }
+
+    Ok(Self {
+      signing,
+      verifying: Arc::new(verifying),
+      jwks: Arc::new(JwkSet { keys: jwks }),
+    })
+  }
+}
+
+fn read_pem(path: &Path) -> Result<String, JwtKeyError> {
+  std::fs::read_to_string(path).map_err(|source| JwtKeyError::Read {
+    path: path.display().to_string(),
+    source,
+  })
+}
+
+fn load_private_key(path: &Path, kid: &str) -> Result<(SigningKey, PublicKey), JwtKeyError> {
+  let pem = read_pem(path)?;
+  let invalid = |e| JwtKeyError::Invalid(path.display().to_string(), e);
+
+  if let Ok(key) = ed25519_dalek::SigningKey::from_pkcs8_pem(&pem) {
+    let signing = SigningKey {
+      kid: kid.to_string(),
+      algorithm: Algorithm::EdDSA,
+      key: EncodingKey::from_ed_pem(pem.as_bytes()).map_err(invalid)?,
+    };
+    return Ok((
+      signing,
+      ed25519_public(&key.verifying_key()).map_err(invalid)?,
+    ));
+  }
+
+  let key = RsaPrivateKey::from_pkcs8_pem(&pem)
+    .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+    .map_err(|_| JwtKeyError::UnsupportedKey(path.display().to_string()))?;
+  let signing = SigningKey {
+    kid: kid.to_string(),
+    algorithm: Algorithm::RS256,
+    key: EncodingKey::from_rsa_pem(pem.as_bytes()).map_err(invalid)?,
+  };
+  Ok((signing, rsa_public(&key.to_public_key()).map_err(invalid)?))
+}
+
+fn load_public_key(path: &Path) -> Result<PublicKey, JwtKeyError> {
+  let pem = read_pem(path)?;
+  let invalid = |e| JwtKeyError::Invalid(path.display().to_string(), e);
+
+  if let Ok(key) = ed25519_dalek::VerifyingKey::from_public_key_pem(&pem) {
+    return ed25519_public(&key).map_err(invalid);
+  }
+
+  let key = RsaPublicKey::from_public_key_pem(&pem)
+    .or_else(|_| RsaPublicKey::from_pkcs1_pem(&pem))
+    .map_err(|_| JwtKeyError::UnsupportedKey(path.display().to_string()))?;
+  rsa_public(&key).map_err(invalid)
+}
+
+fn ed25519_public(
+  key: &ed25519_dalek::VerifyingKey,
+) -> Result<PublicKey, jsonwebtoken::errors::Error> {
+  let x = URL_SAFE_NO_PAD.encode(key.as_bytes());
+  Ok(PublicKey {
+    algorithm: Algorithm::EdDSA,
+    key: DecodingKey::from_ed_components(&x)?,
+    params: JwkParams::Okp { crv: "Ed25519", x },
+  })
+}
+
+fn rsa_public(key: &RsaPublicKey) -> Result<PublicKey, jsonwebtoken::errors::Error> {
+  let n = URL_SAFE_NO_PAD.encode(key.n().to_bytes_be());
+  let e = URL_SAFE_NO_PAD.encode(key.e().to_bytes_be());
+  Ok(PublicKey {
+    algorithm: Algorithm::RS256,
+    key: DecodingKey::from_rsa_components(&n, &e)?,
+    params: JwkParams::Rsa { n, e },
+  })
+}
+
+fn algorithm_name(algorithm: Algorithm) -> &'static str {
+  match algorithm {
+    Algorithm::EdDSA => "EdDSA",
+    _ => "RS256",
+  }
+}
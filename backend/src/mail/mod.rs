@@ -0,0 +1,118 @@
+use std::{path::PathBuf, sync::Arc};
+
+use lettre::{
+  AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+  message::{Mailbox, header::ContentType},
+  transport::smtp::authentication::Credentials,
+};
+
+use crate::config::{MailConfig, MailTransportKind, SmtpTls};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+  #[error("Invalid address: {0}")]
+  Address(#[from] lettre::address::AddressError),
+  #[error("Failed to build email: {0}")]
+  Build(#[from] lettre::error::Error),
+  #[error("SMTP error: {0}")]
+  Smtp(#[from] lettre::transport::smtp::Error),
+  #[error("File transport error: {0}")]
+  File(#[from] lettre::transport::file::Error),
+}
+
+// The smtp transport also works against a local sink such as Mailpit, the file transport
+// writes .eml files and the log transport only records what would have been sent
+#[derive(Clone)]
+pub enum MailTransport {
+  Smtp(AsyncSmtpTransport<Tokio1Executor>),
+  File(AsyncFileTransport<Tokio1Executor>),
+  Log,
+}
+
+#[derive(Clone)]
+pub struct Mailer {
+  transport: MailTransport,
+  from: Arc<Mailbox>,
+  app_url: Arc<str>,
+}
+
+impl Mailer {
+  pub fn from_config(config: &MailConfig) -> anyhow::Result<Self> {
+    let transport = match config.transport {
+      MailTransportKind::Smtp => {
+        let smtp = &config.smtp;
+
+        let mut builder = match smtp.tls {
+          SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?,
+          SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?,
+          SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+        };
+
+        if let Some(port) = smtp.port {
+          builder = builder.port(port);
+        }
+
+        if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+          builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        MailTransport::Smtp(builder.build())
+      }
+      MailTransportKind::File => {
+        let dir = PathBuf::from(&config.dir);
+        std::fs::create_dir_all(&dir)?;
+        MailTransport::File(AsyncFileTransport::new(dir))
+      }
+      MailTransportKind::Log => MailTransport::Log,
+    };
+
+    Ok(Self {
+      transport,
+      from: Arc::new(config.from.parse()?),
+      app_url: config.app_url.trim_end_matches('/').into(),
+    })
+  }
+
+  // Frontend URL that emailed links point to
+  pub fn link(&self, path: &str, token: &str) -> String {
+    format!("{}{}?token={}", self.app_url, path, token)
+  }
+
+  pub async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), MailError> {
+    let message = Message::builder()
+      .from((*self.from).clone())
+      .to(to.parse()?)
+      .subject(subject)
+      .header(ContentType::TEXT_PLAIN)
+      .body(body)?;
+
+    match &self.transport {
+      MailTransport::Smtp(transport) => {
+        transport.send(message).await?;
+      }
+      MailTransport::File(transport) => {
+        transport.send(message).await?;
+      }
+      MailTransport::Log => {
+        tracing::info!(
+          "Email to {}: {}\n{}",
+          to,
+          subject,
+          String::from_utf8_lossy(&message.formatted())
+        );
+      }
+    }
+
+    Ok(())
+  }
+
+  // Sends in the background so callers do not wait on, or leak timing from, the mail server
+  pub fn send_in_background(&self, to: String, subject: &'static str, body: String) {
+    let mailer = self.clone();
+    tokio::spawn(async move {
+      if let Err(e) = mailer.send(&to, subject, body).await {
+        tracing::error!("Failed to send email to {}: {}", to, e);
+      }
+    });
+  }
+}
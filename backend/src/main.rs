@@ -1,9 +1,10 @@
-use std::{env, net::SocketAddr};
+use std::{net::SocketAddr, sync::Arc};
 
 use axum::{
   Router,
+  extract::DefaultBodyLimit,
   http::{Method, header},
-  routing::{get, post},
+  routing::{get, patch, post},
 };
 use dotenv::dotenv;
 use sqlx::postgres::PgPoolOptions;
@@ -14,9 +15,17 @@ use tower_http::{
 };
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
+mod config;
 mod handlers;
+mod jwt;
+mod mail;
 mod middleware;
 mod models;
+mod oidc;
+mod outbox;
+mod outgoing_webhooks;
+mod push;
+mod rate_limit;
 mod routers;
 mod services;
 mod utils;
@@ -24,8 +33,14 @@ mod ws;
 
 #[derive(Clone)]
 pub struct AppState {
+  pub config: Arc<config::Config>,
   pub db: sqlx::PgPool,
   pub connections: ws::ConnectionMap,
+  pub push: Option<push::WebPush>,
+  pub mailer: mail::Mailer,
+  pub oidc: oidc::OidcClient,
+  pub outgoing_webhooks: outgoing_webhooks::OutgoingWebhooks,
+  pub rate_limiter: rate_limit::RateLimiter,
 }
 
 #[tokio::main]
@@ -40,10 +55,13 @@ async fn main() -> anyhow::Result<()> {
     .with(tracing_subscriber::fmt::layer())
     .init();
 
-  let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+  let config = Arc::new(config::Config::load()?);
+
   let db = PgPoolOptions::new()
-    .max_connections(5)
-    .connect(&database_url)
+    .max_connections(config.database.max_connections)
+    .min_connections(config.database.min_connections)
+    .acquire_timeout(config.database.acquire_timeout())
+    .connect(&config.database.url)
     .await
     .expect("Failed to connect to database.");
 
@@ -54,52 +72,168 @@ async fn main() -> anyhow::Result<()> {
 
   tracing::info!("Database migrations completed.");
 
-  let connections = ws::ConnectionMap::default();
+  let pubsub = match config.pubsub.backend {
+    config::Backend::Postgres => ws::PubSub::postgres(db.clone()),
+    config::Backend::Memory => ws::PubSub::memory(),
+  };
+
+  let connections = ws::ConnectionMap::new(pubsub);
+  services::PresenceService::heartbeat(&db, connections.node_id)
+    .await
+    .expect("Failed to register gateway node.");
+  ws::presence::spawn_heartbeat(db.clone(), connections.clone());
+  connections
+    .pubsub
+    .listen(connections.clone())
+    .await
+    .expect("Failed to start gateway listener.");
+
+  let push = push::WebPush::from_config(&config.push)?;
+  match &push {
+    Some(web_push) => push::worker::spawn(db.clone(), web_push.clone()),
+    None => tracing::info!("VAPID_PRIVATE_KEY not set, push notifications are disabled."),
+  }
+
+  let mailer = mail::Mailer::from_config(&config.mail)?;
 
-  let state = AppState { db, connections };
+  let oidc = oidc::OidcClient::new(&config.oidc);
+
+  let outgoing_webhooks = outgoing_webhooks::OutgoingWebhooks::new(&config.webhooks);
+  outgoing_webhooks::worker::spawn(db.clone(), outgoing_webhooks.clone());
+  services::OutgoingWebhookService::spawn_cleanup(db.clone());
+
+  let rate_limiter = match config.rate_limit.backend {
+    config::Backend::Postgres => rate_limit::RateLimiter::postgres(db.clone()),
+    config::Backend::Memory => rate_limit::RateLimiter::memory(),
+  };
+  rate_limiter.spawn_cleanup();
+  services::LoginThrottleService::spawn_cleanup(db.clone());
+
+  let state = AppState {
+    config: config.clone(),
+    db,
+    connections,
+    push,
+    mailer,
+    oidc,
+    outgoing_webhooks,
+    rate_limiter,
+  };
 
   let cors = if cfg!(debug_assertions) {
     CorsLayer::new()
       .allow_origin(Any)
       .allow_methods(Any)
       .allow_headers(Any)
+      .expose_headers([header::RETRY_AFTER])
       .allow_credentials(true)
   } else {
-    let allowed_origins = env::var("ALLOWED_ORIGINS")
-      .unwrap_or_else(|_| "".to_string())
-      .split(",")
-      .map(|s| s.parse().unwrap())
-      .collect::<Vec<_>>();
-
     CorsLayer::new()
-      .allow_origin(allowed_origins)
-      .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
-      .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
+      .allow_origin(config.cors.origins()?)
+      .allow_methods([
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::PATCH,
+        Method::DELETE,
+      ])
+      .allow_headers([
+        header::AUTHORIZATION,
+        header::CONTENT_TYPE,
+        header::ACCEPT,
+        header::HeaderName::from_static(handlers::mfa::MFA_CODE_HEADER),
+        header::HeaderName::from_static(handlers::audit_log::AUDIT_LOG_REASON_HEADER),
+      ])
+      .expose_headers([header::RETRY_AFTER])
       .allow_credentials(true)
   };
 
+  let auth_routes = Router::new()
+    .route("/api/auth/register", post(handlers::auth::register))
+    .route("/api/auth/login", post(handlers::auth::login))
+    .route("/api/auth/login/mfa", post(handlers::auth::login_mfa))
+    .route("/api/auth/verify-email", post(handlers::auth::verify_email))
+    .route(
+      "/api/auth/forgot-password",
+      post(handlers::auth::forgot_password),
+    )
+    .route(
+      "/api/auth/reset-password",
+      post(handlers::auth::reset_password),
+    )
+    .route(
+      "/api/auth/oidc/providers",
+      get(handlers::oidc::get_providers),
+    )
+    .route("/api/auth/oidc/register", post(handlers::oidc::register))
+    .route(
+      "/api/auth/oidc/{provider}/authorize",
+      post(handlers::oidc::authorize),
+    )
+    .route(
+      "/api/auth/oidc/{provider}/callback",
+      post(handlers::oidc::callback),
+    )
+    .layer(axum::middleware::from_fn_with_state(
+      state.clone(),
+      middleware::rate_limit_middleware,
+    ));
+
+  // Authenticated by the token in the path, limited per client IP like the auth routes
+  let webhook_routes = Router::new()
+    .route(
+      "/api/webhooks/{webhook_id}/{token}",
+      post(handlers::webhook::execute_webhook),
+    )
+    .layer(axum::middleware::from_fn_with_state(
+      state.clone(),
+      middleware::rate_limit_middleware,
+    ));
+
+  // Applications answer interactions with the token they were sent, limited per client IP
+  let interaction_routes = Router::new()
+    .route(
+      "/api/interactions/{interaction_id}/{token}/callback",
+      post(handlers::interaction::interaction_callback),
+    )
+    .route(
+      "/api/interactions/{interaction_id}/{token}/response",
+      patch(handlers::interaction::edit_interaction_response),
+    )
+    .layer(axum::middleware::from_fn_with_state(
+      state.clone(),
+      middleware::rate_limit_middleware,
+    ));
+
   let app = Router::new()
     .route("/", get(root_handler))
     .route("/health", get(health_check))
+    .route("/.well-known/jwks.json", get(handlers::auth::jwks))
     // WebSocket route
     .route("/ws", get(ws::ws_handler))
     // Auth routes (public)
-    .route("/api/auth/register", post(handlers::auth::register))
-    .route("/api/auth/login", post(handlers::auth::login))
+    .merge(auth_routes)
+    // Webhook execution (public)
+    .merge(webhook_routes)
+    // Interaction responses (public)
+    .merge(interaction_routes)
     // Protected routes
-    .nest("/api", routers::api::routes())
+    .nest("/api", routers::api::routes(state.clone()))
+    .layer(DefaultBodyLimit::max(config.limits.max_body_bytes))
     .layer(cors)
     .layer(TraceLayer::new_for_http())
     .with_state(state);
 
-  let host = "0.0.0.0".to_string();
-  let port = "8000".to_string();
-  let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+  let addr = config.bind_address();
 
   tracing::info!("Server listening on {}", addr);
 
   let listener = TcpListener::bind(addr).await?;
-  axum::serve(listener, app).await?;
+  axum::serve(
+    listener,
+    app.into_make_service_with_connect_info::<SocketAddr>(),
+  )
+  .await?;
 
   Ok(())
 }
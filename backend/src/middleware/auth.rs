@@ -1,35 +1,103 @@
+use crate::AppState;
 use crate::services::AuthService;
-use crate::utils::{AppError, AppResult};
-use axum::{extract::Request, http::header, middleware::Next, response::Response};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use axum::{
+  extract::{MatchedPath, Request, State},
+  http::{Method, header},
+  middleware::Next,
+  response::Response,
+};
 use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct CurrentUser {
   pub id: Uuid,
+  pub bot: bool,
 }
 
-pub async fn auth_middleware(mut req: Request, next: Next) -> AppResult<Response> {
+// Account, social and application management routes are for people only
+fn bot_allowed(method: &Method, path: &str) -> bool {
+  const HUMAN_ONLY_PREFIXES: &[&str] = &[
+    "/api/me/mfa",
+    "/api/me/identities",
+    "/api/me/reauthenticate",
+    "/api/me/push-subscriptions",
+    "/api/friends",
+    "/api/users/{user_id}/friend",
+    "/api/dms/group",
+    "/api/organization",
+    "/api/applications",
+  ];
+
+  // Bots register their own application's commands
+  const BOT_COMMAND_PREFIXES: &[&str] = &[
+    "/api/applications/{application_id}/commands",
+    "/api/applications/{application_id}/servers/{server_id}/commands",
+  ];
+
+  if BOT_COMMAND_PREFIXES
+    .iter()
+    .any(|prefix| path.starts_with(prefix))
+  {
+    return true;
+  }
+
+  let human_only = matches!(
+    (method.as_str(), path),
+    ("DELETE", "/api/me")
+      | ("PATCH", "/api/me/account")
+      | ("POST", "/api/me/password")
+      | ("POST", "/api/servers")
+      | ("POST", "/api/servers/{server_id}/join")
+      | ("POST", "/api/interactions")
+  ) || HUMAN_ONLY_PREFIXES
+    .iter()
+    .any(|prefix| path.starts_with(prefix));
+
+  !human_only
+}
+
+// Resolves an Authorization header value, user sessions use Bearer and bots use Bot
+pub async fn authenticate(state: &AppState, authorization: Option<&str>) -> AppResult<CurrentUser> {
+  match authorization.and_then(|header| header.split_once(' ')) {
+    Some(("Bearer", token)) => {
+      let id = AuthService::authenticate_token(&state.db, &state.config.jwt, token).await?;
+      Ok(CurrentUser { id, bot: false })
+    }
+    Some(("Bot", token)) => {
+      let id = AuthService::authenticate_bot_token(&state.db, &state.config.jwt, token).await?;
+      Ok(CurrentUser { id, bot: true })
+    }
+    _ => Err(AppError::Unauthorized(
+      ErrorCode::MissingToken,
+      "Missing or invalid authorization header".to_string(),
+    )),
+  }
+}
+
+pub async fn auth_middleware(
+  State(state): State<AppState>,
+  mut req: Request,
+  next: Next,
+) -> AppResult<Response> {
   let auth_header = req
     .headers()
     .get(header::AUTHORIZATION)
     .and_then(|header| header.to_str().ok());
 
-  let token = auth_header
-    .and_then(|header| {
-      if header.starts_with("Bearer ") {
-        Some(header.trim_start_matches("Bearer "))
-      } else {
-        None
-      }
-    })
-    .ok_or_else(|| AppError::Unauthorized("Missing or invalid authorization header".to_string()))?;
+  let user = authenticate(&state, auth_header).await?;
 
-  let claims = AuthService::verify_token(token)?;
-
-  let user_id = Uuid::parse_str(&claims.sub)
-    .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
+  if user.bot
+    && let Some(path) = req.extensions().get::<MatchedPath>()
+    && !bot_allowed(req.method(), path.as_str())
+  {
+    return Err(AppError::Forbidden(
+      ErrorCode::BotNotAllowed,
+      "Bots cannot use this endpoint".to_string(),
+    ));
+  }
 
-  req.extensions_mut().insert(CurrentUser { id: user_id });
+  req.extensions_mut().insert(user);
 
   Ok(next.run(req).await)
 }
@@ -44,3 +112,51 @@ impl RequestExt for Request {
     self.extensions().get::<CurrentUser>()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bots_use_messaging_routes() {
+    assert!(bot_allowed(
+      &Method::POST,
+      "/api/channels/{channel_id}/messages"
+    ));
+    assert!(bot_allowed(&Method::GET, "/api/me"));
+    assert!(bot_allowed(&Method::GET, "/api/servers/{server_id}"));
+  }
+
+  #[test]
+  fn bots_cannot_manage_accounts_or_applications() {
+    assert!(!bot_allowed(&Method::DELETE, "/api/me"));
+    assert!(!bot_allowed(&Method::PATCH, "/api/me/account"));
+    assert!(!bot_allowed(&Method::POST, "/api/me/password"));
+    assert!(!bot_allowed(&Method::POST, "/api/me/mfa/enable"));
+    assert!(!bot_allowed(
+      &Method::POST,
+      "/api/me/reauthenticate/{provider}"
+    ));
+    assert!(!bot_allowed(&Method::POST, "/api/friends"));
+    assert!(!bot_allowed(&Method::POST, "/api/users/{user_id}/friend"));
+    assert!(!bot_allowed(&Method::POST, "/api/servers"));
+    assert!(!bot_allowed(&Method::POST, "/api/servers/{server_id}/join"));
+    assert!(!bot_allowed(&Method::GET, "/api/applications"));
+    assert!(!bot_allowed(
+      &Method::DELETE,
+      "/api/applications/{application_id}"
+    ));
+  }
+
+  #[test]
+  fn bots_register_their_commands() {
+    assert!(bot_allowed(
+      &Method::PUT,
+      "/api/applications/{application_id}/commands"
+    ));
+    assert!(bot_allowed(
+      &Method::DELETE,
+      "/api/applications/{application_id}/servers/{server_id}/commands/{command_id}"
+    ));
+  }
+}
@@ -1,3 +1,5 @@
 pub mod auth;
+pub mod rate_limit;
 
-pub use auth::{CurrentUser, auth_middleware};
+pub use auth::{CurrentUser, auth_middleware, authenticate};
+pub use rate_limit::{ClientIp, rate_limit_middleware};
@@ -0,0 +1,87 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+  extract::{ConnectInfo, FromRequestParts, MatchedPath, Request, State},
+  http::{Extensions, HeaderMap, request::Parts},
+  middleware::Next,
+  response::Response,
+};
+
+use crate::AppState;
+use crate::middleware::CurrentUser;
+use crate::rate_limit::{self, RateLimit};
+use crate::utils::{AppError, AppResult};
+
+// Keys authenticated requests by user and public ones by client IP
+pub async fn rate_limit_middleware(
+  State(state): State<AppState>,
+  req: Request,
+  next: Next,
+) -> AppResult<Response> {
+  let key = match req.extensions().get::<CurrentUser>() {
+    Some(user) => user.id.to_string(),
+    None => client_ip(
+      req.headers(),
+      req.extensions(),
+      state.config.server.trust_proxy,
+    )
+    .map_or_else(|| "unknown".to_string(), |ip| ip.to_string()),
+  };
+
+  let path = req
+    .extensions()
+    .get::<MatchedPath>()
+    .map(|path| path.as_str().to_string());
+
+  let mut limits: Vec<RateLimit> = path
+    .and_then(|path| rate_limit::route_limit(req.method().as_str(), &path))
+    .into_iter()
+    .collect();
+  if req.extensions().get::<CurrentUser>().is_some() {
+    limits.push(rate_limit::GLOBAL);
+  }
+
+  for limit in limits {
+    if let Err(retry_after) = state.rate_limiter.check(limit, &key).await {
+      return Err(AppError::RateLimited(retry_after.as_secs().max(1)));
+    }
+  }
+
+  Ok(next.run(req).await)
+}
+
+// Client address for handlers that track abuse themselves, None when it cannot be determined
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl FromRequestParts<AppState> for ClientIp {
+  type Rejection = Infallible;
+
+  async fn from_request_parts(
+    parts: &mut Parts,
+    state: &AppState,
+  ) -> Result<Self, Self::Rejection> {
+    Ok(ClientIp(client_ip(
+      &parts.headers,
+      &parts.extensions,
+      state.config.server.trust_proxy,
+    )))
+  }
+}
+
+fn client_ip(headers: &HeaderMap, extensions: &Extensions, trust_proxy: bool) -> Option<IpAddr> {
+  // Only trusted behind our reverse proxy, which appends the real client address last
+  if trust_proxy
+    && let Some(ip) = headers
+      .get("x-forwarded-for")
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.rsplit(',').next())
+      .and_then(|ip| ip.trim().parse().ok())
+  {
+    return Some(ip);
+  }
+
+  extensions
+    .get::<ConnectInfo<SocketAddr>>()
+    .map(|ConnectInfo(addr)| addr.ip())
+}
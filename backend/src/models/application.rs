@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Application {
+  pub id: Uuid,
+  pub owner_id: Uuid,
+  pub bot_user_id: Uuid,
+  pub name: String,
+  pub description: Option<String>,
+  pub public: bool,
+  pub allow_dms: bool,
+  // Interactions are POSTed here when set, otherwise they are sent over the bot's gateway
+  pub interactions_url: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApplicationRequest {
+  pub name: String,
+  pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateApplicationRequest {
+  pub name: Option<String>,
+  pub description: Option<String>,
+  pub public: Option<bool>,
+  pub allow_dms: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthorizeBotRequest {
+  pub server_id: Uuid,
+}
+
+// The token is only ever shown here, it is stored hashed
+#[derive(Debug, Serialize)]
+pub struct ApplicationWithTokenResponse {
+  #[serde(flatten)]
+  pub application: Application,
+  pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BotTokenResponse {
+  pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetInteractionsEndpointRequest {
+  pub url: String,
+}
+
+// The secret verifies interaction request signatures and is only shown once
+#[derive(Debug, Serialize)]
+pub struct InteractionsEndpointResponse {
+  pub url: String,
+  pub secret: String,
+}
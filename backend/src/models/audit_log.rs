@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type, types::Json};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "audit_log_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditLogAction {
+  ServerUpdate,
+  ServerOwnershipTransfer,
+  ChannelCreate,
+  ChannelUpdate,
+  ChannelDelete,
+  BotAdd,
+  WebhookCreate,
+  WebhookDelete,
+  OutgoingWebhookCreate,
+  OutgoingWebhookUpdate,
+  OutgoingWebhookDelete,
+  MessageDelete,
+  EmojiCreate,
+  EmojiUpdate,
+  EmojiDelete,
+  MemberVoiceUpdate,
+  MemberBan,
+  MemberUnban,
+}
+
+// A field the action changed, old_value is absent for creations and new_value for deletions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogChange {
+  pub key: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub old_value: Option<serde_json::Value>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub new_value: Option<serde_json::Value>,
+}
+
+// actor_id and actor_username are None once the actor's account is deleted
+#[derive(Debug, FromRow, Serialize)]
+pub struct AuditLogEntry {
+  pub id: Uuid,
+  pub server_id: Uuid,
+  pub actor_id: Option<Uuid>,
+  pub actor_username: Option<String>,
+  pub action: AuditLogAction,
+  pub target_id: Option<Uuid>,
+  pub changes: Json<Vec<AuditLogChange>>,
+  pub reason: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+  pub actor_id: Option<Uuid>,
+  pub action: Option<AuditLogAction>,
+  pub target_id: Option<Uuid>,
+  #[serde(default = "default_limit")]
+  pub limit: i64,
+  #[serde(default)]
+  pub offset: i64,
+}
+
+fn default_limit() -> i64 {
+  50
+}
@@ -22,6 +22,7 @@ pub struct Channel {
   pub channel_type: ChannelType,
   pub topic: Option<String>,
   pub is_private: bool,
+  pub slowmode_seconds: i32,
   pub created_at: DateTime<Utc>,
 }
 
@@ -29,9 +30,12 @@ pub struct Channel {
 pub struct DmChannel {
   pub id: Uuid,
   pub channel_id: Uuid,
+  pub owner_id: Option<Uuid>,
+  pub icon_url: Option<String>,
   pub created_at: DateTime<Utc>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct DmParticipant {
   pub dm_channel_id: Uuid,
@@ -58,11 +62,18 @@ pub struct CreateGroupDmRequest {
   pub recipient_ids: Vec<Uuid>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateGroupDmRequest {
+  pub name: Option<String>,
+  pub icon_url: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateChannelRequest {
   pub name: Option<String>,
   pub position: Option<i32>,
   pub topic: Option<String>,
+  pub slowmode_seconds: Option<i32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -74,18 +85,23 @@ pub struct ChannelResponse {
   pub channel_type: ChannelType,
   pub topic: Option<String>,
   pub is_private: bool,
+  pub slowmode_seconds: i32,
   pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DmChannelResponse {
   pub id: Uuid,
   pub channel_id: Uuid,
+  pub channel_type: ChannelType,
+  pub name: String,
+  pub owner_id: Option<Uuid>,
+  pub icon_url: Option<String>,
   pub participants: Vec<DmParticipantInfo>,
   pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct DmParticipantInfo {
   pub user_id: Uuid,
   pub username: String,
@@ -102,6 +118,7 @@ impl Channel {
       channel_type: self.channel_type,
       topic: self.topic.clone(),
       is_private: self.is_private,
+      slowmode_seconds: self.slowmode_seconds,
       created_at: self.created_at,
     }
   }
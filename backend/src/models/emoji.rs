@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+// Written as <:name:id> in message content, the image is served from /api/emojis/{id}/image
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Emoji {
+  pub id: Uuid,
+  pub server_id: Uuid,
+  pub creator_id: Option<Uuid>,
+  pub name: String,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+// image is a base64 data URI, e.g. data:image/png;base64,...
+#[derive(Debug, Deserialize)]
+pub struct CreateEmojiRequest {
+  pub name: String,
+  pub image: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateEmojiRequest {
+  pub name: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct EmojiImage {
+  pub content_type: String,
+  pub image: Vec<u8>,
+}
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserIdentity {
+  pub id: Uuid,
+  pub provider: String,
+  pub email: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub last_login_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OidcProviderResponse {
+  pub id: String,
+  pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OidcAuthorizationResponse {
+  pub authorization_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackRequest {
+  pub code: String,
+  pub state: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcRegisterRequest {
+  pub registration_token: String,
+  pub username: String,
+  // Only used when the provider did not share an address
+  pub email: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReauthenticationResponse {
+  pub reauthentication_token: String,
+}
+
+// What a provider round trip was started for
+#[derive(Debug, Clone, Copy)]
+pub enum OidcFlow {
+  Login,
+  Connect(Uuid),
+  // Proves a signed in user still controls one of their connected identities
+  Reauthenticate(Uuid),
+}
+
+impl OidcFlow {
+  pub fn user_id(self) -> Option<Uuid> {
+    match self {
+      OidcFlow::Login => None,
+      OidcFlow::Connect(user_id) | OidcFlow::Reauthenticate(user_id) => Some(user_id),
+    }
+  }
+}
+
+// Result of a provider callback before any session is issued
+pub enum OidcCallbackOutcome {
+  SignedIn(Uuid),
+  Connected(UserIdentity),
+  Reauthenticated(Uuid),
+  RegistrationRequired {
+    registration_token: String,
+    email: Option<String>,
+    suggested_username: Option<String>,
+  },
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type, types::Json};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandOptionType {
+  String,
+  Integer,
+  Number,
+  Boolean,
+  // User and channel options carry the id of a user or channel
+  User,
+  Channel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOption {
+  pub name: String,
+  pub description: String,
+  #[serde(rename = "type")]
+  pub option_type: CommandOptionType,
+  #[serde(default)]
+  pub required: bool,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ApplicationCommand {
+  pub id: Uuid,
+  pub application_id: Uuid,
+  // None for global commands
+  pub server_id: Option<Uuid>,
+  pub name: String,
+  pub description: String,
+  pub options: Json<Vec<CommandOption>>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+// Registering a command with an existing name replaces it
+#[derive(Debug, Deserialize)]
+pub struct CreateCommandRequest {
+  pub name: String,
+  pub description: String,
+  #[serde(default)]
+  pub options: Vec<CommandOption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionOptionValue {
+  pub name: String,
+  pub value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateInteractionRequest {
+  pub command_id: Uuid,
+  pub channel_id: Uuid,
+  #[serde(default)]
+  pub options: Vec<InteractionOptionValue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "interaction_state", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum InteractionState {
+  Pending,
+  Deferred,
+  Completed,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Interaction {
+  pub id: Uuid,
+  pub application_id: Uuid,
+  pub command_id: Option<Uuid>,
+  pub channel_id: Uuid,
+  pub user_id: Uuid,
+  pub state: InteractionState,
+  pub ephemeral: bool,
+  pub expires_at: DateTime<Utc>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionCommand {
+  pub id: Uuid,
+  pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionUser {
+  pub id: Uuid,
+  pub username: String,
+}
+
+// What the application receives, the token authenticates its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionPayload {
+  pub id: Uuid,
+  pub token: String,
+  pub application_id: Uuid,
+  pub command: InteractionCommand,
+  pub options: Vec<InteractionOptionValue>,
+  pub channel_id: Uuid,
+  pub server_id: Uuid,
+  pub user: InteractionUser,
+}
+
+// Ephemeral responses are only shown to the user who ran the command
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InteractionCallbackRequest {
+  ChannelMessage {
+    content: String,
+    #[serde(default)]
+    ephemeral: bool,
+  },
+  // Acknowledges now, the content follows through the response endpoint
+  Deferred {
+    #[serde(default)]
+    ephemeral: bool,
+  },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditInteractionResponseRequest {
+  pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateInteractionResponse {
+  pub id: Uuid,
+  pub state: InteractionState,
+}
@@ -1,14 +1,123 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, Type, types::Json};
 use uuid::Uuid;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "message_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MessageType {
+  Default,
+  MemberJoin,
+  MemberLeave,
+  ChannelPinned,
+  RecipientAdd,
+  RecipientRemove,
+  GroupRename,
+  GroupIconChange,
+  GroupOwnerChange,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemMessageUser {
+  pub user_id: Uuid,
+  pub username: String,
+}
+
+// Structured payload stored alongside system messages so clients can render them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SystemMessageData {
+  MemberJoin {
+    user: SystemMessageUser,
+  },
+  MemberLeave {
+    user: SystemMessageUser,
+  },
+  ChannelPinned {
+    user: SystemMessageUser,
+    message_id: Uuid,
+  },
+  RecipientAdd {
+    user: SystemMessageUser,
+    recipient: SystemMessageUser,
+  },
+  RecipientRemove {
+    user: SystemMessageUser,
+    recipient: SystemMessageUser,
+  },
+  GroupRename {
+    user: SystemMessageUser,
+    name: String,
+  },
+  GroupIconChange {
+    user: SystemMessageUser,
+    icon_url: Option<String>,
+  },
+  GroupOwnerChange {
+    user: SystemMessageUser,
+  },
+}
+
+impl SystemMessageData {
+  pub fn message_type(&self) -> MessageType {
+    match self {
+      SystemMessageData::MemberJoin { .. } => MessageType::MemberJoin,
+      SystemMessageData::MemberLeave { .. } => MessageType::MemberLeave,
+      SystemMessageData::ChannelPinned { .. } => MessageType::ChannelPinned,
+      SystemMessageData::RecipientAdd { .. } => MessageType::RecipientAdd,
+      SystemMessageData::RecipientRemove { .. } => MessageType::RecipientRemove,
+      SystemMessageData::GroupRename { .. } => MessageType::GroupRename,
+      SystemMessageData::GroupIconChange { .. } => MessageType::GroupIconChange,
+      SystemMessageData::GroupOwnerChange { .. } => MessageType::GroupOwnerChange,
+    }
+  }
+
+  // Plain text fallback for clients that don't render the structured payload
+  pub fn content(&self) -> String {
+    match self {
+      SystemMessageData::MemberJoin { user } => format!("{} joined the server.", user.username),
+      SystemMessageData::MemberLeave { user } => format!("{} left the server.", user.username),
+      SystemMessageData::ChannelPinned { user, .. } => {
+        format!("{} pinned a message to this channel.", user.username)
+      }
+      SystemMessageData::RecipientAdd { user, recipient } => {
+        format!(
+          "{} added {} to the group.",
+          user.username, recipient.username
+        )
+      }
+      SystemMessageData::RecipientRemove { user, recipient } => {
+        if user.user_id == recipient.user_id {
+          format!("{} left the group.", user.username)
+        } else {
+          format!(
+            "{} removed {} from the group.",
+            user.username, recipient.username
+          )
+        }
+      }
+      SystemMessageData::GroupRename { user, name } => {
+        format!("{} changed the group name to {}.", user.username, name)
+      }
+      SystemMessageData::GroupIconChange { user, .. } => {
+        format!("{} changed the group icon.", user.username)
+      }
+      SystemMessageData::GroupOwnerChange { user } => {
+        format!("{} is now the group owner.", user.username)
+      }
+    }
+  }
+}
+
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct Message {
   pub id: Uuid,
   pub channel_id: Uuid,
-  pub user_id: Uuid,
+  pub user_id: Option<Uuid>,
+  pub message_type: MessageType,
   pub content: String,
+  pub system_data: Option<Json<SystemMessageData>>,
   pub created_at: DateTime<Utc>,
   pub updated_at: DateTime<Utc>,
 }
@@ -18,13 +127,41 @@ pub struct CreateMessageRequest {
   pub content: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateMessageRequest {
+  pub content: String,
+}
+
 #[derive(Debug, Serialize, FromRow)]
 pub struct MessageResponse {
   pub id: Uuid,
   pub channel_id: Uuid,
-  pub user_id: Uuid,
-  pub username: String,
+  pub user_id: Option<Uuid>,
+  pub username: Option<String>,
+  // Webhook messages have no user_id, username and avatar_url are the ones they were posted with
+  pub webhook_id: Option<Uuid>,
+  pub avatar_url: Option<String>,
+  pub message_type: MessageType,
   pub content: String,
+  pub system_data: Option<Json<SystemMessageData>>,
   pub created_at: DateTime<Utc>,
   pub updated_at: DateTime<Utc>,
 }
+
+impl Message {
+  pub fn into_response(self, username: Option<String>) -> MessageResponse {
+    MessageResponse {
+      id: self.id,
+      channel_id: self.channel_id,
+      user_id: self.user_id,
+      username,
+      webhook_id: None,
+      avatar_url: None,
+      message_type: self.message_type,
+      content: self.content,
+      system_data: self.system_data,
+      created_at: self.created_at,
+      updated_at: self.updated_at,
+    }
+  }
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct TotpEnrollmentResponse {
+  pub secret: String,
+  pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaCodeRequest {
+  pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupCodesResponse {
+  pub backup_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaLoginRequest {
+  pub ticket: String,
+  pub code: String,
+}
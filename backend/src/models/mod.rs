@@ -1,25 +1,77 @@
+pub mod application;
+pub mod audit_log;
 pub mod channel;
+pub mod emoji;
 pub mod friendship;
+pub mod identity;
+pub mod interaction;
 pub mod message;
+pub mod mfa;
+pub mod notification;
 pub mod organization;
+pub mod outgoing_webhook;
 pub mod pagination;
+pub mod push;
 pub mod server;
 pub mod user;
+pub mod voice;
+pub mod webhook;
 
+pub use application::{
+  Application, ApplicationWithTokenResponse, AuthorizeBotRequest, BotTokenResponse,
+  CreateApplicationRequest, InteractionsEndpointResponse, SetInteractionsEndpointRequest,
+  UpdateApplicationRequest,
+};
+pub use audit_log::{AuditLogAction, AuditLogChange, AuditLogEntry, AuditLogQuery};
 pub use channel::{
   Channel, ChannelResponse, ChannelType, CreateChannelRequest, CreateDmRequest,
-  CreateGroupDmRequest, DmChannel, DmChannelResponse, DmParticipant, DmParticipantInfo,
-  UpdateChannelRequest,
+  CreateGroupDmRequest, DmChannel, DmChannelResponse, DmParticipantInfo, UpdateChannelRequest,
+  UpdateGroupDmRequest,
 };
+pub use emoji::{CreateEmojiRequest, Emoji, EmojiImage, UpdateEmojiRequest};
 pub use friendship::{Friendship, FriendshipStatus};
-pub use message::{CreateMessageRequest, Message, MessageResponse};
+pub use identity::{
+  OidcAuthorizationResponse, OidcCallbackOutcome, OidcCallbackRequest, OidcFlow,
+  OidcProviderResponse, OidcRegisterRequest, ReauthenticationResponse, UserIdentity,
+};
+pub use interaction::{
+  ApplicationCommand, CommandOption, CommandOptionType, CreateCommandRequest,
+  CreateInteractionRequest, CreateInteractionResponse, EditInteractionResponseRequest, Interaction,
+  InteractionCallbackRequest, InteractionCommand, InteractionOptionValue, InteractionPayload,
+  InteractionState, InteractionUser,
+};
+pub use message::{
+  CreateMessageRequest, Message, MessageResponse, MessageType, SystemMessageData,
+  SystemMessageUser, UpdateMessageRequest,
+};
+pub use mfa::{BackupCodesResponse, MfaCodeRequest, MfaLoginRequest, TotpEnrollmentResponse};
+pub use notification::{
+  ChannelNotificationSettings, MessageNotificationTargets, MessageRecipientSettings,
+  NotificationLevel, NotificationSettingsResponse, ServerNotificationSettings,
+  UpdateChannelNotificationSettingsRequest, UpdateServerNotificationSettingsRequest,
+};
 pub use organization::{
   BatchUpdateServerPositionsRequest, CreateFolderRequest, FolderResponse, OrganizedServersResponse,
-  ServerFolder, ServerOrganization, ServerPositionUpdate, UpdateFolderRequest,
-  UpdateServerOrganizationRequest,
+  ServerFolder, ServerOrganization, UpdateFolderRequest, UpdateServerOrganizationRequest,
+};
+pub use outgoing_webhook::{
+  CreateOutgoingWebhookRequest, OutgoingWebhook, OutgoingWebhookDelivery,
+  OutgoingWebhookDeliveryLog, OutgoingWebhookEvent, OutgoingWebhookMember, OutgoingWebhookPayload,
+  OutgoingWebhookWithSecretResponse, UpdateOutgoingWebhookRequest,
 };
 pub use pagination::{PaginatedResponse, PaginationParams};
-pub use server::{CreateServerRequest, Server, ServerResponse, UpdateServerRequest};
+pub use push::{
+  CreatePushSubscriptionRequest, PushDelivery, PushPayload, PushSubscription,
+  VapidPublicKeyResponse,
+};
+pub use server::{
+  CreateServerRequest, DiscoverableServer, DiscoveryQuery, DiscoverySort, Server, ServerBan,
+  ServerDetailResponse, ServerResponse, TransferServerOwnershipRequest, UpdateServerRequest,
+};
 pub use user::{
-  CreateUserRequest, FullProfile, LoginRequest, Profile, UpdateProfileRequest, User, UserResponse,
+  ChangePasswordRequest, CreateUserRequest, DeleteAccountRequest, DmPrivacy, ForgotPasswordRequest,
+  FullProfile, LoginRequest, Profile, ResetPasswordRequest, UpdateAccountRequest,
+  UpdateProfileRequest, User, UserResponse, UserTokenKind, VerifyEmailRequest,
 };
+pub use voice::{UpdateMemberVoiceRequest, VoiceSignal, VoiceState};
+pub use webhook::{CreateWebhookRequest, ExecuteWebhookRequest, Webhook, WebhookWithTokenResponse};
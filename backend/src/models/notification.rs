@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "notification_level", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationLevel {
+  AllMessages,
+  OnlyMentions,
+  Nothing,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ServerNotificationSettings {
+  pub server_id: Uuid,
+  pub notification_level: NotificationLevel,
+  pub muted_until: Option<DateTime<Utc>>,
+  pub suppress_everyone: bool,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ChannelNotificationSettings {
+  pub channel_id: Uuid,
+  pub notification_level: Option<NotificationLevel>,
+  pub muted_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationSettingsResponse {
+  pub servers: Vec<ServerNotificationSettings>,
+  pub channels: Vec<ChannelNotificationSettings>,
+}
+
+// Distinguishes a missing field (keep) from an explicit null (clear)
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: Deserialize<'de>,
+{
+  T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateServerNotificationSettingsRequest {
+  pub notification_level: Option<NotificationLevel>,
+  #[serde(default, deserialize_with = "deserialize_some")]
+  pub muted_until: Option<Option<DateTime<Utc>>>,
+  pub suppress_everyone: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateChannelNotificationSettingsRequest {
+  #[serde(default, deserialize_with = "deserialize_some")]
+  pub notification_level: Option<Option<NotificationLevel>>,
+  #[serde(default, deserialize_with = "deserialize_some")]
+  pub muted_until: Option<Option<DateTime<Utc>>>,
+}
+
+// Effective settings of one recipient of a message
+#[derive(Debug, Clone, FromRow)]
+pub struct MessageRecipientSettings {
+  pub user_id: Uuid,
+  pub server_level: Option<NotificationLevel>,
+  pub server_muted_until: Option<DateTime<Utc>>,
+  pub suppress_everyone: Option<bool>,
+  pub channel_level: Option<NotificationLevel>,
+  pub channel_muted_until: Option<DateTime<Utc>>,
+}
+
+// Users to notify about a new message, split by the kind of event they receive
+#[derive(Debug, Default)]
+pub struct MessageNotificationTargets {
+  pub server_id: Option<Uuid>,
+  pub mentioned: Vec<Uuid>,
+  pub unread: Vec<Uuid>,
+}
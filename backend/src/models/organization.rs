@@ -60,7 +60,7 @@ pub struct ServerPositionUpdate {
   pub folder_id: Option<Uuid>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FolderResponse {
   pub id: Uuid,
   pub name: String,
@@ -70,7 +70,7 @@ pub struct FolderResponse {
   pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrganizedServersResponse {
   pub folders: Vec<FolderResponse>,
   pub ungrouped_servers: Vec<ServerResponse>,
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "outgoing_webhook_event", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OutgoingWebhookEvent {
+  MessageCreated,
+  MessageUpdated,
+  MessageDeleted,
+  MemberJoined,
+  MemberLeft,
+}
+
+impl OutgoingWebhookEvent {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      OutgoingWebhookEvent::MessageCreated => "message_created",
+      OutgoingWebhookEvent::MessageUpdated => "message_updated",
+      OutgoingWebhookEvent::MessageDeleted => "message_deleted",
+      OutgoingWebhookEvent::MemberJoined => "member_joined",
+      OutgoingWebhookEvent::MemberLeft => "member_left",
+    }
+  }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct OutgoingWebhook {
+  pub id: Uuid,
+  pub server_id: Uuid,
+  pub creator_id: Uuid,
+  pub url: String,
+  pub events: Vec<OutgoingWebhookEvent>,
+  pub enabled: bool,
+  pub consecutive_failures: i32,
+  // Set when the webhook was disabled after repeated failed deliveries
+  pub disabled_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOutgoingWebhookRequest {
+  pub url: String,
+  pub events: Vec<OutgoingWebhookEvent>,
+}
+
+// Enabling a webhook again clears its failure count
+#[derive(Debug, Deserialize)]
+pub struct UpdateOutgoingWebhookRequest {
+  pub url: Option<String>,
+  pub events: Option<Vec<OutgoingWebhookEvent>>,
+  pub enabled: Option<bool>,
+}
+
+// The secret verifies delivery signatures and is only shown once
+#[derive(Debug, Serialize)]
+pub struct OutgoingWebhookWithSecretResponse {
+  #[serde(flatten)]
+  pub webhook: OutgoingWebhook,
+  pub secret: String,
+}
+
+// Body of every delivery, id is shared by all webhooks notified of the same event
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutgoingWebhookPayload {
+  pub id: Uuid,
+  #[serde(rename = "type")]
+  pub event: OutgoingWebhookEvent,
+  pub server_id: Uuid,
+  pub created_at: DateTime<Utc>,
+  pub data: serde_json::Value,
+}
+
+// The user in member_joined and member_left events
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct OutgoingWebhookMember {
+  pub user_id: Uuid,
+  pub username: String,
+  pub bot: bool,
+}
+
+#[derive(Debug, FromRow)]
+pub struct OutgoingWebhookDelivery {
+  pub id: i64,
+  pub webhook_id: Uuid,
+  pub delivery_id: Uuid,
+  pub event: OutgoingWebhookEvent,
+  pub url: String,
+  pub secret: String,
+  pub payload: sqlx::types::Json<OutgoingWebhookPayload>,
+  pub attempts: i32,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct OutgoingWebhookDeliveryLog {
+  pub id: i64,
+  pub delivery_id: Uuid,
+  pub event: OutgoingWebhookEvent,
+  pub attempt: i32,
+  pub success: bool,
+  pub status_code: Option<i32>,
+  pub error: Option<String>,
+  pub duration_ms: i32,
+  pub created_at: DateTime<Utc>,
+}
@@ -24,6 +24,7 @@ fn default_limit() -> i64 {
 }
 
 impl PaginationParams {
+  #[allow(dead_code)]
   pub fn validate(&self) -> Result<(), String> {
     if self.limit < 1 {
       return Err("Limit must be at least 1".to_string());
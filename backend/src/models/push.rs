@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PushSubscription {
+  pub id: Uuid,
+  pub endpoint: String,
+  pub created_at: DateTime<Utc>,
+}
+
+// Mirrors the browser's PushSubscription.toJSON()
+#[derive(Debug, Deserialize)]
+pub struct CreatePushSubscriptionRequest {
+  pub endpoint: String,
+  pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PushSubscriptionKeys {
+  pub p256dh: String,
+  pub auth: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VapidPublicKeyResponse {
+  pub public_key: String,
+}
+
+// Body of a push message, decrypted by the service worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushPayload {
+  pub title: String,
+  pub body: String,
+  pub channel_id: Uuid,
+  pub server_id: Option<Uuid>,
+  pub message_id: Uuid,
+}
+
+// An outbox row joined with the subscription it is addressed to
+#[derive(Debug, Clone, FromRow)]
+pub struct PushDelivery {
+  pub id: i64,
+  pub subscription_id: Uuid,
+  pub endpoint: String,
+  pub p256dh: String,
+  pub auth: String,
+  pub payload: sqlx::types::Json<PushPayload>,
+  pub attempts: i32,
+}
@@ -3,12 +3,18 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::Emoji;
+
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct Server {
   pub id: Uuid,
   pub name: String,
   pub owner_id: Uuid,
   pub main_channel_id: Option<Uuid>,
+  pub public: bool,
+  pub description: Option<String>,
+  pub tags: Vec<String>,
+  pub member_count: i32,
   pub created_at: DateTime<Utc>,
   pub updated_at: DateTime<Utc>,
 }
@@ -22,18 +28,88 @@ pub struct CreateServerRequest {
 pub struct UpdateServerRequest {
   pub name: Option<String>,
   pub main_channel_id: Option<Uuid>,
+  pub public: Option<bool>,
+  // An empty description clears it
+  pub description: Option<String>,
+  pub tags: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize)]
+pub struct TransferServerOwnershipRequest {
+  pub user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerResponse {
   pub id: Uuid,
   pub name: String,
   pub owner_id: Uuid,
   pub main_channel_id: Option<Uuid>,
+  pub public: bool,
+  pub description: Option<String>,
+  pub tags: Vec<String>,
+  pub member_count: i32,
   pub created_at: DateTime<Utc>,
   pub is_owner: bool,
 }
 
+// Returned when a single server is fetched, lists and events carry the plain response
+#[derive(Debug, Serialize)]
+pub struct ServerDetailResponse {
+  #[serde(flatten)]
+  pub server: ServerResponse,
+  pub emojis: Vec<Emoji>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoverySort {
+  #[default]
+  Popular,
+  Newest,
+  // Falls back to popular without a search query
+  Relevance,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscoveryQuery {
+  pub q: Option<String>,
+  pub tag: Option<String>,
+  #[serde(default)]
+  pub sort: DiscoverySort,
+  #[serde(default = "default_limit")]
+  pub limit: i64,
+  #[serde(default)]
+  pub offset: i64,
+}
+
+fn default_limit() -> i64 {
+  50
+}
+
+// What a public server shows to someone who may not be a member
+#[derive(Debug, FromRow, Serialize)]
+pub struct DiscoverableServer {
+  pub id: Uuid,
+  pub name: String,
+  pub description: Option<String>,
+  pub tags: Vec<String>,
+  pub member_count: i32,
+  pub created_at: DateTime<Utc>,
+  pub is_member: bool,
+}
+
+// username is None once the banned user's account is deleted
+#[derive(Debug, FromRow, Serialize)]
+pub struct ServerBan {
+  pub server_id: Uuid,
+  pub user_id: Uuid,
+  pub username: Option<String>,
+  pub actor_id: Option<Uuid>,
+  pub reason: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
 impl Server {
   pub fn to_response(&self, current_user_id: Uuid) -> ServerResponse {
     ServerResponse {
@@ -41,6 +117,10 @@ impl Server {
       name: self.name.clone(),
       owner_id: self.owner_id,
       main_channel_id: self.main_channel_id,
+      public: self.public,
+      description: self.description.clone(),
+      tags: self.tags.clone(),
+      member_count: self.member_count,
       created_at: self.created_at,
       is_owner: self.owner_id == current_user_id,
     }
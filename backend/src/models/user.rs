@@ -13,6 +13,15 @@ pub enum ProfileStatus {
   Dnd,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "dm_privacy", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DmPrivacy {
+  Everyone,
+  ServerMembers,
+  Friends,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct User {
   pub id: Uuid,
@@ -20,6 +29,10 @@ pub struct User {
   pub email: String,
   #[serde(skip_serializing)]
   pub password_hash: String,
+  pub email_verified_at: Option<DateTime<Utc>>,
+  #[serde(skip_serializing)]
+  pub token_version: i32,
+  pub totp_enabled_at: Option<DateTime<Utc>>,
   pub created_at: DateTime<Utc>,
   pub updated_at: DateTime<Utc>,
 }
@@ -35,15 +48,16 @@ pub struct Profile {
   pub custom_status: Option<String>,
   pub status_emoji: Option<String>,
   pub show_online_status: bool,
-  pub allow_dms: bool,
+  pub dm_privacy: DmPrivacy,
   pub created_at: DateTime<Utc>,
   pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct FullProfile {
   pub id: Uuid,
   pub username: String,
+  pub bot: bool,
   pub display_name: Option<String>,
   pub bio: Option<String>,
   pub avatar_url: Option<String>,
@@ -65,7 +79,7 @@ pub struct UpdateProfileRequest {
   pub custom_status: Option<String>,
   pub status_emoji: Option<String>,
   pub show_online_status: Option<bool>,
-  pub allow_dms: Option<bool>,
+  pub dm_privacy: Option<DmPrivacy>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,11 +96,61 @@ pub struct LoginRequest {
   pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateAccountRequest {
+  pub username: Option<String>,
+  pub email: Option<String>,
+  // One of these is required when changing the email address
+  pub password: Option<String>,
+  pub reauthentication_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+  // Accounts created through a provider use a reauthentication token instead
+  pub current_password: Option<String>,
+  pub reauthentication_token: Option<String>,
+  pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+  pub password: Option<String>,
+  pub reauthentication_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+  pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+  pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+  pub token: String,
+  pub password: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[sqlx(type_name = "user_token_kind", rename_all = "snake_case")]
+pub enum UserTokenKind {
+  EmailVerification,
+  PasswordReset,
+  MfaTicket,
+  Reauthentication,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UserResponse {
   pub id: Uuid,
   pub username: String,
   pub email: String,
+  pub email_verified: bool,
+  pub mfa_enabled: bool,
   pub created_at: DateTime<Utc>,
 }
 
@@ -96,6 +160,8 @@ impl From<User> for UserResponse {
       id: value.id,
       username: value.username,
       email: value.email,
+      email_verified: value.email_verified_at.is_some(),
+      mfa_enabled: value.totp_enabled_at.is_some(),
       created_at: value.created_at,
     }
   }
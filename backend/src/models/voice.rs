@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+// channel_id is None in the event sent when a user leaves voice
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct VoiceState {
+  pub user_id: Uuid,
+  pub server_id: Uuid,
+  pub channel_id: Option<Uuid>,
+  pub self_mute: bool,
+  pub self_deaf: bool,
+  // Set by the server owner, clients must not send audio while it is
+  pub server_mute: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IceCandidate {
+  pub candidate: String,
+  pub sdp_mid: Option<String>,
+  pub sdp_m_line_index: Option<u16>,
+}
+
+// WebRTC negotiation relayed between two peers in the same voice channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VoiceSignal {
+  Offer { sdp: String },
+  Answer { sdp: String },
+  // Candidates gathered together should be sent in one frame, sockets are rate limited
+  IceCandidates { candidates: Vec<IceCandidate> },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMemberVoiceRequest {
+  pub server_mute: bool,
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Webhook {
+  pub id: Uuid,
+  pub channel_id: Uuid,
+  pub creator_id: Uuid,
+  pub name: String,
+  pub avatar_url: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+  pub name: String,
+  pub avatar_url: Option<String>,
+}
+
+// The token is part of the execute URL and is only shown once
+#[derive(Debug, Serialize)]
+pub struct WebhookWithTokenResponse {
+  #[serde(flatten)]
+  pub webhook: Webhook,
+  pub token: String,
+  pub url: String,
+}
+
+// username and avatar_url override the webhook's own for this message only
+#[derive(Debug, Deserialize)]
+pub struct ExecuteWebhookRequest {
+  pub content: String,
+  pub username: Option<String>,
+  pub avatar_url: Option<String>,
+}
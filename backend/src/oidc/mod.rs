@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+  DecodingKey, Validation, decode, decode_header,
+  jwk::{AlgorithmParameters, Jwk, JwkSet},
+};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::config::{OidcConfig, OidcProviderConfig};
+use crate::utils::random_token;
+
+// Provider metadata and keys are refetched after this, or sooner when a token names an unknown key
+const METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+  #[error("Unknown provider '{0}'")]
+  UnknownProvider(String),
+  #[error("Provider request failed: {0}")]
+  Http(#[from] reqwest::Error),
+  #[error("Provider metadata is invalid: {0}")]
+  InvalidMetadata(String),
+  #[error("Token exchange failed: {0}")]
+  TokenExchange(String),
+  #[error("ID token is invalid: {0}")]
+  InvalidIdToken(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+  issuer: String,
+  authorization_endpoint: String,
+  token_endpoint: String,
+  jwks_uri: String,
+}
+
+struct Discovered {
+  metadata: ProviderMetadata,
+  jwks: JwkSet,
+  fetched_at: Instant,
+}
+
+struct Provider {
+  config: OidcProviderConfig,
+  discovered: RwLock<Option<Arc<Discovered>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+  id_token: Option<String>,
+  error: Option<String>,
+  error_description: Option<String>,
+}
+
+// Claims we rely on from the ID token, audience and issuer are checked by the decoder
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+  pub sub: String,
+  pub nonce: Option<String>,
+  pub email: Option<String>,
+  #[serde(default)]
+  pub email_verified: bool,
+  pub preferred_username: Option<String>,
+  pub name: Option<String>,
+}
+
+// Secrets for one authorization request, kept server side until the callback
+pub struct AuthorizationRequest {
+  pub url: String,
+  pub state: String,
+  pub nonce: String,
+  pub code_verifier: String,
+}
+
+#[derive(Clone)]
+pub struct OidcClient {
+  http: reqwest::Client,
+  redirect_uri: String,
+  providers: Arc<HashMap<String, Provider>>,
+}
+
+impl OidcClient {
+  pub fn new(config: &OidcConfig) -> Self {
+    let http = reqwest::Client::builder()
+      .timeout(Duration::from_secs(10))
+      .build()
+      .expect("Failed to build OIDC http client.");
+
+    let providers = config
+      .providers
+      .iter()
+      .map(|provider| {
+        (
+          provider.id.clone(),
+          Provider {
+            config: provider.clone(),
+            discovered: RwLock::new(None),
+          },
+        )
+      })
+      .collect();
+
+    Self {
+      http,
+      redirect_uri: config.redirect_uri.clone(),
+      providers: Arc::new(providers),
+    }
+  }
+
+  // (id, display name) of each configured provider
+  pub fn providers(&self) -> Vec<(String, String)> {
+    let mut providers: Vec<_> = self
+      .providers
+      .values()
+      .map(|provider| (provider.config.id.clone(), provider.config.name.clone()))
+      .collect();
+    providers.sort();
+    providers
+  }
+
+  fn provider(&self, id: &str) -> Result<&Provider, OidcError> {
+    self
+      .providers
+      .get(id)
+      .ok_or_else(|| OidcError::UnknownProvider(id.to_string()))
+  }
+
+  async fn discover(&self, provider: &Provider, force: bool) -> Result<Arc<Discovered>, OidcError> {
+    if !force
+      && let Some(discovered) = provider.discovered.read().await.as_ref()
+      && discovered.fetched_at.elapsed() < METADATA_TTL
+    {
+      return Ok(discovered.clone());
+    }
+
+    let issuer = provider.config.issuer.trim_end_matches('/');
+    let metadata: ProviderMetadata = self
+      .http
+      .get(format!("{}/.well-known/openid-configuration", issuer))
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+
+    if metadata.issuer.trim_end_matches('/') != issuer {
+      return Err(OidcError::InvalidMetadata(format!(
+        "issuer '{}' does not match configured '{}'",
+        metadata.issuer, provider.config.issuer
+      )));
+    }
+
+    let jwks: JwkSet = self
+      .http
+      .get(&metadata.jwks_uri)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+
+    let discovered = Arc::new(Discovered {
+      metadata,
+      jwks,
+      fetched_at: Instant::now(),
+    });
+    *provider.discovered.write().await = Some(discovered.clone());
+
+    Ok(discovered)
+  }
+
+  // Authorization code request with PKCE (S256), state and nonce
+  pub async fn authorization_request(
+    &self,
+    provider_id: &str,
+  ) -> Result<AuthorizationRequest, OidcError> {
+    let provider = self.provider(provider_id)?;
+    let discovered = self.discover(provider, false).await?;
+
+    let state = random_token();
+    let nonce = random_token();
+    let code_verifier = random_token();
+    let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+    let url = Url::parse_with_params(
+      &discovered.metadata.authorization_endpoint,
+      [
+        ("response_type", "code"),
+        ("client_id", provider.config.client_id.as_str()),
+        ("redirect_uri", self.redirect_uri.as_str()),
+        ("scope", provider.config.scopes.join(" ").as_str()),
+        ("state", state.as_str()),
+        ("nonce", nonce.as_str()),
+        ("code_challenge", code_challenge.as_str()),
+        ("code_challenge_method", "S256"),
+      ],
+    )
+    .map_err(|e| OidcError::InvalidMetadata(format!("authorization_endpoint: {}", e)))?;
+
+    Ok(AuthorizationRequest {
+      url: url.to_string(),
+      state,
+      nonce,
+      code_verifier,
+    })
+  }
+
+  // Exchanges the code and returns the verified ID token claims
+  pub async fn exchange_code(
+    &self,
+    provider_id: &str,
+    code: &str,
+    code_verifier: &str,
+    nonce: &str,
+  ) -> Result<IdTokenClaims, OidcError> {
+    let provider = self.provider(provider_id)?;
+    let discovered = self.discover(provider, false).await?;
+
+    let mut form = vec![
+      ("grant_type", "authorization_code"),
+      ("code", code),
+      ("redirect_uri", self.redirect_uri.as_str()),
+      ("client_id", provider.config.client_id.as_str()),
+      ("code_verifier", code_verifier),
+    ];
+    if let Some(secret) = &provider.config.client_secret {
+      form.push(("client_secret", secret.as_str()));
+    }
+
+    let response: TokenResponse = self
+      .http
+      .post(&discovered.metadata.token_endpoint)
+      .form(&form)
+      .send()
+      .await?
+      .json()
+      .await?;
+
+    let id_token = match response {
+      TokenResponse {
+        id_token: Some(id_token),
+        ..
+      } => id_token,
+      TokenResponse {
+        error,
+        error_description,
+        ..
+      } => {
+        return Err(OidcError::TokenExchange(
+          error_description
+            .or(error)
+            .unwrap_or_else(|| "no id_token in response".to_string()),
+        ));
+      }
+    };
+
+    let claims = self
+      .verify_id_token(provider, discovered, &id_token)
+      .await?;
+
+    if claims.nonce.as_deref() != Some(nonce) {
+      return Err(OidcError::InvalidIdToken("nonce mismatch".to_string()));
+    }
+
+    Ok(claims)
+  }
+
+  async fn verify_id_token(
+    &self,
+    provider: &Provider,
+    mut discovered: Arc<Discovered>,
+    id_token: &str,
+  ) -> Result<IdTokenClaims, OidcError> {
+    let header = decode_header(id_token).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+
+    let find_key = |jwks: &JwkSet| -> Option<Jwk> {
+      match &header.kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+        None => None,
+      }
+    };
+
+    // The provider may have rotated its keys since we last fetched them
+    let jwk = match find_key(&discovered.jwks) {
+      Some(jwk) => jwk,
+      None => {
+        discovered = self.discover(provider, true).await?;
+        find_key(&discovered.jwks)
+          .ok_or_else(|| OidcError::InvalidIdToken("signed with an unknown key".to_string()))?
+      }
+    };
+
+    if matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_)) {
+      return Err(OidcError::InvalidIdToken(
+        "symmetric keys are not accepted".to_string(),
+      ));
+    }
+    let key = DecodingKey::from_jwk(&jwk).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+
+    // The algorithm comes from the token, the decoder rejects it unless it suits the key
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[&discovered.metadata.issuer]);
+    validation.set_audience(&[&provider.config.client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    decode::<IdTokenClaims>(id_token, &key, &validation)
+      .map(|data| data.claims)
+      .map_err(|e| OidcError::InvalidIdToken(e.to_string()))
+  }
+}
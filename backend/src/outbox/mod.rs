@@ -0,0 +1,117 @@
+pub mod worker;
+
+use std::{sync::Arc, time::Duration};
+
+use sqlx::{FromRow, PgPool, postgres::PgRow};
+use tokio::sync::Notify;
+
+use crate::utils::AppResult;
+
+// A table of queued deliveries drained by a worker. Rows need an id BIGINT, attempts,
+// next_attempt_at, last_error and created_at
+#[derive(Clone, Copy)]
+pub struct Outbox {
+  pub name: &'static str,
+  pub table: &'static str,
+  // Selects the deliveries from the `claimed` rows, joined with what they are sent to
+  pub deliveries: &'static str,
+  // Deliveries not sent by then are dropped
+  pub retention: &'static str,
+  pub max_attempts: i32,
+}
+
+impl Outbox {
+  // Claimed rows are hidden from other workers until claim_timeout has passed
+  pub async fn claim<T>(
+    &self,
+    db: &PgPool,
+    limit: i64,
+    claim_timeout: Duration,
+  ) -> AppResult<Vec<T>>
+  where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+  {
+    sqlx::query(&format!(
+      "DELETE FROM {} WHERE created_at < NOW() - INTERVAL '{}'",
+      self.table, self.retention
+    ))
+    .execute(db)
+    .await?;
+
+    let deliveries = sqlx::query_as::<_, T>(&format!(
+      r#"
+      WITH claimed AS (
+        UPDATE {table}
+        SET next_attempt_at = NOW() + make_interval(secs => $2)
+        WHERE id IN (
+          SELECT id FROM {table}
+          WHERE next_attempt_at <= NOW()
+          ORDER BY next_attempt_at
+          LIMIT $1
+          FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *
+      )
+      {deliveries}
+      "#,
+      table = self.table,
+      deliveries = self.deliveries,
+    ))
+    .bind(limit)
+    .bind(claim_timeout.as_secs_f64())
+    .fetch_all(db)
+    .await?;
+
+    Ok(deliveries)
+  }
+
+  pub async fn complete(&self, db: &PgPool, id: i64) -> AppResult<()> {
+    sqlx::query(&format!("DELETE FROM {} WHERE id = $1", self.table))
+      .bind(id)
+      .execute(db)
+      .await?;
+
+    Ok(())
+  }
+
+  pub fn can_retry(&self, attempts: i32) -> bool {
+    attempts + 1 < self.max_attempts
+  }
+
+  // Backs off from 30s, doubling with every attempt made
+  pub async fn retry(&self, db: &PgPool, id: i64, attempts: i32, error: &str) -> AppResult<()> {
+    let delay_secs = 30 * 2i64.pow(attempts as u32);
+
+    sqlx::query(&format!(
+      r#"
+      UPDATE {}
+      SET attempts = attempts + 1,
+        next_attempt_at = NOW() + make_interval(secs => $2),
+        last_error = $3
+      WHERE id = $1
+      "#,
+      self.table
+    ))
+    .bind(id)
+    .bind(delay_secs as f64)
+    .bind(error)
+    .execute(db)
+    .await?;
+
+    Ok(())
+  }
+}
+
+// Lets the worker pick up newly queued deliveries without waiting for its next poll
+#[derive(Clone, Default)]
+pub struct Wake(Arc<Notify>);
+
+impl Wake {
+  pub fn notify(&self) {
+    self.0.notify_one();
+  }
+
+  async fn notified(&self) {
+    self.0.notified().await;
+  }
+}
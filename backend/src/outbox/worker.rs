@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+use sqlx::{FromRow, PgPool, postgres::PgRow};
+
+use crate::outbox::{Outbox, Wake};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const BATCH_SIZE: i64 = 50;
+
+// Deliveries sent at once, a batch takes at most BATCH_SIZE / CONCURRENCY request timeouts
+const CONCURRENCY: usize = 25;
+// Time on top of the requests for recording the outcomes
+const CLAIM_MARGIN: Duration = Duration::from_secs(30);
+
+// Drains the outbox in the background, safe to run on several nodes at once. `process` sends
+// one delivery and completes, retries or drops it
+pub fn spawn<T, F, Fut>(
+  db: PgPool,
+  outbox: Outbox,
+  wake: Wake,
+  request_timeout: Duration,
+  process: F,
+) where
+  T: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+  F: Fn(T) -> Fut + Send + Sync + 'static,
+  Fut: Future<Output = ()> + Send,
+{
+  // Claims outlast the whole batch so no delivery is claimed again while still being sent
+  let rounds = (BATCH_SIZE as u32).div_ceil(CONCURRENCY as u32);
+  let claim_timeout = request_timeout * rounds + CLAIM_MARGIN;
+
+  tokio::spawn(async move {
+    loop {
+      match outbox.claim::<T>(&db, BATCH_SIZE, claim_timeout).await {
+        Ok(deliveries) => {
+          let drained = (deliveries.len() as i64) < BATCH_SIZE;
+
+          stream::iter(deliveries)
+            .for_each_concurrent(CONCURRENCY, &process)
+            .await;
+
+          if !drained {
+            continue;
+          }
+        }
+        Err(e) => tracing::error!("Failed to claim {} deliveries: {}", outbox.name, e),
+      }
+
+      tokio::select! {
+        _ = wake.notified() => {}
+        _ = tokio::time::sleep(POLL_INTERVAL) => {}
+      }
+    }
+  });
+
+  tracing::info!("Worker for {} started", outbox.table);
+}
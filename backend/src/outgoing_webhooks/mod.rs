@@ -0,0 +1,228 @@
+pub mod worker;
+
+use std::time::{Duration, Instant};
+
+use hmac::{Hmac, Mac};
+use reqwest::{Url, header};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::config::WebhooksConfig;
+use crate::models::OutgoingWebhookEvent;
+use crate::outbox::Wake;
+use crate::utils::net::{is_private_host, outbound_client};
+
+pub const SIGNATURE_HEADER: &str = "X-Harmony-Signature";
+pub const EVENT_HEADER: &str = "X-Harmony-Event";
+pub const DELIVERY_HEADER: &str = "X-Harmony-Delivery";
+
+// Largest interaction response read, a callback is one message and fits well within it
+const MAX_INTERACTION_RESPONSE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutgoingWebhookError {
+  #[error("Invalid URL: {0}")]
+  InvalidUrl(String),
+  #[error("URL must use https")]
+  InsecureUrl,
+  #[error("URL must not point at a local or private address")]
+  PrivateAddress,
+}
+
+pub enum DeliveryOutcome {
+  Delivered(u16),
+  // Timeouts, connection errors, 408, 429 and server errors are tried again
+  Retry(Option<u16>, String),
+  Failed(Option<u16>, String),
+}
+
+pub struct DeliveryResult {
+  pub outcome: DeliveryOutcome,
+  pub duration: Duration,
+}
+
+// Sends signed requests to server webhooks and application interaction URLs
+#[derive(Clone)]
+pub struct OutgoingWebhooks {
+  client: reqwest::Client,
+  pub(crate) timeout: Duration,
+  allow_insecure_urls: bool,
+  pub wake: Wake,
+}
+
+impl OutgoingWebhooks {
+  pub fn new(config: &WebhooksConfig) -> Self {
+    let client = outbound_client(
+      Duration::from_secs(config.timeout_secs),
+      config.allow_insecure_urls,
+    );
+
+    Self {
+      client,
+      timeout: Duration::from_secs(config.timeout_secs),
+      allow_insecure_urls: config.allow_insecure_urls,
+      wake: Wake::default(),
+    }
+  }
+
+  // Plain http and local addresses are only accepted when allow_insecure_urls is set
+  pub fn validate_url(&self, url: &str) -> Result<Url, OutgoingWebhookError> {
+    let parsed = Url::parse(url).map_err(|e| OutgoingWebhookError::InvalidUrl(e.to_string()))?;
+
+    match parsed.scheme() {
+      "https" => {}
+      "http" if self.allow_insecure_urls => {}
+      "http" => return Err(OutgoingWebhookError::InsecureUrl),
+      other => {
+        return Err(OutgoingWebhookError::InvalidUrl(format!(
+          "unsupported scheme '{}'",
+          other
+        )));
+      }
+    }
+
+    let Some(host) = parsed.host_str() else {
+      return Err(OutgoingWebhookError::InvalidUrl("missing host".to_string()));
+    };
+
+    if !self.allow_insecure_urls && is_private_host(host) {
+      return Err(OutgoingWebhookError::PrivateAddress);
+    }
+
+    Ok(parsed)
+  }
+
+  pub async fn send(
+    &self,
+    url: &str,
+    secret: &str,
+    event: OutgoingWebhookEvent,
+    delivery_id: Uuid,
+    body: Vec<u8>,
+  ) -> DeliveryResult {
+    let started = Instant::now();
+
+    let response = self
+      .signed_post(url, secret, body)
+      .header(EVENT_HEADER, event.as_str())
+      .header(DELIVERY_HEADER, delivery_id.to_string())
+      .send()
+      .await;
+
+    let outcome = match response {
+      Ok(response) => {
+        let status = response.status();
+        let code = Some(status.as_u16());
+        match status.as_u16() {
+          200..=299 => DeliveryOutcome::Delivered(status.as_u16()),
+          408 | 429 | 500..=599 => DeliveryOutcome::Retry(code, status.to_string()),
+          _ => DeliveryOutcome::Failed(code, status.to_string()),
+        }
+      }
+      Err(e) => DeliveryOutcome::Retry(None, e.to_string()),
+    };
+
+    DeliveryResult {
+      outcome,
+      duration: started.elapsed(),
+    }
+  }
+
+  // Interaction requests are answered in the response body, which is returned as is
+  pub async fn post_interaction(
+    &self,
+    url: &str,
+    secret: &str,
+    body: Vec<u8>,
+  ) -> Result<Vec<u8>, String> {
+    let mut response = self
+      .signed_post(url, secret, body)
+      .send()
+      .await
+      .map_err(|e| e.to_string())?;
+
+    let status = response.status();
+    if !status.is_success() {
+      return Err(status.to_string());
+    }
+
+    let too_large = || {
+      format!(
+        "response is larger than {} bytes",
+        MAX_INTERACTION_RESPONSE_BYTES
+      )
+    };
+
+    if response
+      .content_length()
+      .is_some_and(|length| length > MAX_INTERACTION_RESPONSE_BYTES as u64)
+    {
+      return Err(too_large());
+    }
+
+    // Read in chunks so a receiver cannot make us buffer an unbounded body
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+      if body.len() + chunk.len() > MAX_INTERACTION_RESPONSE_BYTES {
+        return Err(too_large());
+      }
+      body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+  }
+
+  fn signed_post(&self, url: &str, secret: &str, body: Vec<u8>) -> reqwest::RequestBuilder {
+    let signature = sign(secret, chrono::Utc::now().timestamp(), &body);
+
+    self
+      .client
+      .post(url)
+      .header(header::CONTENT_TYPE, "application/json")
+      .header(header::USER_AGENT, "Harmony-Webhooks/1.0")
+      .header(SIGNATURE_HEADER, signature)
+      .body(body)
+  }
+}
+
+// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`, receivers should reject old timestamps
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+  mac.update(timestamp.to_string().as_bytes());
+  mac.update(b".");
+  mac.update(body);
+
+  let digest: String = mac
+    .finalize()
+    .into_bytes()
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect();
+
+  format!("t={},v1={}", timestamp, digest)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sign_matches_known_hmac() {
+    let signature = sign("whsec_test", 1700000000, br#"{"event":"member_joined"}"#);
+
+    assert_eq!(
+      signature,
+      "t=1700000000,v1=35812da9340041868a347e4c56421724bb3d90927d5a5b430e90fd0a480771a9"
+    );
+  }
+
+  #[test]
+  fn sign_covers_timestamp_and_body() {
+    let signature = sign("whsec_test", 1700000000, b"{}");
+
+    assert_ne!(signature, sign("whsec_test", 1700000001, b"{}"));
+    assert_ne!(signature, sign("whsec_test", 1700000000, b"[]"));
+    assert_ne!(signature, sign("whsec_other", 1700000000, b"{}"));
+  }
+}
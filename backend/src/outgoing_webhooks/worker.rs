@@ -0,0 +1,83 @@
+use sqlx::PgPool;
+
+use crate::models::OutgoingWebhookDelivery;
+use crate::outbox;
+use crate::outgoing_webhooks::{DeliveryOutcome, OutgoingWebhooks};
+use crate::services::OutgoingWebhookService;
+
+pub fn spawn(db: PgPool, webhooks: OutgoingWebhooks) {
+  outbox::worker::spawn(
+    db.clone(),
+    OutgoingWebhookService::OUTBOX,
+    webhooks.wake.clone(),
+    webhooks.timeout,
+    move |delivery| {
+      let db = db.clone();
+      let webhooks = webhooks.clone();
+      async move { process(&db, &webhooks, delivery).await }
+    },
+  );
+}
+
+async fn process(db: &PgPool, webhooks: &OutgoingWebhooks, delivery: OutgoingWebhookDelivery) {
+  let body = match serde_json::to_vec(&delivery.payload.0) {
+    Ok(body) => body,
+    Err(e) => {
+      tracing::error!("Failed to encode webhook delivery {}: {}", delivery.id, e);
+      return;
+    }
+  };
+
+  let result = webhooks
+    .send(
+      &delivery.url,
+      &delivery.secret,
+      delivery.event,
+      delivery.delivery_id,
+      body,
+    )
+    .await;
+  let duration_ms = result.duration.as_millis().min(i32::MAX as u128) as i32;
+
+  let (success, status_code, error) = match &result.outcome {
+    DeliveryOutcome::Delivered(status) => (true, Some(*status), None),
+    DeliveryOutcome::Retry(status, error) | DeliveryOutcome::Failed(status, error) => {
+      (false, *status, Some(error.as_str()))
+    }
+  };
+
+  if let Err(e) =
+    OutgoingWebhookService::record_attempt(db, &delivery, success, status_code, error, duration_ms)
+      .await
+  {
+    tracing::error!("Failed to log webhook delivery {}: {}", delivery.id, e);
+  }
+
+  let outbox = OutgoingWebhookService::OUTBOX;
+  let update = match result.outcome {
+    DeliveryOutcome::Delivered(_) => OutgoingWebhookService::complete_delivery(db, &delivery).await,
+    DeliveryOutcome::Retry(_, reason) if outbox.can_retry(delivery.attempts) => {
+      outbox
+        .retry(db, delivery.id, delivery.attempts, &reason)
+        .await
+    }
+    DeliveryOutcome::Retry(_, reason) | DeliveryOutcome::Failed(_, reason) => {
+      tracing::warn!("Dropping webhook delivery {}: {}", delivery.id, reason);
+      match OutgoingWebhookService::fail_delivery(db, &delivery).await {
+        Ok(true) => {
+          tracing::warn!(
+            "Disabled outgoing webhook {} after repeated failures",
+            delivery.webhook_id
+          );
+          Ok(())
+        }
+        Ok(false) => Ok(()),
+        Err(e) => Err(e),
+      }
+    }
+  };
+
+  if let Err(e) = update {
+    tracing::error!("Failed to update webhook delivery {}: {}", delivery.id, e);
+  }
+}
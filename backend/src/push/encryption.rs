@@ -0,0 +1,206 @@
+// Message encryption for Web Push (RFC 8291) using the aes128gcm content coding (RFC 8188)
+use aes_gcm::{
+  Aes128Gcm, KeyInit, Nonce,
+  aead::{Aead, Payload},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hkdf::Hkdf;
+use p256::{PublicKey, SecretKey, ecdh::diffie_hellman};
+use rand::{RngCore, rngs::OsRng};
+use sha2::Sha256;
+
+use crate::push::PushError;
+
+const RECORD_SIZE: u32 = 4096;
+
+// Record size minus the AEAD tag and padding delimiter
+pub const MAX_PLAINTEXT_LEN: usize = RECORD_SIZE as usize - 16 - 1;
+
+pub struct SubscriptionKeys {
+  pub p256dh: PublicKey,
+  pub auth: [u8; 16],
+}
+
+impl SubscriptionKeys {
+  pub fn decode(p256dh: &str, auth: &str) -> Result<Self, PushError> {
+    let p256dh = URL_SAFE_NO_PAD
+      .decode(p256dh.trim_end_matches('='))
+      .map_err(|e| PushError::InvalidKey(e.to_string()))?;
+    let p256dh = PublicKey::from_sec1_bytes(&p256dh)
+      .map_err(|_| PushError::InvalidKey("p256dh is not a valid P-256 point".to_string()))?;
+
+    let auth = URL_SAFE_NO_PAD
+      .decode(auth.trim_end_matches('='))
+      .map_err(|e| PushError::InvalidKey(e.to_string()))?
+      .try_into()
+      .map_err(|_| PushError::InvalidKey("auth must be 16 bytes".to_string()))?;
+
+    Ok(Self { p256dh, auth })
+  }
+}
+
+pub fn encrypt(keys: &SubscriptionKeys, plaintext: &[u8]) -> Result<Vec<u8>, PushError> {
+  let mut salt = [0u8; 16];
+  OsRng.fill_bytes(&mut salt);
+
+  encrypt_with(keys, plaintext, &SecretKey::random(&mut OsRng), &salt)
+}
+
+// Deterministic for a given application server key and salt, both must be fresh for every message
+fn encrypt_with(
+  keys: &SubscriptionKeys,
+  plaintext: &[u8],
+  as_secret: &SecretKey,
+  salt: &[u8; 16],
+) -> Result<Vec<u8>, PushError> {
+  if plaintext.len() > MAX_PLAINTEXT_LEN {
+    return Err(PushError::PayloadTooLarge);
+  }
+
+  let as_public = as_secret.public_key().to_sec1_bytes();
+  let ua_public = keys.p256dh.to_sec1_bytes();
+  let shared = diffie_hellman(as_secret.to_nonzero_scalar(), keys.p256dh.as_affine());
+
+  let (cek, nonce) = content_keys(
+    &keys.auth,
+    &ua_public,
+    &as_public,
+    shared.raw_secret_bytes(),
+    salt,
+  )?;
+
+  // A single record, terminated by the last-record delimiter
+  let mut record = plaintext.to_vec();
+  record.push(2);
+
+  let cipher = Aes128Gcm::new_from_slice(&cek).map_err(|_| PushError::Encryption)?;
+  let ciphertext = cipher
+    .encrypt(
+      Nonce::from_slice(&nonce),
+      Payload {
+        msg: &record,
+        aad: &[],
+      },
+    )
+    .map_err(|_| PushError::Encryption)?;
+
+  let mut body = Vec::with_capacity(16 + 4 + 1 + as_public.len() + ciphertext.len());
+  body.extend_from_slice(salt);
+  body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+  body.push(as_public.len() as u8);
+  body.extend_from_slice(&as_public);
+  body.extend_from_slice(&ciphertext);
+
+  Ok(body)
+}
+
+// The content encryption key and nonce, the same on both ends of the exchange
+fn content_keys(
+  auth: &[u8; 16],
+  ua_public: &[u8],
+  as_public: &[u8],
+  shared: &[u8],
+  salt: &[u8; 16],
+) -> Result<([u8; 16], [u8; 12]), PushError> {
+  let mut key_info = b"WebPush: info\0".to_vec();
+  key_info.extend_from_slice(ua_public);
+  key_info.extend_from_slice(as_public);
+
+  let mut ikm = [0u8; 32];
+  Hkdf::<Sha256>::new(Some(auth), shared)
+    .expand(&key_info, &mut ikm)
+    .map_err(|_| PushError::Encryption)?;
+
+  let hkdf = Hkdf::<Sha256>::new(Some(salt), &ikm);
+  let mut cek = [0u8; 16];
+  let mut nonce = [0u8; 12];
+  hkdf
+    .expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+    .map_err(|_| PushError::Encryption)?;
+  hkdf
+    .expand(b"Content-Encoding: nonce\0", &mut nonce)
+    .map_err(|_| PushError::Encryption)?;
+
+  Ok((cek, nonce))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  // RFC 8291 section 5
+  const PLAINTEXT: &[u8] = b"When I grow up, I want to be a watermelon";
+  const AS_PRIVATE: &str = "yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw";
+  const UA_PUBLIC: &str =
+    "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+  pub const UA_PRIVATE: &str = "q1dXpw3UpT5VOmu_cf_v6ih07Aems3njxI-JWgLcM94";
+  const AUTH_SECRET: &str = "BTBZMqHH6r4Tts7J_aSIgg";
+  const SALT: &str = "DGv6ra1nlYgDCS1FRnbzlw";
+  const BODY: &str = "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN";
+
+  pub fn rfc_keys() -> SubscriptionKeys {
+    SubscriptionKeys::decode(UA_PUBLIC, AUTH_SECRET).unwrap()
+  }
+
+  fn decode(value: &str) -> Vec<u8> {
+    URL_SAFE_NO_PAD.decode(value).unwrap()
+  }
+
+  // Undoes encrypt the way a user agent does, for a single record
+  pub fn decrypt(ua_private: &str, auth: &[u8; 16], body: &[u8]) -> Vec<u8> {
+    let ua_secret = SecretKey::from_slice(&decode(ua_private)).unwrap();
+    let salt: [u8; 16] = body[..16].try_into().unwrap();
+    let id_len = body[20] as usize;
+    let as_public = &body[21..21 + id_len];
+    let ciphertext = &body[21 + id_len..];
+
+    let as_key = PublicKey::from_sec1_bytes(as_public).unwrap();
+    let shared = diffie_hellman(ua_secret.to_nonzero_scalar(), as_key.as_affine());
+    let (cek, nonce) = content_keys(
+      auth,
+      &ua_secret.public_key().to_sec1_bytes(),
+      as_public,
+      shared.raw_secret_bytes(),
+      &salt,
+    )
+    .unwrap();
+
+    let mut record = Aes128Gcm::new_from_slice(&cek)
+      .unwrap()
+      .decrypt(Nonce::from_slice(&nonce), ciphertext)
+      .unwrap();
+    assert_eq!(record.pop(), Some(2));
+    record
+  }
+
+  #[test]
+  fn matches_rfc_8291_example() {
+    let as_secret = SecretKey::from_slice(&decode(AS_PRIVATE)).unwrap();
+    let salt: [u8; 16] = decode(SALT).try_into().unwrap();
+
+    let body = encrypt_with(&rfc_keys(), PLAINTEXT, &as_secret, &salt).unwrap();
+
+    assert_eq!(URL_SAFE_NO_PAD.encode(&body), BODY);
+  }
+
+  #[test]
+  fn round_trips_with_fresh_keys() {
+    let keys = rfc_keys();
+    let first = encrypt(&keys, PLAINTEXT).unwrap();
+    let second = encrypt(&keys, PLAINTEXT).unwrap();
+
+    assert_ne!(first, second);
+    assert_eq!(decrypt(UA_PRIVATE, &keys.auth, &first), PLAINTEXT);
+    assert_eq!(decrypt(UA_PRIVATE, &keys.auth, &second), PLAINTEXT);
+  }
+
+  #[test]
+  fn rejects_payloads_larger_than_a_record() {
+    let payload = vec![0u8; MAX_PLAINTEXT_LEN + 1];
+    assert!(matches!(
+      encrypt(&rfc_keys(), &payload),
+      Err(PushError::PayloadTooLarge)
+    ));
+    assert!(encrypt(&rfc_keys(), &payload[1..]).is_ok());
+  }
+}
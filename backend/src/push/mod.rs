@@ -0,0 +1,58 @@
+pub mod encryption;
+pub mod transport;
+pub mod vapid;
+pub mod worker;
+
+use std::sync::Arc;
+
+use crate::config::{PushConfig, PushTransportKind};
+use crate::outbox::Wake;
+
+pub use transport::PushTransport;
+pub use vapid::VapidKey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PushError {
+  #[error("Invalid key: {0}")]
+  InvalidKey(String),
+  #[error("Payload is too large to fit in a single record")]
+  PayloadTooLarge,
+  #[error("Encryption failed")]
+  Encryption,
+  #[error("Invalid endpoint: {0}")]
+  InvalidEndpoint(String),
+}
+
+// Everything needed to send Web Push messages, absent when VAPID is not configured
+#[derive(Clone)]
+pub struct WebPush {
+  pub vapid: Arc<VapidKey>,
+  pub transport: PushTransport,
+  pub wake: Wake,
+}
+
+impl WebPush {
+  pub fn new(vapid: VapidKey, transport: PushTransport) -> Self {
+    Self {
+      vapid: Arc::new(vapid),
+      transport,
+      wake: Wake::default(),
+    }
+  }
+
+  pub fn from_config(config: &PushConfig) -> anyhow::Result<Option<Self>> {
+    let (Some(private_key), Some(subject)) = (&config.vapid_private_key, &config.vapid_subject)
+    else {
+      return Ok(None);
+    };
+
+    let vapid = VapidKey::from_base64(private_key, subject.clone())?;
+
+    let transport = match config.transport {
+      PushTransportKind::Http => PushTransport::http(config.allow_insecure_endpoints),
+      PushTransportKind::Log => PushTransport::Log,
+    };
+
+    Ok(Some(Self::new(vapid, transport)))
+  }
+}
@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use reqwest::{StatusCode, header};
+
+use crate::push::{PushError, VapidKey, encryption};
+use crate::utils::net::outbound_client;
+
+// How long the push service should hold a message for an unreachable device
+const TTL_SECS: u32 = 24 * 60 * 60;
+
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub enum PushOutcome {
+  Delivered,
+  // The subscription expired or was revoked and should be removed
+  Gone,
+  Retry(String),
+  Failed(String),
+}
+
+// The http transport also works against a local mock push endpoint, the log transport
+// only records what would have been sent
+#[derive(Clone)]
+pub enum PushTransport {
+  Http(reqwest::Client),
+  Log,
+}
+
+impl PushTransport {
+  // Endpoints come from browsers, so they get the same checks as webhook URLs
+  pub fn http(allow_private: bool) -> Self {
+    PushTransport::Http(outbound_client(REQUEST_TIMEOUT, allow_private))
+  }
+
+  pub async fn send(
+    &self,
+    vapid: &VapidKey,
+    endpoint: &str,
+    keys: &encryption::SubscriptionKeys,
+    payload: &[u8],
+  ) -> Result<PushOutcome, PushError> {
+    match self {
+      PushTransport::Http(client) => {
+        let body = encryption::encrypt(keys, payload)?;
+        let authorization = vapid.authorization(endpoint)?;
+
+        let response = client
+          .post(endpoint)
+          .header(header::AUTHORIZATION, authorization)
+          .header(header::CONTENT_ENCODING, "aes128gcm")
+          .header(header::CONTENT_TYPE, "application/octet-stream")
+          .header("TTL", TTL_SECS.to_string())
+          .header("Urgency", "high")
+          .body(body)
+          .send()
+          .await;
+
+        let response = match response {
+          Ok(response) => response,
+          Err(e) => return Ok(PushOutcome::Retry(e.to_string())),
+        };
+
+        let status = response.status();
+        Ok(match status {
+          s if s.is_success() => PushOutcome::Delivered,
+          StatusCode::NOT_FOUND | StatusCode::GONE => PushOutcome::Gone,
+          StatusCode::TOO_MANY_REQUESTS => PushOutcome::Retry(status.to_string()),
+          s if s.is_server_error() => PushOutcome::Retry(status.to_string()),
+          _ => PushOutcome::Failed(status.to_string()),
+        })
+      }
+      PushTransport::Log => {
+        tracing::info!("Push to {}: {}", endpoint, String::from_utf8_lossy(payload));
+        Ok(PushOutcome::Delivered)
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, header::LOCATION},
+    response::IntoResponse,
+    routing::post,
+  };
+
+  use super::*;
+  use crate::push::encryption::tests::{UA_PRIVATE, decrypt, rfc_keys};
+  use crate::push::vapid::tests::test_key;
+
+  type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+  // A local push service that answers every request with the given status
+  async fn mock_push_service(status: StatusCode) -> (String, Received) {
+    let received = Received::default();
+    let app = Router::new()
+      .route(
+        "/push/{device}",
+        post(
+          move |State(received): State<Received>, headers: HeaderMap, body: Bytes| async move {
+            received.lock().unwrap().push((headers, body));
+            (status, [(LOCATION, "/push/elsewhere")]).into_response()
+          },
+        ),
+      )
+      .with_state(received.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (format!("http://127.0.0.1:{}/push/device", port), received)
+  }
+
+  async fn send(transport: &PushTransport, endpoint: &str) -> PushOutcome {
+    transport
+      .send(&test_key(), endpoint, &rfc_keys(), br#"{"title":"Hi"}"#)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn delivers_an_encrypted_message() {
+    let (endpoint, received) = mock_push_service(StatusCode::CREATED).await;
+
+    let outcome = send(&PushTransport::http(true), &endpoint).await;
+    assert!(matches!(outcome, PushOutcome::Delivered));
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+
+    let (headers, body) = &received[0];
+    assert_eq!(headers[header::CONTENT_ENCODING], "aes128gcm");
+    assert_eq!(headers["ttl"], TTL_SECS.to_string());
+    assert!(
+      headers[header::AUTHORIZATION]
+        .to_str()
+        .unwrap()
+        .starts_with("vapid t=")
+    );
+    assert_eq!(
+      decrypt(UA_PRIVATE, &rfc_keys().auth, body),
+      br#"{"title":"Hi"}"#
+    );
+  }
+
+  #[tokio::test]
+  async fn maps_push_service_responses() {
+    for (status, expected) in [
+      (StatusCode::NOT_FOUND, "gone"),
+      (StatusCode::GONE, "gone"),
+      (StatusCode::TOO_MANY_REQUESTS, "retry"),
+      (StatusCode::SERVICE_UNAVAILABLE, "retry"),
+      (StatusCode::BAD_REQUEST, "failed"),
+      (StatusCode::PAYLOAD_TOO_LARGE, "failed"),
+    ] {
+      let (endpoint, _) = mock_push_service(status).await;
+      let outcome = match send(&PushTransport::http(true), &endpoint).await {
+        PushOutcome::Delivered => "delivered",
+        PushOutcome::Gone => "gone",
+        PushOutcome::Retry(_) => "retry",
+        PushOutcome::Failed(_) => "failed",
+      };
+      assert_eq!(outcome, expected, "for {}", status);
+    }
+  }
+
+  #[tokio::test]
+  async fn does_not_follow_redirects() {
+    let (endpoint, received) = mock_push_service(StatusCode::TEMPORARY_REDIRECT).await;
+
+    let outcome = send(&PushTransport::http(true), &endpoint).await;
+
+    assert!(matches!(outcome, PushOutcome::Failed(_)));
+    assert_eq!(received.lock().unwrap().len(), 1);
+  }
+
+  #[tokio::test]
+  async fn refuses_names_that_resolve_to_private_addresses() {
+    let (endpoint, received) = mock_push_service(StatusCode::CREATED).await;
+    let endpoint = endpoint.replace("127.0.0.1", "localhost");
+
+    let outcome = send(&PushTransport::http(false), &endpoint).await;
+
+    assert!(matches!(outcome, PushOutcome::Retry(_)));
+    assert!(received.lock().unwrap().is_empty());
+  }
+}
@@ -0,0 +1,134 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use p256::ecdsa::{Signature, SigningKey, signature::Signer};
+use serde_json::json;
+
+use crate::push::PushError;
+
+// Tokens may be valid for at most 24 hours
+const TOKEN_LIFETIME_SECS: i64 = 12 * 60 * 60;
+
+pub struct VapidKey {
+  signing_key: SigningKey,
+  public_key: String,
+  subject: String,
+}
+
+impl VapidKey {
+  // Takes the raw private scalar, base64url encoded, as generated by common web-push tooling
+  pub fn from_base64(private_key: &str, subject: String) -> Result<Self, PushError> {
+    let bytes = URL_SAFE_NO_PAD
+      .decode(private_key.trim().trim_end_matches('='))
+      .map_err(|e| PushError::InvalidKey(e.to_string()))?;
+    let signing_key =
+      SigningKey::from_slice(&bytes).map_err(|e| PushError::InvalidKey(e.to_string()))?;
+
+    let public_key = URL_SAFE_NO_PAD.encode(
+      signing_key
+        .verifying_key()
+        .to_encoded_point(false)
+        .as_bytes(),
+    );
+
+    Ok(Self {
+      signing_key,
+      public_key,
+      subject,
+    })
+  }
+
+  // Uncompressed P-256 point, passed to pushManager.subscribe as applicationServerKey
+  pub fn public_key(&self) -> &str {
+    &self.public_key
+  }
+
+  // Builds the `Authorization` header value for a push service endpoint
+  pub fn authorization(&self, endpoint: &str) -> Result<String, PushError> {
+    let url =
+      reqwest::Url::parse(endpoint).map_err(|e| PushError::InvalidEndpoint(e.to_string()))?;
+    let audience = url.origin().ascii_serialization();
+
+    let header = URL_SAFE_NO_PAD.encode(json!({ "typ": "JWT", "alg": "ES256" }).to_string());
+    let claims = URL_SAFE_NO_PAD.encode(
+      json!({
+        "aud": audience,
+        "exp": chrono::Utc::now().timestamp() + TOKEN_LIFETIME_SECS,
+        "sub": self.subject,
+      })
+      .to_string(),
+    );
+
+    let signing_input = format!("{}.{}", header, claims);
+    let signature: Signature = self.signing_key.sign(signing_input.as_bytes());
+
+    Ok(format!(
+      "vapid t={}.{}, k={}",
+      signing_input,
+      URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+      self.public_key
+    ))
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+  use p256::ecdsa::{VerifyingKey, signature::Verifier};
+
+  // The application server key of the RFC 8291 example
+  const PRIVATE_KEY: &str = "yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw";
+  const PUBLIC_KEY: &str =
+    "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8";
+
+  pub fn test_key() -> VapidKey {
+    VapidKey::from_base64(PRIVATE_KEY, "mailto:admin@example.com".to_string()).unwrap()
+  }
+
+  fn decode_json(value: &str) -> serde_json::Value {
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(value).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn derives_the_uncompressed_public_key() {
+    assert_eq!(test_key().public_key(), PUBLIC_KEY);
+  }
+
+  #[test]
+  fn authorization_is_a_signed_token_for_the_push_service_origin() {
+    let authorization = test_key()
+      .authorization("https://push.example.net:8443/send/abc?topic=1")
+      .unwrap();
+
+    let (token, public_key) = authorization
+      .strip_prefix("vapid t=")
+      .and_then(|rest| rest.split_once(", k="))
+      .unwrap();
+    assert_eq!(public_key, PUBLIC_KEY);
+
+    let (signing_input, signature) = token.rsplit_once('.').unwrap();
+    let (header, claims) = signing_input.split_once('.').unwrap();
+
+    assert_eq!(decode_json(header), json!({ "typ": "JWT", "alg": "ES256" }));
+
+    let claims = decode_json(claims);
+    assert_eq!(claims["aud"], "https://push.example.net:8443");
+    assert_eq!(claims["sub"], "mailto:admin@example.com");
+    let expires_in = claims["exp"].as_i64().unwrap() - chrono::Utc::now().timestamp();
+    assert!(expires_in > 0 && expires_in <= 24 * 60 * 60);
+
+    let verifying_key =
+      VerifyingKey::from_sec1_bytes(&URL_SAFE_NO_PAD.decode(public_key).unwrap()).unwrap();
+    let signature = Signature::from_slice(&URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+    assert!(
+      verifying_key
+        .verify(signing_input.as_bytes(), &signature)
+        .is_ok()
+    );
+  }
+
+  #[test]
+  fn rejects_bad_keys_and_endpoints() {
+    assert!(VapidKey::from_base64("not a key", "mailto:a@b.c".to_string()).is_err());
+    assert!(VapidKey::from_base64("AAAA", "mailto:a@b.c".to_string()).is_err());
+    assert!(test_key().authorization("not a url").is_err());
+  }
+}
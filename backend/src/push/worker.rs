@@ -0,0 +1,63 @@
+use sqlx::PgPool;
+
+use crate::models::PushDelivery;
+use crate::outbox;
+use crate::push::{
+  WebPush,
+  encryption::SubscriptionKeys,
+  transport::{PushOutcome, REQUEST_TIMEOUT},
+};
+use crate::services::PushService;
+
+pub fn spawn(db: PgPool, web_push: WebPush) {
+  outbox::worker::spawn(
+    db.clone(),
+    PushService::OUTBOX,
+    web_push.wake.clone(),
+    REQUEST_TIMEOUT,
+    move |delivery| {
+      let db = db.clone();
+      let web_push = web_push.clone();
+      async move { process(&db, &web_push, delivery).await }
+    },
+  );
+}
+
+async fn process(db: &PgPool, web_push: &WebPush, delivery: PushDelivery) {
+  let outcome = match SubscriptionKeys::decode(&delivery.p256dh, &delivery.auth) {
+    Ok(keys) => match serde_json::to_vec(&delivery.payload.0) {
+      Ok(payload) => web_push
+        .transport
+        .send(&web_push.vapid, &delivery.endpoint, &keys, &payload)
+        .await
+        .unwrap_or_else(|e| PushOutcome::Failed(e.to_string())),
+      Err(e) => PushOutcome::Failed(e.to_string()),
+    },
+    Err(e) => PushOutcome::Failed(e.to_string()),
+  };
+
+  let outbox = PushService::OUTBOX;
+  let result = match outcome {
+    PushOutcome::Delivered => outbox.complete(db, delivery.id).await,
+    PushOutcome::Gone => {
+      tracing::debug!(
+        "Removing expired push subscription {}",
+        delivery.subscription_id
+      );
+      PushService::remove_subscription(db, delivery.subscription_id).await
+    }
+    PushOutcome::Retry(reason) if outbox.can_retry(delivery.attempts) => {
+      outbox
+        .retry(db, delivery.id, delivery.attempts, &reason)
+        .await
+    }
+    PushOutcome::Retry(reason) | PushOutcome::Failed(reason) => {
+      tracing::warn!("Dropping push delivery {}: {}", delivery.id, reason);
+      outbox.complete(db, delivery.id).await
+    }
+  };
+
+  if let Err(e) = result {
+    tracing::error!("Failed to update push delivery {}: {}", delivery.id, e);
+  }
+}
@@ -0,0 +1,216 @@
+use std::{
+  collections::HashMap,
+  sync::Arc,
+  time::{Duration, Instant},
+};
+
+use sqlx::PgPool;
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+  pub bucket: &'static str,
+  pub requests: u32,
+  pub window: Duration,
+}
+
+impl RateLimit {
+  pub const fn new(bucket: &'static str, requests: u32, window_secs: u64) -> Self {
+    Self {
+      bucket,
+      requests,
+      window: Duration::from_secs(window_secs),
+    }
+  }
+}
+
+// Applied to every authenticated request on top of any route bucket
+pub const GLOBAL: RateLimit = RateLimit::new("global", 120, 60);
+
+// Per-route buckets, public auth routes are keyed by client IP
+pub fn route_limit(method: &str, path: &str) -> Option<RateLimit> {
+  let limit = match (method, path) {
+    ("POST", "/api/auth/login") => RateLimit::new("auth_login", 5, 60),
+    ("POST", "/api/auth/login/mfa") => RateLimit::new("auth_login_mfa", 5, 60),
+    ("POST", "/api/auth/register") => RateLimit::new("auth_register", 5, 60 * 60),
+    ("POST", "/api/auth/forgot-password") => RateLimit::new("auth_forgot_password", 3, 60 * 60),
+    ("POST", "/api/auth/reset-password") => RateLimit::new("auth_reset_password", 10, 60 * 60),
+    ("POST", "/api/auth/verify-email") => RateLimit::new("auth_verify_email", 10, 60 * 60),
+    ("POST", "/api/auth/oidc/{provider}/authorize") => RateLimit::new("oidc_authorize", 10, 60),
+    ("POST", "/api/auth/oidc/{provider}/callback") => RateLimit::new("oidc_callback", 10, 60),
+    ("POST", "/api/auth/oidc/register") => RateLimit::new("auth_register", 5, 60 * 60),
+    ("POST", "/api/channels/{channel_id}/messages") => RateLimit::new("message_create", 10, 10),
+    ("POST", "/api/webhooks/{webhook_id}/{token}") => RateLimit::new("webhook_execute", 30, 60),
+    ("POST", "/api/interactions") => RateLimit::new("interaction_create", 10, 10),
+    ("POST", "/api/interactions/{interaction_id}/{token}/callback")
+    | ("PATCH", "/api/interactions/{interaction_id}/{token}/response") => {
+      RateLimit::new("interaction_callback", 60, 60)
+    }
+    ("POST", "/api/servers") => RateLimit::new("server_create", 10, 60 * 60),
+    ("POST", "/api/applications") => RateLimit::new("application_create", 5, 60 * 60),
+    ("POST", "/api/dms") | ("POST", "/api/dms/group") => RateLimit::new("dm_create", 10, 60),
+    ("POST", "/api/friends") | ("POST", "/api/users/{user_id}/friend") => {
+      RateLimit::new("friend_request", 20, 60 * 60)
+    }
+    ("POST", "/api/me/identities/{provider}") | ("POST", "/api/me/reauthenticate/{provider}") => {
+      RateLimit::new("oidc_authorize", 10, 60)
+    }
+    ("PATCH", "/api/me/account") | ("POST", "/api/me/password") => {
+      RateLimit::new("account_update", 5, 60 * 60)
+    }
+    _ => return None,
+  };
+
+  Some(limit)
+}
+
+#[derive(Clone)]
+pub enum RateLimiter {
+  Memory(Arc<Mutex<HashMap<String, (Instant, u32)>>>),
+  Postgres(PgPool),
+}
+
+impl RateLimiter {
+  pub fn memory() -> Self {
+    RateLimiter::Memory(Arc::new(Mutex::new(HashMap::new())))
+  }
+
+  pub fn postgres(db: PgPool) -> Self {
+    RateLimiter::Postgres(db)
+  }
+
+  // Counts a hit against `limit` for `key`, returning how long to wait when over the limit
+  pub async fn check(&self, limit: RateLimit, key: &str) -> Result<(), Duration> {
+    let key = format!("{}:{}", limit.bucket, key);
+
+    match self {
+      RateLimiter::Memory(buckets) => {
+        let mut buckets = buckets.lock().await;
+        let now = Instant::now();
+
+        let (window_start, count) = buckets.entry(key).or_insert((now, 0));
+        if now.duration_since(*window_start) >= limit.window {
+          *window_start = now;
+          *count = 0;
+        }
+        *count += 1;
+
+        if *count > limit.requests {
+          return Err(limit.window - now.duration_since(*window_start));
+        }
+      }
+      RateLimiter::Postgres(db) => {
+        let window_secs = limit.window.as_secs_f64();
+
+        let result: Result<(i32, f64), sqlx::Error> = sqlx::query_as(
+          r#"
+          INSERT INTO rate_limits (key, window_start, count)
+          VALUES ($1, NOW(), 1)
+          ON CONFLICT (key) DO UPDATE
+          SET
+            window_start = CASE
+              WHEN rate_limits.window_start + make_interval(secs => $2) <= NOW() THEN NOW()
+              ELSE rate_limits.window_start
+            END,
+            count = CASE
+              WHEN rate_limits.window_start + make_interval(secs => $2) <= NOW() THEN 1
+              ELSE rate_limits.count + 1
+            END
+          RETURNING count, EXTRACT(EPOCH FROM (window_start + make_interval(secs => $2) - NOW()))::float8
+          "#,
+        )
+        .bind(&key)
+        .bind(window_secs)
+        .fetch_one(db)
+        .await;
+
+        match result {
+          Ok((count, remaining)) if count as u32 > limit.requests => {
+            return Err(Duration::from_secs_f64(remaining.max(0.0)));
+          }
+          Ok(_) => {}
+          // Failing open keeps the API usable if the limiter table is unavailable
+          Err(e) => tracing::error!("Rate limit check failed: {}", e),
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  // Periodically drops expired windows so the store does not grow without bound
+  pub fn spawn_cleanup(&self) {
+    let limiter = self.clone();
+
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(Duration::from_secs(60));
+      loop {
+        interval.tick().await;
+
+        match &limiter {
+          RateLimiter::Memory(buckets) => {
+            // Longest window in use is an hour
+            let mut buckets = buckets.lock().await;
+            buckets
+              .retain(|_, (window_start, _)| window_start.elapsed() < Duration::from_secs(60 * 60));
+          }
+          RateLimiter::Postgres(db) => {
+            if let Err(e) =
+              sqlx::query("DELETE FROM rate_limits WHERE window_start < NOW() - INTERVAL '1 hour'")
+                .execute(db)
+                .await
+            {
+              tracing::error!("Failed to clean up rate limits: {}", e);
+            }
+          }
+        }
+      }
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn routes_are_keyed_by_matched_path() {
+    let limit = route_limit("POST", "/api/channels/{channel_id}/messages").unwrap();
+    assert_eq!(limit.bucket, "message_create");
+    assert_eq!(limit.requests, 10);
+    assert_eq!(limit.window, Duration::from_secs(10));
+
+    // Concrete paths never match, the middleware passes the route template
+    assert!(route_limit("POST", "/api/channels/7f3e/messages").is_none());
+  }
+
+  #[test]
+  fn routes_share_buckets() {
+    let bucket = |method, path| route_limit(method, path).map(|limit| limit.bucket);
+
+    assert_eq!(bucket("POST", "/api/auth/register"), Some("auth_register"));
+    assert_eq!(
+      bucket("POST", "/api/auth/oidc/register"),
+      Some("auth_register")
+    );
+    assert_eq!(bucket("POST", "/api/dms/group"), Some("dm_create"));
+    assert_eq!(
+      bucket(
+        "PATCH",
+        "/api/interactions/{interaction_id}/{token}/response"
+      ),
+      Some("interaction_callback")
+    );
+    assert_eq!(
+      bucket("POST", "/api/me/reauthenticate/{provider}"),
+      Some("oidc_authorize")
+    );
+  }
+
+  #[test]
+  fn method_is_part_of_the_key() {
+    assert!(route_limit("POST", "/api/auth/login").is_some());
+    assert!(route_limit("GET", "/api/auth/login").is_none());
+    assert!(route_limit("GET", "/api/channels/{channel_id}/messages").is_none());
+  }
+}
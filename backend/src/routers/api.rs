@@ -1,15 +1,74 @@
 use axum::{
   Router,
-  routing::{delete, get, patch, post},
+  routing::{delete, get, patch, post, put},
 };
 
 use crate::{AppState, handlers, middleware};
 
 // Protected api routes
-pub fn routes() -> Router<AppState> {
+pub fn routes(state: AppState) -> Router<AppState> {
   Router::new()
     .route("/me/profile", get(handlers::profile::get_my_profile))
     .route("/me/profile", patch(handlers::profile::update_my_profile))
+    .route(
+      "/me/notification-settings",
+      get(handlers::notification::get_notification_settings),
+    )
+    .route(
+      "/me/notification-settings/servers/{server_id}",
+      patch(handlers::notification::update_server_notification_settings),
+    )
+    .route(
+      "/me/notification-settings/channels/{channel_id}",
+      patch(handlers::notification::update_channel_notification_settings),
+    )
+    .route("/me", delete(handlers::account::delete_account))
+    .route("/me/account", patch(handlers::account::update_account))
+    .route("/me/password", post(handlers::account::change_password))
+    .route("/me/mfa/totp", post(handlers::mfa::enroll_totp))
+    .route("/me/mfa/totp", delete(handlers::mfa::disable_totp))
+    .route("/me/mfa/totp/confirm", post(handlers::mfa::confirm_totp))
+    .route(
+      "/me/mfa/backup-codes",
+      post(handlers::mfa::regenerate_backup_codes),
+    )
+    .route("/me/identities", get(handlers::oidc::get_identities))
+    .route(
+      "/me/identities/{provider}",
+      post(handlers::oidc::connect_identity),
+    )
+    .route(
+      "/me/identities/{provider}",
+      delete(handlers::oidc::disconnect_identity),
+    )
+    .route(
+      "/me/identities/{provider}/callback",
+      post(handlers::oidc::connect_identity_callback),
+    )
+    .route(
+      "/me/reauthenticate/{provider}",
+      post(handlers::oidc::reauthenticate),
+    )
+    .route(
+      "/me/reauthenticate/{provider}/callback",
+      post(handlers::oidc::reauthenticate_callback),
+    )
+    .route(
+      "/me/push-subscriptions",
+      get(handlers::push::get_push_subscriptions),
+    )
+    .route(
+      "/me/push-subscriptions",
+      post(handlers::push::create_push_subscription),
+    )
+    .route(
+      "/me/push-subscriptions/{subscription_id}",
+      delete(handlers::push::delete_push_subscription),
+    )
+    .route(
+      "/push/vapid-public-key",
+      get(handlers::push::get_vapid_public_key),
+    )
     // Users
     .route("/users/search", get(handlers::friendship::search_users))
     .route(
@@ -64,9 +123,17 @@ pub fn routes() -> Router<AppState> {
       get(handlers::friendship::get_blocked_users),
     )
     // Servers
+    .route(
+      "/discovery/servers",
+      get(handlers::server::discover_servers),
+    )
     .route("/servers", post(handlers::server::create_server))
     .route("/servers", get(handlers::server::get_user_servers))
     .route("/servers/{server_id}", get(handlers::server::get_server))
+    .route(
+      "/servers/{server_id}/join",
+      post(handlers::server::join_server),
+    )
     .route(
       "/servers/{server_id}",
       delete(handlers::server::delete_server),
@@ -75,10 +142,60 @@ pub fn routes() -> Router<AppState> {
       "/servers/{server_id}",
       patch(handlers::server::update_server),
     )
+    .route(
+      "/servers/{server_id}/transfer-ownership",
+      post(handlers::server::transfer_server_ownership),
+    )
     .route(
       "/servers/{server_id}/members",
       get(handlers::server::get_server_members),
     )
+    .route("/servers/{server_id}/bans", get(handlers::ban::get_bans))
+    .route(
+      "/servers/{server_id}/bans/{user_id}",
+      put(handlers::ban::ban_member),
+    )
+    .route(
+      "/servers/{server_id}/bans/{user_id}",
+      delete(handlers::ban::unban_member),
+    )
+    .route(
+      "/servers/{server_id}/members/{user_id}/voice",
+      patch(handlers::voice::update_member_voice),
+    )
+    .route(
+      "/servers/{server_id}/voice-states",
+      get(handlers::voice::get_voice_states),
+    )
+    .route(
+      "/servers/{server_id}/commands",
+      get(handlers::interaction::get_server_commands),
+    )
+    .route(
+      "/servers/{server_id}/audit-log",
+      get(handlers::audit_log::get_audit_log),
+    )
+    // Server Emojis
+    .route(
+      "/servers/{server_id}/emojis",
+      post(handlers::emoji::create_emoji),
+    )
+    .route(
+      "/servers/{server_id}/emojis",
+      get(handlers::emoji::get_server_emojis),
+    )
+    .route(
+      "/emojis/{emoji_id}/image",
+      get(handlers::emoji::get_emoji_image),
+    )
+    .route(
+      "/servers/{server_id}/emojis/{emoji_id}",
+      patch(handlers::emoji::update_emoji),
+    )
+    .route(
+      "/servers/{server_id}/emojis/{emoji_id}",
+      delete(handlers::emoji::delete_emoji),
+    )
     // Server Channels
     .route(
       "/servers/{server_id}/channels",
@@ -92,7 +209,21 @@ pub fn routes() -> Router<AppState> {
     .route("/dms", post(handlers::dm::create_dm))
     .route("/dms", get(handlers::dm::get_user_dms))
     .route("/dms/group", post(handlers::dm::create_group_dm))
+    .route("/dms/{channel_id}", patch(handlers::dm::update_group_dm))
+    .route("/dms/{channel_id}", delete(handlers::dm::leave_group_dm))
+    .route(
+      "/dms/{channel_id}/recipients/{user_id}",
+      post(handlers::dm::add_group_dm_recipient),
+    )
+    .route(
+      "/dms/{channel_id}/recipients/{user_id}",
+      delete(handlers::dm::remove_group_dm_recipient),
+    )
     // Channels
+    .route(
+      "/channels/{channel_id}",
+      patch(handlers::channel::update_channel),
+    )
     .route(
       "/channels/{channel_id}",
       delete(handlers::channel::delete_channel),
@@ -106,6 +237,110 @@ pub fn routes() -> Router<AppState> {
       "/channels/{channel_id}/messages",
       get(handlers::message::get_messages),
     )
+    .route(
+      "/channels/{channel_id}/messages/{message_id}",
+      patch(handlers::message::update_message),
+    )
+    .route(
+      "/channels/{channel_id}/messages/{message_id}",
+      delete(handlers::message::delete_message),
+    )
+    // Webhooks
+    .route(
+      "/channels/{channel_id}/webhooks",
+      post(handlers::webhook::create_webhook),
+    )
+    .route(
+      "/channels/{channel_id}/webhooks",
+      get(handlers::webhook::get_channel_webhooks),
+    )
+    .route(
+      "/webhooks/{webhook_id}",
+      delete(handlers::webhook::delete_webhook),
+    )
+    // Outgoing webhooks
+    .route(
+      "/servers/{server_id}/outgoing-webhooks",
+      post(handlers::outgoing_webhook::create_outgoing_webhook),
+    )
+    .route(
+      "/servers/{server_id}/outgoing-webhooks",
+      get(handlers::outgoing_webhook::get_outgoing_webhooks),
+    )
+    .route(
+      "/outgoing-webhooks/{webhook_id}",
+      patch(handlers::outgoing_webhook::update_outgoing_webhook),
+    )
+    .route(
+      "/outgoing-webhooks/{webhook_id}",
+      delete(handlers::outgoing_webhook::delete_outgoing_webhook),
+    )
+    .route(
+      "/outgoing-webhooks/{webhook_id}/deliveries",
+      get(handlers::outgoing_webhook::get_outgoing_webhook_deliveries),
+    )
+    // Applications and bots
+    .route(
+      "/applications",
+      post(handlers::application::create_application),
+    )
+    .route(
+      "/applications",
+      get(handlers::application::get_applications),
+    )
+    .route(
+      "/applications/{application_id}",
+      get(handlers::application::get_application),
+    )
+    .route(
+      "/applications/{application_id}",
+      patch(handlers::application::update_application),
+    )
+    .route(
+      "/applications/{application_id}",
+      delete(handlers::application::delete_application),
+    )
+    .route(
+      "/applications/{application_id}/bot/reset-token",
+      post(handlers::application::reset_bot_token),
+    )
+    .route(
+      "/applications/{application_id}/authorize",
+      post(handlers::application::authorize_bot),
+    )
+    .route(
+      "/applications/{application_id}/interactions-endpoint",
+      put(handlers::application::set_interactions_endpoint),
+    )
+    .route(
+      "/applications/{application_id}/interactions-endpoint",
+      delete(handlers::application::clear_interactions_endpoint),
+    )
+    // Commands and interactions
+    .route(
+      "/applications/{application_id}/commands",
+      post(handlers::interaction::create_global_command),
+    )
+    .route(
+      "/applications/{application_id}/commands",
+      get(handlers::interaction::get_global_commands),
+    )
+    .route(
+      "/applications/{application_id}/commands/{command_id}",
+      delete(handlers::interaction::delete_command),
+    )
+    .route(
+      "/applications/{application_id}/servers/{server_id}/commands",
+      post(handlers::interaction::create_server_command),
+    )
+    .route(
+      "/applications/{application_id}/servers/{server_id}/commands",
+      get(handlers::interaction::get_server_application_commands),
+    )
+    .route(
+      "/interactions",
+      post(handlers::interaction::create_interaction),
+    )
     // Organization
     .route(
       "/organization/servers",
@@ -131,6 +366,14 @@ pub fn routes() -> Router<AppState> {
       "/organization/folders/{folder_id}",
       delete(handlers::organization::delete_folder),
     )
+    // Rate limiting runs after auth so requests are keyed by user
+    .layer(axum::middleware::from_fn_with_state(
+      state.clone(),
+      middleware::rate_limit_middleware,
+    ))
     // Auth middleware
-    .layer(axum::middleware::from_fn(middleware::auth_middleware))
+    .layer(axum::middleware::from_fn_with_state(
+      state,
+      middleware::auth_middleware,
+    ))
 }
@@ -0,0 +1,397 @@
+// backend/src/services/account.rs
+use crate::config::JwtConfig;
+use crate::models::{
+  AuditLogAction, ChangePasswordRequest, DmChannel, Server, UpdateAccountRequest, User,
+  UserTokenKind,
+};
+use crate::services::{AuditLogService, AuthService, ChannelService};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+const USERNAME_CHANGE_COOLDOWN_HOURS: i64 = 24;
+
+// What deleting an account changed, for the events sent once it is committed
+pub struct AccountDeletion {
+  // Group DMs that were left, with whether the group still exists
+  pub group_dms: Vec<(Uuid, bool)>,
+  pub bot_user_ids: Vec<Uuid>,
+}
+
+pub struct AccountService;
+
+impl AccountService {
+  // Accounts created through a provider have no usable password, they prove themselves with a
+  // token from /api/me/reauthenticate/{provider} instead
+  pub async fn verify_current_password(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    password: Option<&str>,
+    reauthentication_token: Option<&str>,
+  ) -> AppResult<()> {
+    if let Some(token) = reauthentication_token {
+      let token_user_id =
+        AuthService::consume_user_token(db, jwt, token, UserTokenKind::Reauthentication).await?;
+
+      if token_user_id != user_id {
+        return Err(AppError::BadRequest(
+          ErrorCode::InvalidOrExpiredToken,
+          "Invalid or expired token".to_string(),
+        ));
+      }
+
+      return Ok(());
+    }
+
+    let password_set: bool = sqlx::query_scalar("SELECT password_set FROM users WHERE id = $1")
+      .bind(user_id)
+      .fetch_one(db)
+      .await?;
+
+    if !password_set {
+      return Err(AppError::Forbidden(
+        ErrorCode::ReauthenticationRequired,
+        "Sign in with your provider again to confirm this change".to_string(),
+      ));
+    }
+
+    let password = password.ok_or_else(|| {
+      AppError::invalid_field(
+        "password",
+        ErrorCode::FieldRequired,
+        "Current password is required",
+      )
+    })?;
+
+    let user = AuthService::get_user_by_id(db, user_id).await?;
+
+    if !AuthService::verify_password(password, &user.password_hash)? {
+      return Err(AppError::Forbidden(
+        ErrorCode::InvalidPassword,
+        "Current password is incorrect".to_string(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Returns the updated user and whether the email changed and needs verifying again
+  pub async fn update_account(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    req: UpdateAccountRequest,
+  ) -> AppResult<(User, bool)> {
+    let current = AuthService::get_user_by_id(db, user_id).await?;
+
+    let username = req
+      .username
+      .map(|username| username.trim().to_lowercase())
+      .filter(|username| *username != current.username);
+    let email = req
+      .email
+      .map(|email| email.trim().to_string())
+      .filter(|email| *email != current.email);
+
+    if username.as_deref() == Some("") {
+      return Err(AppError::invalid_field(
+        "username",
+        ErrorCode::FieldRequired,
+        "Username cannot be empty",
+      ));
+    }
+    if email.as_deref() == Some("") {
+      return Err(AppError::invalid_field(
+        "email",
+        ErrorCode::FieldRequired,
+        "Email cannot be empty",
+      ));
+    }
+
+    if username.is_some() {
+      let changed_at: Option<chrono::DateTime<Utc>> =
+        sqlx::query_scalar("SELECT username_changed_at FROM users WHERE id = $1")
+          .bind(user_id)
+          .fetch_one(db)
+          .await?;
+
+      if let Some(changed_at) = changed_at
+        && changed_at + Duration::hours(USERNAME_CHANGE_COOLDOWN_HOURS) > Utc::now()
+      {
+        return Err(AppError::BadRequest(
+          ErrorCode::UsernameChangeCooldown,
+          "Username can only be changed once every 24 hours".to_string(),
+        ));
+      }
+    }
+
+    if email.is_some() {
+      Self::verify_current_password(
+        db,
+        jwt,
+        user_id,
+        req.password.as_deref(),
+        req.reauthentication_token.as_deref(),
+      )
+      .await?;
+    }
+
+    let user = sqlx::query_as::<_, User>(
+      r#"
+      UPDATE users
+      SET
+        username = COALESCE($1, username),
+        username_changed_at = CASE WHEN $1 IS NULL THEN username_changed_at ELSE NOW() END,
+        email = COALESCE($2, email),
+        email_verified_at = CASE WHEN $2 IS NULL THEN email_verified_at ELSE NULL END,
+        updated_at = NOW()
+      WHERE id = $3
+      RETURNING id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
+      "#,
+    )
+    .bind(&username)
+    .bind(&email)
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+    .map_err(|e| match e {
+      sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+        AppError::Conflict(ErrorCode::UsernameOrEmailTaken, "Username or email already exists".to_string())
+      }
+      _ => AppError::from(e),
+    })?;
+
+    Ok((user, email.is_some()))
+  }
+
+  // Sets a new password and revokes every existing session
+  pub async fn change_password(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    req: ChangePasswordRequest,
+  ) -> AppResult<User> {
+    Self::verify_current_password(
+      db,
+      jwt,
+      user_id,
+      req.current_password.as_deref(),
+      req.reauthentication_token.as_deref(),
+    )
+    .await?;
+
+    let new_password = req.new_password.trim();
+    AuthService::validate_password("new_password", new_password)?;
+    let password_hash = AuthService::hash_password(new_password)?;
+
+    let user = sqlx::query_as::<_, User>(
+      r#"
+      UPDATE users
+      SET password_hash = $1, password_set = TRUE, token_version = token_version + 1, updated_at = NOW()
+      WHERE id = $2
+      RETURNING id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
+      "#,
+    )
+    .bind(&password_hash)
+    .bind(user_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(user)
+  }
+
+  pub async fn get_group_dm_channel_ids(db: &PgPool, user_id: Uuid) -> AppResult<Vec<Uuid>> {
+    let channel_ids = sqlx::query_scalar(
+      r#"
+      SELECT dc.channel_id
+      FROM dm_participants dp
+      INNER JOIN dm_channels dc ON dp.dm_channel_id = dc.id
+      INNER JOIN channels c ON dc.channel_id = c.id
+      WHERE dp.user_id = $1 AND c.channel_type = 'group_dm'
+      "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(channel_ids)
+  }
+
+  // Leaves group DMs, deletes the account's applications and their bots, then anonymizes it.
+  // Nothing is changed unless every step succeeds.
+  pub async fn delete_account(db: &PgPool, user_id: Uuid) -> AppResult<AccountDeletion> {
+    let mut tx = db.begin().await?;
+
+    let dm_channels = sqlx::query_as::<_, DmChannel>(
+      r#"
+      SELECT dc.id, dc.channel_id, dc.owner_id, dc.icon_url, dc.created_at
+      FROM dm_participants dp
+      INNER JOIN dm_channels dc ON dp.dm_channel_id = dc.id
+      INNER JOIN channels c ON dc.channel_id = c.id
+      WHERE dp.user_id = $1 AND c.channel_type = 'group_dm'
+      "#,
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut group_dms = Vec::with_capacity(dm_channels.len());
+    for dm_channel in dm_channels {
+      let remains =
+        ChannelService::remove_group_dm_participant(&mut tx, &dm_channel, user_id).await?;
+      group_dms.push((dm_channel.channel_id, remains));
+    }
+
+    // Bots do not outlive their owner
+    let bot_user_ids: Vec<Uuid> =
+      sqlx::query_scalar("DELETE FROM applications WHERE owner_id = $1 RETURNING bot_user_id")
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for bot_user_id in &bot_user_ids {
+      Self::anonymize_account(&mut tx, *bot_user_id).await?;
+    }
+
+    Self::anonymize_account(&mut tx, user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(AccountDeletion {
+      group_dms,
+      bot_user_ids,
+    })
+  }
+
+  // Anonymizes the account rather than deleting it so messages keep a (deleted) author.
+  // Owned servers pass to their longest-standing human member, or are deleted without one.
+  pub async fn anonymize_account(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+  ) -> AppResult<()> {
+    let owned_servers = sqlx::query_as::<_, Server>(
+      r#"
+      SELECT id, name, owner_id, main_channel_id, public, description, tags,
+        member_count, created_at, updated_at
+      FROM servers
+      WHERE owner_id = $1
+      "#,
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for server in owned_servers {
+      // Bots are passed over, no person could act for a server they owned
+      let next_owner: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT sm.user_id
+        FROM server_members sm
+        INNER JOIN users u ON sm.user_id = u.id
+        WHERE sm.server_id = $1 AND sm.user_id <> $2 AND NOT u.bot
+        ORDER BY sm.joined_at ASC
+        LIMIT 1
+        "#,
+      )
+      .bind(server.id)
+      .bind(user_id)
+      .fetch_optional(&mut **tx)
+      .await?;
+
+      match next_owner {
+        Some(next_owner) => {
+          let updated = sqlx::query_as::<_, Server>(
+            r#"
+            UPDATE servers
+            SET owner_id = $1, updated_at = NOW()
+            WHERE id = $2
+            RETURNING id, name, owner_id, main_channel_id, public, description, tags,
+              member_count, created_at, updated_at
+            "#,
+          )
+          .bind(next_owner)
+          .bind(server.id)
+          .fetch_one(&mut **tx)
+          .await?;
+
+          AuditLogService::record(
+            &mut **tx,
+            server.id,
+            user_id,
+            AuditLogAction::ServerOwnershipTransfer,
+            Some(next_owner),
+            AuditLogService::diff(Some(&server), Some(&updated)),
+            Some("The previous owner deleted their account"),
+          )
+          .await?;
+        }
+        None => {
+          sqlx::query("DELETE FROM servers WHERE id = $1")
+            .bind(server.id)
+            .execute(&mut **tx)
+            .await?;
+        }
+      }
+    }
+
+    for query in [
+      "DELETE FROM server_members WHERE user_id = $1",
+      "DELETE FROM server_organization WHERE user_id = $1",
+      "DELETE FROM server_folders WHERE user_id = $1",
+      "DELETE FROM friendships WHERE user_low = $1 OR user_high = $1",
+      "DELETE FROM channel_notification_settings WHERE user_id = $1",
+      "DELETE FROM push_subscriptions WHERE user_id = $1",
+      "DELETE FROM user_tokens WHERE user_id = $1",
+      "DELETE FROM mfa_backup_codes WHERE user_id = $1",
+      "DELETE FROM user_identities WHERE user_id = $1",
+      "DELETE FROM oidc_auth_requests WHERE user_id = $1",
+    ] {
+      sqlx::query(query).bind(user_id).execute(&mut **tx).await?;
+    }
+
+    sqlx::query(
+      r#"
+      UPDATE profiles
+      SET
+        display_name = 'Deleted User',
+        bio = NULL,
+        avatar_url = NULL,
+        banner_url = NULL,
+        status = 'offline',
+        custom_status = NULL,
+        status_emoji = NULL,
+        show_online_status = FALSE,
+        updated_at = NOW()
+      WHERE user_id = $1
+      "#,
+    )
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET
+        username = 'deleted_user_' || REPLACE(id::text, '-', ''),
+        email = id::text || '@deleted.invalid',
+        password_hash = '',
+        email_verified_at = NULL,
+        totp_secret = NULL,
+        totp_enabled_at = NULL,
+        totp_last_used_step = NULL,
+        token_version = token_version + 1,
+        deleted_at = NOW(),
+        updated_at = NOW()
+      WHERE id = $1
+      "#,
+    )
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+  }
+}
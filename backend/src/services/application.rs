@@ -0,0 +1,435 @@
+// backend/src/services/application.rs
+use crate::config::JwtConfig;
+use crate::models::{
+  Application, AuditLogAction, AuditLogChange, CreateApplicationRequest, Server,
+  UpdateApplicationRequest,
+};
+use crate::outgoing_webhooks::OutgoingWebhooks;
+use crate::services::{AccountService, AuditLogService, AuthService, ServerService};
+use crate::utils::{AppError, AppResult, ErrorCode, random_token};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const MAX_APPLICATION_NAME_LENGTH: usize = 32;
+
+pub struct ApplicationService;
+
+impl ApplicationService {
+  fn validate_name(name: &str) -> AppResult<()> {
+    if name.is_empty() {
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::FieldRequired,
+        "Application name cannot be empty",
+      ));
+    }
+    if name.chars().count() > MAX_APPLICATION_NAME_LENGTH {
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::FieldTooLong,
+        "Application name must be at most 32 characters",
+      ));
+    }
+
+    Ok(())
+  }
+
+  // The bot's username is derived from the application name, its display name is the name itself
+  fn bot_username(name: &str) -> AppResult<String> {
+    let username: String = name
+      .to_lowercase()
+      .chars()
+      .map(|c| if c.is_whitespace() { '_' } else { c })
+      .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+      .collect();
+
+    if username.is_empty() {
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::InvalidFormat,
+        "Application name must contain letters or digits",
+      ));
+    }
+
+    Ok(username)
+  }
+
+  // Returns the application and its bot token, which is not stored in plain text
+  pub async fn create_application(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    owner_id: Uuid,
+    req: CreateApplicationRequest,
+  ) -> AppResult<(Application, String)> {
+    let name = req.name.trim();
+    Self::validate_name(name)?;
+    let username = Self::bot_username(name)?;
+    let description = req
+      .description
+      .map(|description| description.trim().to_string())
+      .filter(|description| !description.is_empty());
+
+    let bot_user_id = Uuid::new_v4();
+    let token = random_token();
+
+    let mut tx = db.begin().await?;
+
+    // Bots cannot log in, so they get no password and an address that never receives mail
+    sqlx::query(
+      r#"
+      INSERT INTO users (id, username, email, password_hash, password_set, bot)
+      VALUES ($1, $2, $3, '', FALSE, TRUE)
+      "#,
+    )
+    .bind(bot_user_id)
+    .bind(&username)
+    .bind(format!("{}@bots.invalid", bot_user_id))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+      sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+        AppError::Conflict(
+          ErrorCode::UsernameOrEmailTaken,
+          "A user with this name already exists".to_string(),
+        )
+      }
+      _ => AppError::from(e),
+    })?;
+
+    sqlx::query("UPDATE profiles SET display_name = $1 WHERE user_id = $2")
+      .bind(name)
+      .bind(bot_user_id)
+      .execute(&mut *tx)
+      .await?;
+
+    let application = sqlx::query_as::<_, Application>(
+      r#"
+      INSERT INTO applications (owner_id, bot_user_id, name, description, bot_token_hash)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING id, owner_id, bot_user_id, name, description, public, allow_dms, interactions_url, created_at, updated_at
+      "#,
+    )
+    .bind(owner_id)
+    .bind(bot_user_id)
+    .bind(name)
+    .bind(&description)
+    .bind(AuthService::hash_user_token(jwt, &token))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((application, token))
+  }
+
+  pub async fn get_applications(db: &PgPool, owner_id: Uuid) -> AppResult<Vec<Application>> {
+    let applications = sqlx::query_as::<_, Application>(
+      r#"
+      SELECT id, owner_id, bot_user_id, name, description, public, allow_dms, interactions_url, created_at, updated_at
+      FROM applications
+      WHERE owner_id = $1
+      ORDER BY created_at ASC
+      "#,
+    )
+    .bind(owner_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(applications)
+  }
+
+  pub async fn get_application(db: &PgPool, application_id: Uuid) -> AppResult<Application> {
+    sqlx::query_as::<_, Application>(
+      r#"
+      SELECT id, owner_id, bot_user_id, name, description, public, allow_dms, interactions_url, created_at, updated_at
+      FROM applications
+      WHERE id = $1
+      "#,
+    )
+    .bind(application_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| {
+      AppError::NotFound(
+        ErrorCode::UnknownApplication,
+        "Application not found".to_string(),
+      )
+    })
+  }
+
+  // Applications owned by someone else are reported as missing
+  pub async fn get_owned_application(
+    db: &PgPool,
+    application_id: Uuid,
+    owner_id: Uuid,
+  ) -> AppResult<Application> {
+    let application = Self::get_application(db, application_id).await?;
+
+    if application.owner_id != owner_id {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownApplication,
+        "Application not found".to_string(),
+      ));
+    }
+
+    Ok(application)
+  }
+
+  pub async fn update_application(
+    db: &PgPool,
+    application_id: Uuid,
+    owner_id: Uuid,
+    req: UpdateApplicationRequest,
+  ) -> AppResult<Application> {
+    Self::get_owned_application(db, application_id, owner_id).await?;
+
+    let name = req.name.map(|name| name.trim().to_string());
+    if let Some(name) = &name {
+      Self::validate_name(name)?;
+    }
+
+    let mut tx = db.begin().await?;
+
+    let application = sqlx::query_as::<_, Application>(
+      r#"
+      UPDATE applications
+      SET
+        name = COALESCE($1, name),
+        description = COALESCE($2, description),
+        public = COALESCE($3, public),
+        allow_dms = COALESCE($4, allow_dms),
+        updated_at = NOW()
+      WHERE id = $5
+      RETURNING id, owner_id, bot_user_id, name, description, public, allow_dms, interactions_url, created_at, updated_at
+      "#,
+    )
+    .bind(&name)
+    .bind(req.description.map(|description| description.trim().to_string()))
+    .bind(req.public)
+    .bind(req.allow_dms)
+    .bind(application_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(name) = &name {
+      sqlx::query("UPDATE profiles SET display_name = $1, updated_at = NOW() WHERE user_id = $2")
+        .bind(name)
+        .bind(application.bot_user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(application)
+  }
+
+  // Replaces the bot token, the old one stops working immediately
+  pub async fn reset_bot_token(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    application_id: Uuid,
+    owner_id: Uuid,
+  ) -> AppResult<(Application, String)> {
+    let application = Self::get_owned_application(db, application_id, owner_id).await?;
+    let token = random_token();
+
+    sqlx::query("UPDATE applications SET bot_token_hash = $1, updated_at = NOW() WHERE id = $2")
+      .bind(AuthService::hash_user_token(jwt, &token))
+      .bind(application_id)
+      .execute(db)
+      .await?;
+
+    Ok((application, token))
+  }
+
+  // Every call replaces the signing secret, which is returned alongside the URL
+  pub async fn set_interactions_endpoint(
+    db: &PgPool,
+    webhooks: &OutgoingWebhooks,
+    application_id: Uuid,
+    owner_id: Uuid,
+    url: &str,
+  ) -> AppResult<(String, String)> {
+    Self::get_owned_application(db, application_id, owner_id).await?;
+
+    let url = url.trim();
+    webhooks
+      .validate_url(url)
+      .map_err(|e| AppError::invalid_field("url", ErrorCode::InvalidFormat, e.to_string()))?;
+
+    let secret = random_token();
+
+    sqlx::query(
+      r#"
+      UPDATE applications
+      SET interactions_url = $1, interactions_secret = $2, updated_at = NOW()
+      WHERE id = $3
+      "#,
+    )
+    .bind(url)
+    .bind(&secret)
+    .bind(application_id)
+    .execute(db)
+    .await?;
+
+    Ok((url.to_string(), secret))
+  }
+
+  // Interactions go back to the bot's gateway connection
+  pub async fn clear_interactions_endpoint(
+    db: &PgPool,
+    application_id: Uuid,
+    owner_id: Uuid,
+  ) -> AppResult<()> {
+    Self::get_owned_application(db, application_id, owner_id).await?;
+
+    sqlx::query(
+      r#"
+      UPDATE applications
+      SET interactions_url = NULL, interactions_secret = NULL, updated_at = NOW()
+      WHERE id = $1
+      "#,
+    )
+    .bind(application_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+  }
+
+  // The URL and signing secret, when the application receives interactions over HTTP
+  pub async fn get_interactions_endpoint(
+    db: &PgPool,
+    application_id: Uuid,
+  ) -> AppResult<Option<(String, String)>> {
+    let endpoint: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+      "SELECT interactions_url, interactions_secret FROM applications WHERE id = $1",
+    )
+    .bind(application_id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(match endpoint {
+      Some((Some(url), Some(secret))) => Some((url, secret)),
+      _ => None,
+    })
+  }
+
+  // Removes the application and deletes its bot like any other account
+  pub async fn delete_application(
+    db: &PgPool,
+    application_id: Uuid,
+    owner_id: Uuid,
+  ) -> AppResult<Application> {
+    let application = Self::get_owned_application(db, application_id, owner_id).await?;
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM applications WHERE id = $1")
+      .bind(application_id)
+      .execute(&mut *tx)
+      .await?;
+
+    AccountService::anonymize_account(&mut tx, application.bot_user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(application)
+  }
+
+  // Adds the application's bot to a server owned by the caller
+  pub async fn authorize_bot(
+    db: &PgPool,
+    application_id: Uuid,
+    user_id: Uuid,
+    server_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<(Application, Server)> {
+    let application = Self::get_application(db, application_id).await?;
+
+    if !application.public && application.owner_id != user_id {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownApplication,
+        "Application not found".to_string(),
+      ));
+    }
+
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can add bots".to_string(),
+      ));
+    }
+
+    let mut tx = db.begin().await?;
+
+    let result = sqlx::query(
+      r#"
+      INSERT INTO server_members (server_id, user_id)
+      VALUES ($1, $2)
+      ON CONFLICT DO NOTHING
+      "#,
+    )
+    .bind(server_id)
+    .bind(application.bot_user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(AppError::Conflict(
+        ErrorCode::AlreadyMember,
+        "The bot is already a member of this server".to_string(),
+      ));
+    }
+
+    let changes = vec![AuditLogChange {
+      key: "application_id".to_string(),
+      old_value: None,
+      new_value: Some(serde_json::json!(application.id)),
+    }];
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::BotAdd,
+      Some(application.bot_user_id),
+      changes,
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((application, server))
+  }
+
+  // Whether either user is a bot, and if so whether their applications allow direct messages
+  pub async fn bot_dms_allowed(
+    db: &PgPool,
+    user_id: Uuid,
+    other_id: Uuid,
+  ) -> AppResult<Option<bool>> {
+    let allowed: Option<bool> = sqlx::query_scalar(
+      "SELECT bool_and(allow_dms) FROM applications WHERE bot_user_id = $1 OR bot_user_id = $2",
+    )
+    .bind(user_id)
+    .bind(other_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(allowed)
+  }
+
+  pub async fn is_bot(db: &PgPool, user_id: Uuid) -> AppResult<bool> {
+    let bot: Option<bool> = sqlx::query_scalar("SELECT bot FROM users WHERE id = $1")
+      .bind(user_id)
+      .fetch_optional(db)
+      .await?;
+
+    Ok(bot.unwrap_or(false))
+  }
+}
@@ -0,0 +1,230 @@
+use crate::models::{
+  AuditLogAction, AuditLogChange, AuditLogEntry, AuditLogQuery, PaginatedResponse,
+};
+use crate::services::ServerService;
+use crate::utils::{AppError, AppResult, ErrorCode};
+use serde::Serialize;
+use sqlx::{Executor, PgPool, Postgres, types::Json};
+use uuid::Uuid;
+
+pub const MAX_REASON_LENGTH: usize = 512;
+
+// Bookkeeping fields that change on their own and say nothing about the action
+const IGNORED_KEYS: &[&str] = &[
+  "id",
+  "server_id",
+  "member_count",
+  "created_at",
+  "updated_at",
+];
+
+pub struct AuditLogService;
+
+impl AuditLogService {
+  pub fn validate_reason(reason: &str) -> AppResult<()> {
+    if reason.chars().count() > MAX_REASON_LENGTH {
+      return Err(AppError::invalid_field(
+        "reason",
+        ErrorCode::FieldTooLong,
+        format!(
+          "Audit log reason must be at most {} characters",
+          MAX_REASON_LENGTH
+        ),
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Compares the top level fields of two snapshots, either side is None for creations and deletions
+  pub fn diff<T: Serialize>(before: Option<&T>, after: Option<&T>) -> Vec<AuditLogChange> {
+    let to_map = |value: Option<&T>| match value.map(serde_json::to_value) {
+      Some(Ok(serde_json::Value::Object(map))) => map,
+      _ => serde_json::Map::new(),
+    };
+    let before = to_map(before);
+    let mut after = to_map(after);
+
+    let mut changes = Vec::new();
+    for (key, old_value) in before {
+      let new_value = after.remove(&key);
+      // A missing field reads as null, so deleting a null field is no change
+      let unchanged = new_value.as_ref().unwrap_or(&serde_json::Value::Null) == &old_value;
+      if IGNORED_KEYS.contains(&key.as_str()) || unchanged {
+        continue;
+      }
+      changes.push(AuditLogChange {
+        key,
+        old_value: Some(old_value).filter(|v| !v.is_null()),
+        new_value: new_value.filter(|v| !v.is_null()),
+      });
+    }
+    for (key, new_value) in after {
+      if IGNORED_KEYS.contains(&key.as_str()) || new_value.is_null() {
+        continue;
+      }
+      changes.push(AuditLogChange {
+        key,
+        old_value: None,
+        new_value: Some(new_value),
+      });
+    }
+
+    changes
+  }
+
+  // Runs on the mutation's transaction so the entry is only kept if the change is
+  pub async fn record<'e, E>(
+    db: E,
+    server_id: Uuid,
+    actor_id: Uuid,
+    action: AuditLogAction,
+    target_id: Option<Uuid>,
+    changes: Vec<AuditLogChange>,
+    reason: Option<&str>,
+  ) -> AppResult<()>
+  where
+    E: Executor<'e, Database = Postgres>,
+  {
+    sqlx::query(
+      r#"
+      INSERT INTO audit_log_entries (server_id, actor_id, action, target_id, changes, reason)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      "#,
+    )
+    .bind(server_id)
+    .bind(actor_id)
+    .bind(action)
+    .bind(target_id)
+    .bind(Json(changes))
+    .bind(reason)
+    .execute(db)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_entries(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+    query: AuditLogQuery,
+  ) -> AppResult<PaginatedResponse<AuditLogEntry>> {
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can view the audit log".to_string(),
+      ));
+    }
+
+    let limit = query.limit.clamp(1, 100);
+    let offset = query.offset.max(0);
+
+    let total: i64 = sqlx::query_scalar(
+      r#"
+      SELECT COUNT(*)
+      FROM audit_log_entries
+      WHERE server_id = $1
+        AND ($2::uuid IS NULL OR actor_id = $2)
+        AND ($3::audit_log_action IS NULL OR action = $3)
+        AND ($4::uuid IS NULL OR target_id = $4)
+      "#,
+    )
+    .bind(server_id)
+    .bind(query.actor_id)
+    .bind(query.action)
+    .bind(query.target_id)
+    .fetch_one(db)
+    .await?;
+
+    let entries = sqlx::query_as::<_, AuditLogEntry>(
+      r#"
+      SELECT
+        a.id, a.server_id, a.actor_id, u.username AS actor_username, a.action, a.target_id,
+        a.changes, a.reason, a.created_at
+      FROM audit_log_entries a
+      LEFT JOIN users u ON u.id = a.actor_id
+      WHERE a.server_id = $1
+        AND ($2::uuid IS NULL OR a.actor_id = $2)
+        AND ($3::audit_log_action IS NULL OR a.action = $3)
+        AND ($4::uuid IS NULL OR a.target_id = $4)
+      ORDER BY a.created_at DESC, a.id DESC
+      LIMIT $5 OFFSET $6
+      "#,
+    )
+    .bind(server_id)
+    .bind(query.actor_id)
+    .bind(query.action)
+    .bind(query.target_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    Ok(PaginatedResponse {
+      data: entries,
+      total,
+      limit,
+      offset,
+      has_more: offset + limit < total,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn changes(
+    diff: Vec<AuditLogChange>,
+  ) -> Vec<(String, Option<serde_json::Value>, Option<serde_json::Value>)> {
+    let mut changes: Vec<_> = diff
+      .into_iter()
+      .map(|change| (change.key, change.old_value, change.new_value))
+      .collect();
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    changes
+  }
+
+  #[test]
+  fn diff_reports_changed_fields_only() {
+    let before =
+      json!({"id": 1, "name": "general", "topic": null, "position": 0, "updated_at": "a"});
+    let after =
+      json!({"id": 1, "name": "chat", "topic": "hello", "position": 0, "updated_at": "b"});
+
+    assert_eq!(
+      changes(AuditLogService::diff(Some(&before), Some(&after))),
+      vec![
+        (
+          "name".to_string(),
+          Some(json!("general")),
+          Some(json!("chat"))
+        ),
+        ("topic".to_string(), None, Some(json!("hello"))),
+      ]
+    );
+  }
+
+  #[test]
+  fn diff_of_creation_and_deletion() {
+    let value = json!({"id": 1, "name": "general", "topic": null, "member_count": 3});
+
+    assert_eq!(
+      changes(AuditLogService::diff(None, Some(&value))),
+      vec![("name".to_string(), None, Some(json!("general")))]
+    );
+    assert_eq!(
+      changes(AuditLogService::diff(Some(&value), None)),
+      vec![("name".to_string(), Some(json!("general")), None)]
+    );
+  }
+
+  #[test]
+  fn diff_of_identical_snapshots_is_empty() {
+    let value = json!({"name": "general", "tags": ["a", "b"]});
+    assert!(AuditLogService::diff(Some(&value), Some(&value)).is_empty());
+  }
+}
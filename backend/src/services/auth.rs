@@ -1,14 +1,23 @@
-use crate::models::{CreateUserRequest, LoginRequest, User};
-use crate::utils::{AppError, AppResult};
+use crate::config::JwtConfig;
+use crate::models::{CreateUserRequest, LoginRequest, User, UserTokenKind};
+use crate::services::LoginThrottleService;
+use crate::utils::{AppError, AppResult, ErrorCode, FieldError, random_token};
 use argon2::{
   Argon2,
   password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{
+  Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+  errors::ErrorKind,
+};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use sqlx::PgPool;
-use std::env;
+use std::net::IpAddr;
+use std::sync::OnceLock;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,6 +25,10 @@ pub struct Claims {
   pub sub: String, // user id
   pub exp: i64,    // expiration time
   pub iat: i64,    // issued at
+  pub iss: String,
+  pub aud: String,
+  #[serde(default)]
+  pub ver: i32, // users.token_version at issue time
 }
 
 pub struct AuthService;
@@ -42,40 +55,336 @@ impl AuthService {
     )
   }
 
-  pub fn generate_token(user_id: Uuid) -> AppResult<String> {
-    let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    let expiration = env::var("JWT_EXPIRATION")
-      .unwrap_or_else(|_| "86400".to_string())
-      .parse::<i64>()
-      .unwrap_or(86400);
-
+  pub fn generate_token(jwt: &JwtConfig, user: &User) -> AppResult<String> {
     let now = Utc::now();
-    let exp = (now + Duration::seconds(expiration)).timestamp();
+    let exp = (now + Duration::seconds(jwt.expiration_secs)).timestamp();
 
     let claims = Claims {
-      sub: user_id.to_string(),
+      sub: user.id.to_string(),
       exp,
       iat: now.timestamp(),
+      iss: jwt.issuer.clone(),
+      aud: jwt.audience.clone(),
+      ver: user.token_version,
+    };
+
+    let result = match &jwt.keys.signing {
+      Some(signing) => {
+        let mut header = Header::new(signing.algorithm);
+        header.kid = Some(signing.kid.clone());
+        encode(&header, &claims, &signing.key)
+      }
+      None => encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt.secret.as_bytes()),
+      ),
+    };
+
+    result.map_err(|e| AppError::InternalServerError(format!("Failed to generate token: {}", e)))
+  }
+
+  pub fn verify_token(jwt: &JwtConfig, token: &str) -> AppResult<Claims> {
+    let header = decode_header(token).map_err(Self::token_error)?;
+
+    // Asymmetric tokens name their key, shared secret tokens are only accepted without key files
+    let secret_key;
+    let (algorithm, key) = match (&header.kid, &jwt.keys.signing) {
+      (Some(kid), _) => {
+        let verifying = jwt.keys.verifying.get(kid).ok_or_else(|| {
+          AppError::Unauthorized(
+            ErrorCode::InvalidTokenSignature,
+            "Token was signed with an unknown key".to_string(),
+          )
+        })?;
+        (verifying.algorithm, &verifying.key)
+      }
+      (None, None) => {
+        secret_key = DecodingKey::from_secret(jwt.secret.as_bytes());
+        (Algorithm::HS256, &secret_key)
+      }
+      (None, Some(_)) => {
+        return Err(AppError::Unauthorized(
+          ErrorCode::TokenMalformed,
+          "Token is missing a key ID".to_string(),
+        ));
+      }
+    };
+
+    let mut validation = Validation::new(algorithm);
+    validation.set_issuer(&[&jwt.issuer]);
+    validation.set_audience(&[&jwt.audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    decode::<Claims>(token, key, &validation)
+      .map(|data| data.claims)
+      .map_err(Self::token_error)
+  }
+
+  // Decoder details stay in the logs
+  fn token_error(e: jsonwebtoken::errors::Error) -> AppError {
+    tracing::debug!("Rejected token: {}", e);
+
+    let (code, message) = match e.kind() {
+      ErrorKind::ExpiredSignature => (ErrorCode::TokenExpired, "Token has expired"),
+      ErrorKind::InvalidSignature | ErrorKind::InvalidAlgorithm => (
+        ErrorCode::InvalidTokenSignature,
+        "Token signature is invalid",
+      ),
+      ErrorKind::InvalidToken
+      | ErrorKind::Base64(_)
+      | ErrorKind::Json(_)
+      | ErrorKind::Utf8(_)
+      | ErrorKind::MissingRequiredClaim(_) => (ErrorCode::TokenMalformed, "Token is malformed"),
+      _ => (ErrorCode::InvalidToken, "Token is invalid"),
     };
+    AppError::Unauthorized(code, message.to_string())
+  }
+
+  // Verifies the JWT and that it has not been revoked by a password reset
+  pub async fn authenticate_token(db: &PgPool, jwt: &JwtConfig, token: &str) -> AppResult<Uuid> {
+    let claims = Self::verify_token(jwt, token)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| {
+      AppError::Unauthorized(
+        ErrorCode::InvalidToken,
+        "Invalid user ID in token".to_string(),
+      )
+    })?;
+
+    let token_version: Option<i32> =
+      sqlx::query_scalar("SELECT token_version FROM users WHERE id = $1 AND deleted_at IS NULL")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+    if token_version != Some(claims.ver) {
+      return Err(AppError::Unauthorized(
+        ErrorCode::SessionInvalidated,
+        "Session is no longer valid".to_string(),
+      ));
+    }
+
+    Ok(user_id)
+  }
+
+  // Bot tokens are long-lived and only revoked by resetting them or deleting the application
+  pub async fn authenticate_bot_token(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    token: &str,
+  ) -> AppResult<Uuid> {
+    sqlx::query_scalar(
+      r#"
+      SELECT a.bot_user_id
+      FROM applications a
+      JOIN users u ON u.id = a.bot_user_id
+      WHERE a.bot_token_hash = $1 AND u.deleted_at IS NULL
+      "#,
+    )
+    .bind(Self::hash_user_token(jwt, token))
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::Unauthorized(ErrorCode::InvalidToken, "Invalid bot token".to_string()))
+  }
+
+  pub fn validate_password(field: &'static str, password: &str) -> AppResult<()> {
+    if password.len() < 8 {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::PasswordTooShort,
+        "Password must be at least 8 characters",
+      ));
+    }
+
+    Ok(())
+  }
+
+  pub fn hash_user_token(jwt: &JwtConfig, token: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(jwt.token_hash_secret.as_bytes())
+      .expect("HMAC accepts any key length");
+    mac.update(token.as_bytes());
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+  }
+
+  // Creates a one-time token, replacing any unused token of the same kind
+  pub async fn issue_user_token(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    kind: UserTokenKind,
+    ttl: Duration,
+  ) -> AppResult<String> {
+    let token = random_token();
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM user_tokens WHERE user_id = $1 AND kind = $2 AND used_at IS NULL")
+      .bind(user_id)
+      .bind(kind)
+      .execute(&mut *tx)
+      .await?;
+
+    sqlx::query(
+      r#"
+      INSERT INTO user_tokens (user_id, kind, token_hash, expires_at)
+      VALUES ($1, $2, $3, $4)
+      "#,
+    )
+    .bind(user_id)
+    .bind(kind)
+    .bind(Self::hash_user_token(jwt, &token))
+    .bind(Utc::now() + ttl)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(token)
+  }
+
+  pub async fn consume_user_token<'e, E>(
+    db: E,
+    jwt: &JwtConfig,
+    token: &str,
+    kind: UserTokenKind,
+  ) -> AppResult<Uuid>
+  where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+  {
+    sqlx::query_scalar(
+      r#"
+      UPDATE user_tokens
+      SET used_at = NOW()
+      WHERE token_hash = $1 AND kind = $2 AND used_at IS NULL AND expires_at > NOW()
+      RETURNING user_id
+      "#,
+    )
+    .bind(Self::hash_user_token(jwt, token))
+    .bind(kind)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| {
+      AppError::BadRequest(
+        ErrorCode::InvalidOrExpiredToken,
+        "Invalid or expired token".to_string(),
+      )
+    })
+  }
+
+  // Resolves a token without using it up
+  pub async fn peek_user_token(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    token: &str,
+    kind: UserTokenKind,
+  ) -> AppResult<Uuid> {
+    sqlx::query_scalar(
+      r#"
+      SELECT user_id FROM user_tokens
+      WHERE token_hash = $1 AND kind = $2 AND used_at IS NULL AND expires_at > NOW()
+      "#,
+    )
+    .bind(Self::hash_user_token(jwt, token))
+    .bind(kind)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| {
+      AppError::BadRequest(
+        ErrorCode::InvalidOrExpiredToken,
+        "Invalid or expired token".to_string(),
+      )
+    })
+  }
+
+  pub async fn verify_email(db: &PgPool, jwt: &JwtConfig, token: &str) -> AppResult<()> {
+    let mut tx = db.begin().await?;
+
+    let user_id =
+      Self::consume_user_token(&mut *tx, jwt, token, UserTokenKind::EmailVerification).await?;
 
-    encode(
-      &Header::default(),
-      &claims,
-      &EncodingKey::from_secret(secret.as_bytes()),
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET email_verified_at = COALESCE(email_verified_at, NOW()), updated_at = NOW()
+      WHERE id = $1
+      "#,
     )
-    .map_err(|e| AppError::InternalServerError(format!("Failed to generate token: {}", e)))
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
   }
 
-  pub fn verify_token(token: &str) -> AppResult<Claims> {
-    let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+  pub async fn get_user_by_id(db: &PgPool, user_id: Uuid) -> AppResult<User> {
+    sqlx::query_as::<_, User>(
+      r#"
+      SELECT id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
+      FROM users
+      WHERE id = $1
+      "#,
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))
+  }
 
-    decode::<Claims>(
-      token,
-      &DecodingKey::from_secret(secret.as_bytes()),
-      &Validation::default(),
+  pub async fn get_user_by_email(db: &PgPool, email: &str) -> AppResult<Option<User>> {
+    let user = sqlx::query_as::<_, User>(
+      r#"
+      SELECT id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
+      FROM users
+      WHERE email = $1 AND deleted_at IS NULL
+      "#,
     )
-    .map(|data| data.claims)
-    .map_err(|e| AppError::InternalServerError(format!("Invalid token: {}", e)))
+    .bind(email.trim())
+    .fetch_optional(db)
+    .await?;
+
+    Ok(user)
+  }
+
+  // Sets a new password and signs the user out everywhere
+  pub async fn reset_password(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    token: &str,
+    password: &str,
+  ) -> AppResult<Uuid> {
+    let password = password.trim();
+    Self::validate_password("password", password)?;
+    let password_hash = Self::hash_password(password)?;
+
+    let mut tx = db.begin().await?;
+
+    let user_id =
+      Self::consume_user_token(&mut *tx, jwt, token, UserTokenKind::PasswordReset).await?;
+
+    // Receiving the reset link also proves ownership of the address
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET
+        password_hash = $1,
+        password_set = TRUE,
+        token_version = token_version + 1,
+        email_verified_at = COALESCE(email_verified_at, NOW()),
+        updated_at = NOW()
+      WHERE id = $2
+      "#,
+    )
+    .bind(&password_hash)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(user_id)
   }
 
   pub async fn create_user(db: &PgPool, req: CreateUserRequest) -> AppResult<User> {
@@ -83,35 +392,42 @@ impl AuthService {
     let email = req.email.trim();
     let password = req.password.trim();
 
-    if username.is_empty() || email.is_empty() || password.is_empty() {
-      return Err(AppError::ValidationError(
-        "All fields are required".to_string(),
-      ));
+    let missing: Vec<FieldError> = [
+      ("username", username.is_empty()),
+      ("email", email.is_empty()),
+      ("password", password.is_empty()),
+    ]
+    .into_iter()
+    .filter(|(_, empty)| *empty)
+    .map(|(field, _)| FieldError {
+      field,
+      code: ErrorCode::FieldRequired,
+      message: "All fields are required".to_string(),
+    })
+    .collect();
+    if !missing.is_empty() {
+      return Err(AppError::ValidationError(missing));
     }
 
-    if password.len() < 8 {
-      return Err(AppError::ValidationError(
-        "Password must be at least 8 characters".to_string(),
-      ));
-    }
+    Self::validate_password("password", password)?;
 
-    let password_hash = Self::hash_password(&password)?;
+    let password_hash = Self::hash_password(password)?;
 
     let user = sqlx::query_as::<_, User>(
       r#"
       INSERT INTO users (username, email, password_hash)
       VALUES ($1, $2, $3)
-      RETURNING id, username, email, password_hash, created_at, updated_at
+      RETURNING id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
       "#,
     )
     .bind(&username)
-    .bind(&email)
+    .bind(email)
     .bind(&password_hash)
     .fetch_one(db)
     .await
     .map_err(|e| match e {
       sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
-        AppError::BadRequest("Username or email already exists".to_string())
+        AppError::Conflict(ErrorCode::UsernameOrEmailTaken, "Username or email already exists".to_string())
       }
       _ => AppError::from(e),
     })?;
@@ -119,48 +435,96 @@ impl AuthService {
     Ok(user)
   }
 
-  pub async fn authenticate_user(db: &PgPool, req: LoginRequest) -> AppResult<User> {
-    let mut user: Option<User> = None;
+  // Argon2 hash of a random password, verified against when the account does not exist so
+  // unknown users take as long to reject as wrong passwords
+  fn dummy_password_hash() -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    DUMMY_HASH.get_or_init(|| {
+      let password = Uuid::new_v4().to_string();
+      Self::hash_password(&password).expect("Failed to hash dummy password")
+    })
+  }
 
-    if let Some(email) = req.email {
-      user = Some(
-        sqlx::query_as::<_, User>(
-          r#"
-        SELECT id, username, email, password_hash, created_at, updated_at
+  // What failed password attempts are counted against, the same for unknown identifiers
+  fn login_identifier(req: &LoginRequest) -> Option<String> {
+    if let Some(email) = &req.email {
+      Some(format!("email:{}", email.trim().to_lowercase()))
+    } else {
+      req
+        .username
+        .as_ref()
+        .map(|username| format!("username:{}", username.trim().to_lowercase()))
+    }
+  }
+
+  pub async fn authenticate_user(
+    db: &PgPool,
+    req: LoginRequest,
+    ip: Option<IpAddr>,
+  ) -> AppResult<User> {
+    let dummy_hash = Self::dummy_password_hash();
+    let identifier = Self::login_identifier(&req);
+
+    let user = if let Some(email) = req.email {
+      sqlx::query_as::<_, User>(
+        r#"
+        SELECT id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
         FROM users
-        WHERE email = $1
+        WHERE email = $1 AND deleted_at IS NULL AND NOT bot
         "#,
-        )
-        .bind(&email)
-        .fetch_optional(db)
-        .await?
-        .ok_or_else(|| AppError::Unauthorized("Invalid login information".to_string()))?,
-      );
+      )
+      .bind(&email)
+      .fetch_optional(db)
+      .await?
     } else if let Some(username) = req.username {
-      user = Some(
-        sqlx::query_as::<_, User>(
-          r#"
-        SELECT id, username, email, password_hash, created_at, updated_at
+      sqlx::query_as::<_, User>(
+        r#"
+        SELECT id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
         FROM users
-        WHERE username = $1
+        WHERE username = $1 AND deleted_at IS NULL AND NOT bot
         "#,
-        )
-        .bind(&username)
-        .fetch_optional(db)
-        .await?
-        .ok_or_else(|| AppError::Unauthorized("Invalid login information".to_string()))?,
-      );
-    }
+      )
+      .bind(&username)
+      .fetch_optional(db)
+      .await?
+    } else {
+      None
+    };
 
-    let user =
-      user.ok_or_else(|| AppError::Unauthorized("Invalid login information".to_string()))?;
+    // Hashed before any lockout is checked so every rejection takes as long
+    let password_hash = user.as_ref().map_or(dummy_hash, |user| &user.password_hash);
+    let password_matches = Self::verify_password(&req.password, password_hash)?;
 
-    if !Self::verify_password(&req.password, &user.password_hash)? {
-      return Err(AppError::Unauthorized(
-        "Invalid login information".to_string(),
-      ));
+    LoginThrottleService::check_ip(db, ip).await?;
+    if let Some(identifier) = &identifier {
+      LoginThrottleService::check_identifier(db, identifier, ip).await?;
+    }
+    if let Some(user) = &user {
+      LoginThrottleService::check_account(db, user.id, ip).await?;
     }
 
-    Ok(user)
+    match (user, identifier) {
+      (Some(user), Some(identifier)) if password_matches => {
+        LoginThrottleService::record_password_success(db, &identifier).await?;
+        // With two-factor enabled the account counter is only reset once the code is verified
+        if user.totp_enabled_at.is_none() {
+          LoginThrottleService::record_success(db, user.id, ip).await?;
+        }
+        Ok(user)
+      }
+      (user, identifier) => {
+        match identifier {
+          Some(identifier) => {
+            let user_id = user.map(|user| user.id);
+            LoginThrottleService::record_identifier_failure(db, &identifier, user_id, ip).await?
+          }
+          None => LoginThrottleService::record_failure(db, None, ip).await?,
+        }
+        Err(AppError::Unauthorized(
+          ErrorCode::InvalidCredentials,
+          "Invalid login information".to_string(),
+        ))
+      }
+    }
   }
 }
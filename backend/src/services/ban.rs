@@ -0,0 +1,160 @@
+// backend/src/services/ban.rs
+use crate::models::{AuditLogAction, Server, ServerBan, VoiceState};
+use crate::services::{AuditLogService, AuthService, ServerService, VoiceService};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct BanService;
+
+impl BanService {
+  async fn require_owner(db: &PgPool, server_id: Uuid, user_id: Uuid) -> AppResult<Server> {
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can manage bans".to_string(),
+      ));
+    }
+
+    Ok(server)
+  }
+
+  pub async fn get_bans(db: &PgPool, server_id: Uuid, user_id: Uuid) -> AppResult<Vec<ServerBan>> {
+    Self::require_owner(db, server_id, user_id).await?;
+
+    let bans = sqlx::query_as::<_, ServerBan>(
+      r#"
+      SELECT b.server_id, b.user_id, u.username, b.actor_id, b.reason, b.created_at
+      FROM server_bans b
+      LEFT JOIN users u ON u.id = b.user_id
+      WHERE b.server_id = $1
+      ORDER BY b.created_at DESC
+      "#,
+    )
+    .bind(server_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(bans)
+  }
+
+  // Users can be banned before they ever join, returns whether they were a member
+  // and the voice state they left if they were in a call in the server
+  pub async fn ban(
+    db: &PgPool,
+    server_id: Uuid,
+    member_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<(bool, Option<VoiceState>)> {
+    let server = Self::require_owner(db, server_id, user_id).await?;
+
+    if member_id == server.owner_id {
+      return Err(AppError::BadRequest(
+        ErrorCode::CannotBanOwner,
+        "The server owner cannot be banned".to_string(),
+      ));
+    }
+
+    AuthService::get_user_by_id(db, member_id).await?;
+
+    let mut tx = db.begin().await?;
+
+    // Joins hold the server row too, so none can slip in between the ban and the removal
+    sqlx::query("SELECT id FROM servers WHERE id = $1 FOR UPDATE")
+      .bind(server_id)
+      .execute(&mut *tx)
+      .await?;
+
+    let result = sqlx::query(
+      r#"
+      INSERT INTO server_bans (server_id, user_id, actor_id, reason)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT DO NOTHING
+      "#,
+    )
+    .bind(server_id)
+    .bind(member_id)
+    .bind(user_id)
+    .bind(reason)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(AppError::Conflict(
+        ErrorCode::AlreadyExists,
+        "That user is already banned".to_string(),
+      ));
+    }
+
+    let voice_state = VoiceService::leave_server(&mut *tx, server_id, member_id).await?;
+
+    let removed = sqlx::query("DELETE FROM server_members WHERE server_id = $1 AND user_id = $2")
+      .bind(server_id)
+      .bind(member_id)
+      .execute(&mut *tx)
+      .await?;
+
+    sqlx::query("DELETE FROM server_organization WHERE server_id = $1 AND user_id = $2")
+      .bind(server_id)
+      .bind(member_id)
+      .execute(&mut *tx)
+      .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::MemberBan,
+      Some(member_id),
+      Vec::new(),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((removed.rows_affected() > 0, voice_state))
+  }
+
+  pub async fn unban(
+    db: &PgPool,
+    server_id: Uuid,
+    member_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<()> {
+    Self::require_owner(db, server_id, user_id).await?;
+
+    let mut tx = db.begin().await?;
+
+    let result = sqlx::query("DELETE FROM server_bans WHERE server_id = $1 AND user_id = $2")
+      .bind(server_id)
+      .bind(member_id)
+      .execute(&mut *tx)
+      .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownBan,
+        "Ban not found".to_string(),
+      ));
+    }
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::MemberUnban,
+      Some(member_id),
+      Vec::new(),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+}
@@ -1,12 +1,21 @@
 use crate::models::{
-  Channel, ChannelType, CreateChannelRequest, CreateDmRequest, CreateGroupDmRequest, DmChannel,
-  DmChannelResponse, DmParticipantInfo,
+  AuditLogAction, Channel, ChannelType, CreateChannelRequest, CreateDmRequest,
+  CreateGroupDmRequest, DmChannel, DmChannelResponse, DmParticipantInfo, DmPrivacy,
+  UpdateChannelRequest, UpdateGroupDmRequest,
 };
-use crate::services::server::ServerService;
-use crate::utils::{AppError, AppResult};
-use sqlx::PgPool;
+use crate::services::{
+  ApplicationService, AuditLogService, FriendshipService, ProfileService, ServerService,
+};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
+// Matches the CHECK constraint on channels.slowmode_seconds
+const MAX_SLOWMODE_SECONDS: i32 = 21600;
+
+// Including the creator
+const MAX_GROUP_DM_PARTICIPANTS: usize = 10;
+
 pub struct ChannelService;
 
 impl ChannelService {
@@ -15,16 +24,20 @@ impl ChannelService {
     server_id: Uuid,
     user_id: Uuid,
     req: CreateChannelRequest,
+    reason: Option<&str>,
   ) -> AppResult<Channel> {
     if !ServerService::is_member(db, server_id, user_id).await? {
-      return Err(AppError::Unauthorized(
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
         "You must be a member of the server to create channels".to_string(),
       ));
     }
 
     if req.name.trim().is_empty() {
-      return Err(AppError::ValidationError(
-        "Channel name cannot be empty".to_string(),
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::FieldRequired,
+        "Channel name cannot be empty",
       ));
     }
 
@@ -38,11 +51,13 @@ impl ChannelService {
 
     let position = max_position.unwrap_or(-1) + 1;
 
+    let mut tx = db.begin().await?;
+
     let channel = sqlx::query_as::<_, Channel>(
       r#"
       INSERT INTO channels (server_id, name, position, channel_type, topic)
       VALUES ($1, $2, $3, $4, $5)
-      RETURNING id, server_id, name, position, channel_type, topic, is_private, created_at
+      RETURNING id, server_id, name, position, channel_type, topic, is_private, slowmode_seconds, created_at
       "#,
     )
     .bind(server_id)
@@ -50,9 +65,22 @@ impl ChannelService {
     .bind(position)
     .bind(channel_type)
     .bind(&req.topic)
-    .fetch_one(db)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::ChannelCreate,
+      Some(channel.id),
+      AuditLogService::diff(None, Some(&channel)),
+      reason,
+    )
     .await?;
 
+    tx.commit().await?;
+
     Ok(channel)
   }
 
@@ -63,6 +91,7 @@ impl ChannelService {
   ) -> AppResult<DmChannelResponse> {
     if user_id == req.recipient_id {
       return Err(AppError::BadRequest(
+        ErrorCode::CannotDmSelf,
         "Cannot create DM with yourself".to_string(),
       ));
     }
@@ -93,7 +122,14 @@ impl ChannelService {
       .bind(req.recipient_id)
       .fetch_one(db)
       .await
-      .map_err(|_| AppError::NotFound("Recipient not found".to_string()))?;
+      .map_err(|_| AppError::NotFound(ErrorCode::UnknownUser, "Recipient not found".to_string()))?;
+
+    if !Self::can_dm(db, user_id, req.recipient_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::CannotDmUser,
+        "Unable to send direct messages to this user".to_string(),
+      ));
+    }
 
     let mut tx = db.begin().await?;
 
@@ -102,7 +138,7 @@ impl ChannelService {
       r#"
       INSERT INTO channels (name, position, channel_type, is_private)
       VALUES ($1, 0, 'dm', true)
-      RETURNING id, server_id, name, position, channel_type, topic, is_private, created_at
+      RETURNING id, server_id, name, position, channel_type, topic, is_private, slowmode_seconds, created_at
       "#,
     )
     .bind(format!("@{}", recipient_username))
@@ -114,7 +150,7 @@ impl ChannelService {
       r#"
       INSERT INTO dm_channels (channel_id)
       VALUES ($1)
-      RETURNING id, channel_id, created_at
+      RETURNING id, channel_id, owner_id, icon_url, created_at
       "#,
     )
     .bind(channel.id)
@@ -145,23 +181,41 @@ impl ChannelService {
     req: CreateGroupDmRequest,
   ) -> AppResult<DmChannelResponse> {
     if req.recipient_ids.is_empty() {
-      return Err(AppError::ValidationError(
-        "Group DM must have at least one recipient".to_string(),
+      return Err(AppError::invalid_field(
+        "recipient_ids",
+        ErrorCode::FieldRequired,
+        "Group DM must have at least one recipient",
       ));
     }
 
-    if req.recipient_ids.len() > 9 {
-      return Err(AppError::ValidationError(
-        "Group DM can have at most 9 recipients".to_string(),
+    if req.recipient_ids.len() >= MAX_GROUP_DM_PARTICIPANTS {
+      return Err(AppError::invalid_field(
+        "recipient_ids",
+        ErrorCode::GroupDmFull,
+        format!(
+          "Group DM can have at most {} recipients",
+          MAX_GROUP_DM_PARTICIPANTS - 1
+        ),
       ));
     }
 
     if req.recipient_ids.contains(&user_id) {
-      return Err(AppError::ValidationError(
-        "Cannot include yourself in recipients list".to_string(),
+      return Err(AppError::invalid_field(
+        "recipient_ids",
+        ErrorCode::CannotDmSelf,
+        "Cannot include yourself in recipients list",
       ));
     }
 
+    for recipient_id in &req.recipient_ids {
+      if !FriendshipService::are_friends(db, user_id, *recipient_id).await? {
+        return Err(AppError::BadRequest(
+          ErrorCode::NotFriends,
+          "You can only add friends to a group DM".to_string(),
+        ));
+      }
+    }
+
     let mut tx = db.begin().await?;
 
     // Create channel
@@ -169,7 +223,7 @@ impl ChannelService {
       r#"
       INSERT INTO channels (name, position, channel_type, is_private)
       VALUES ($1, 0, 'group_dm', true)
-      RETURNING id, server_id, name, position, channel_type, topic, is_private, created_at
+      RETURNING id, server_id, name, position, channel_type, topic, is_private, slowmode_seconds, created_at
       "#,
     )
     .bind(&req.name)
@@ -179,12 +233,13 @@ impl ChannelService {
     // Create DM channel entry
     let dm_channel = sqlx::query_as::<_, DmChannel>(
       r#"
-      INSERT INTO dm_channels (channel_id)
-      VALUES ($1)
-      RETURNING id, channel_id, created_at
+      INSERT INTO dm_channels (channel_id, owner_id)
+      VALUES ($1, $2)
+      RETURNING id, channel_id, owner_id, icon_url, created_at
       "#,
     )
     .bind(channel.id)
+    .bind(user_id)
     .fetch_one(&mut *tx)
     .await?;
 
@@ -219,6 +274,52 @@ impl ChannelService {
     Self::get_dm_channel_response(db, dm_channel.id, dm_channel.channel_id).await
   }
 
+  pub async fn can_dm(db: &PgPool, user_id: Uuid, recipient_id: Uuid) -> AppResult<bool> {
+    if FriendshipService::user_is_blocked_by(db, user_id, recipient_id).await? {
+      return Ok(false);
+    }
+
+    // Bots only talk to people they share a server with, and only if their application allows it
+    match ApplicationService::bot_dms_allowed(db, user_id, recipient_id).await? {
+      Some(false) => return Ok(false),
+      Some(true) if !ServerService::share_server(db, user_id, recipient_id).await? => {
+        return Ok(false);
+      }
+      _ => {}
+    }
+
+    let profile = ProfileService::get_profile(db, recipient_id).await?;
+
+    match profile.dm_privacy {
+      DmPrivacy::Everyone => Ok(true),
+      DmPrivacy::ServerMembers => Ok(
+        FriendshipService::are_friends(db, user_id, recipient_id).await?
+          || ServerService::share_server(db, user_id, recipient_id).await?,
+      ),
+      DmPrivacy::Friends => FriendshipService::are_friends(db, user_id, recipient_id).await,
+    }
+  }
+
+  // Returns the other participant of a 1:1 DM channel
+  pub async fn get_dm_recipient(db: &PgPool, channel_id: Uuid, user_id: Uuid) -> AppResult<Uuid> {
+    let recipient_id = sqlx::query_scalar::<_, Uuid>(
+      r#"
+      SELECT dp.user_id
+      FROM dm_participants dp
+      INNER JOIN dm_channels dc ON dp.dm_channel_id = dc.id
+      WHERE dc.channel_id = $1 AND dp.user_id <> $2
+      LIMIT 1
+      "#,
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "Recipient not found".to_string()))?;
+
+    Ok(recipient_id)
+  }
+
   pub async fn get_user_dm_channels(
     db: &PgPool,
     user_id: Uuid,
@@ -262,23 +363,311 @@ impl ChannelService {
     .fetch_all(db)
     .await?;
 
-    let created_at = sqlx::query_scalar("SELECT created_at FROM dm_channels WHERE id = $1")
-      .bind(dm_id)
-      .fetch_one(db)
-      .await?;
+    let (channel_type, name, owner_id, icon_url, created_at) = sqlx::query_as(
+      r#"
+      SELECT c.channel_type, c.name, dc.owner_id, dc.icon_url, dc.created_at
+      FROM dm_channels dc
+      INNER JOIN channels c ON dc.channel_id = c.id
+      WHERE dc.id = $1
+      "#,
+    )
+    .bind(dm_id)
+    .fetch_one(db)
+    .await?;
 
     Ok(DmChannelResponse {
       id: dm_id,
       channel_id,
+      channel_type,
+      name,
+      owner_id,
+      icon_url,
       participants,
       created_at,
     })
   }
 
+  pub async fn get_dm_channel(db: &PgPool, channel_id: Uuid) -> AppResult<DmChannelResponse> {
+    let dm_id: Uuid = sqlx::query_scalar("SELECT id FROM dm_channels WHERE channel_id = $1")
+      .bind(channel_id)
+      .fetch_optional(db)
+      .await?
+      .ok_or_else(|| {
+        AppError::NotFound(
+          ErrorCode::UnknownChannel,
+          "DM channel not found".to_string(),
+        )
+      })?;
+
+    Self::get_dm_channel_response(db, dm_id, channel_id).await
+  }
+
+  pub async fn get_group_dm_channel(db: &PgPool, channel_id: Uuid) -> AppResult<DmChannel> {
+    let dm_channel = sqlx::query_as::<_, DmChannel>(
+      r#"
+      SELECT dc.id, dc.channel_id, dc.owner_id, dc.icon_url, dc.created_at
+      FROM dm_channels dc
+      INNER JOIN channels c ON dc.channel_id = c.id
+      WHERE dc.channel_id = $1 AND c.channel_type = 'group_dm'
+      "#,
+    )
+    .bind(channel_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| {
+      AppError::NotFound(ErrorCode::UnknownChannel, "Group DM not found".to_string())
+    })?;
+
+    Ok(dm_channel)
+  }
+
+  async fn is_dm_participant(db: &PgPool, dm_id: Uuid, user_id: Uuid) -> AppResult<bool> {
+    let is_participant: bool = sqlx::query_scalar(
+      r#"
+      SELECT EXISTS(
+        SELECT 1 FROM dm_participants
+        WHERE dm_channel_id = $1 AND user_id = $2
+      )
+      "#,
+    )
+    .bind(dm_id)
+    .bind(user_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(is_participant)
+  }
+
+  pub async fn add_group_dm_recipient(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+    recipient_id: Uuid,
+  ) -> AppResult<DmChannelResponse> {
+    let dm_channel = Self::get_group_dm_channel(db, channel_id).await?;
+
+    if !Self::is_dm_participant(db, dm_channel.id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You are not a member of this group DM".to_string(),
+      ));
+    }
+
+    if Self::is_dm_participant(db, dm_channel.id, recipient_id).await? {
+      return Err(AppError::Conflict(
+        ErrorCode::AlreadyMember,
+        "User is already a member of this group DM".to_string(),
+      ));
+    }
+
+    if !FriendshipService::are_friends(db, user_id, recipient_id).await? {
+      return Err(AppError::BadRequest(
+        ErrorCode::NotFriends,
+        "You can only add friends to a group DM".to_string(),
+      ));
+    }
+
+    let mut tx = db.begin().await?;
+
+    // Holding the group row keeps concurrent adds from going over the limit together
+    sqlx::query("SELECT id FROM dm_channels WHERE id = $1 FOR UPDATE")
+      .bind(dm_channel.id)
+      .execute(&mut *tx)
+      .await?;
+
+    let participant_count: i64 =
+      sqlx::query_scalar("SELECT COUNT(*) FROM dm_participants WHERE dm_channel_id = $1")
+        .bind(dm_channel.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    if participant_count >= MAX_GROUP_DM_PARTICIPANTS as i64 {
+      return Err(AppError::BadRequest(
+        ErrorCode::GroupDmFull,
+        format!(
+          "Group DM can have at most {} members",
+          MAX_GROUP_DM_PARTICIPANTS
+        ),
+      ));
+    }
+
+    sqlx::query(
+      r#"
+      INSERT INTO dm_participants (dm_channel_id, user_id)
+      VALUES ($1, $2)
+      "#,
+    )
+    .bind(dm_channel.id)
+    .bind(recipient_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Self::get_dm_channel_response(db, dm_channel.id, channel_id).await
+  }
+
+  pub async fn remove_group_dm_recipient(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+    recipient_id: Uuid,
+  ) -> AppResult<DmChannelResponse> {
+    let dm_channel = Self::get_group_dm_channel(db, channel_id).await?;
+
+    if dm_channel.owner_id != Some(user_id) {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the group owner can remove members".to_string(),
+      ));
+    }
+
+    if recipient_id == user_id {
+      return Err(AppError::BadRequest(
+        ErrorCode::CannotRemoveSelf,
+        "Leave the group DM to remove yourself".to_string(),
+      ));
+    }
+
+    let result =
+      sqlx::query("DELETE FROM dm_participants WHERE dm_channel_id = $1 AND user_id = $2")
+        .bind(dm_channel.id)
+        .bind(recipient_id)
+        .execute(db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownMember,
+        "User is not a member of this group DM".to_string(),
+      ));
+    }
+
+    Self::get_dm_channel_response(db, dm_channel.id, channel_id).await
+  }
+
+  pub async fn update_group_dm(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+    req: UpdateGroupDmRequest,
+  ) -> AppResult<DmChannelResponse> {
+    let dm_channel = Self::get_group_dm_channel(db, channel_id).await?;
+
+    if !Self::is_dm_participant(db, dm_channel.id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You are not a member of this group DM".to_string(),
+      ));
+    }
+
+    if let Some(ref name) = req.name
+      && (name.trim().is_empty() || name.len() > 100)
+    {
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::InvalidFormat,
+        "Group name must be between 1 and 100 characters",
+      ));
+    }
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("UPDATE channels SET name = COALESCE($1, name) WHERE id = $2")
+      .bind(&req.name)
+      .bind(channel_id)
+      .execute(&mut *tx)
+      .await?;
+
+    sqlx::query("UPDATE dm_channels SET icon_url = COALESCE($1, icon_url) WHERE id = $2")
+      .bind(&req.icon_url)
+      .bind(dm_channel.id)
+      .execute(&mut *tx)
+      .await?;
+
+    tx.commit().await?;
+
+    Self::get_dm_channel_response(db, dm_channel.id, channel_id).await
+  }
+
+  // Returns None when the last member left and the group was deleted
+  pub async fn leave_group_dm(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Option<DmChannelResponse>> {
+    let dm_channel = Self::get_group_dm_channel(db, channel_id).await?;
+
+    let mut tx = db.begin().await?;
+    let remains = Self::remove_group_dm_participant(&mut tx, &dm_channel, user_id).await?;
+    tx.commit().await?;
+
+    if !remains {
+      return Ok(None);
+    }
+
+    Self::get_dm_channel_response(db, dm_channel.id, channel_id)
+      .await
+      .map(Some)
+  }
+
+  // Hands ownership to the longest-standing member, returns false when the group was deleted
+  pub async fn remove_group_dm_participant(
+    tx: &mut Transaction<'_, Postgres>,
+    dm_channel: &DmChannel,
+    user_id: Uuid,
+  ) -> AppResult<bool> {
+    let result =
+      sqlx::query("DELETE FROM dm_participants WHERE dm_channel_id = $1 AND user_id = $2")
+        .bind(dm_channel.id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownMember,
+        "You are not a member of this group DM".to_string(),
+      ));
+    }
+
+    let next_owner: Option<Uuid> = sqlx::query_scalar(
+      r#"
+      SELECT user_id
+      FROM dm_participants
+      WHERE dm_channel_id = $1
+      ORDER BY joined_at ASC
+      LIMIT 1
+      "#,
+    )
+    .bind(dm_channel.id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(next_owner) = next_owner else {
+      sqlx::query("DELETE FROM channels WHERE id = $1")
+        .bind(dm_channel.channel_id)
+        .execute(&mut **tx)
+        .await?;
+
+      return Ok(false);
+    };
+
+    if dm_channel.owner_id == Some(user_id) {
+      sqlx::query("UPDATE dm_channels SET owner_id = $1 WHERE id = $2")
+        .bind(next_owner)
+        .bind(dm_channel.id)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(true)
+  }
+
   pub async fn get_server_channels(db: &PgPool, server_id: Uuid) -> AppResult<Vec<Channel>> {
     let channels = sqlx::query_as::<_, Channel>(
       r#"
-      SELECT id, server_id, name, position, channel_type, topic, is_private, created_at
+      SELECT id, server_id, name, position, channel_type, topic, is_private, slowmode_seconds, created_at
       FROM channels
       WHERE server_id = $1
       ORDER BY position ASC
@@ -294,7 +683,7 @@ impl ChannelService {
   pub async fn get_channel_by_id(db: &PgPool, channel_id: Uuid) -> AppResult<Channel> {
     let channel = sqlx::query_as::<_, Channel>(
       r#"
-      SELECT id, server_id, name, position, channel_type, topic, is_private, created_at
+      SELECT id, server_id, name, position, channel_type, topic, is_private, slowmode_seconds, created_at
       FROM channels
       WHERE id = $1
       "#,
@@ -302,7 +691,7 @@ impl ChannelService {
     .bind(channel_id)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("Channel not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownChannel, "Channel not found".to_string()))?;
 
     Ok(channel)
   }
@@ -344,12 +733,103 @@ impl ChannelService {
     }
   }
 
-  pub async fn delete_channel(db: &PgPool, channel_id: Uuid, user_id: Uuid) -> AppResult<()> {
+  pub async fn update_channel(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+    req: UpdateChannelRequest,
+    reason: Option<&str>,
+  ) -> AppResult<Channel> {
     let channel = Self::get_channel_by_id(db, channel_id).await?;
 
-    match channel.channel_type {
+    let Some(server_id) = channel.server_id else {
+      return Err(AppError::BadRequest(
+        ErrorCode::InvalidChannel,
+        "DM channels cannot be updated here".to_string(),
+      ));
+    };
+
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can update channels".to_string(),
+      ));
+    }
+
+    if let Some(ref name) = req.name
+      && name.trim().is_empty()
+    {
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::FieldRequired,
+        "Channel name cannot be empty",
+      ));
+    }
+
+    if let Some(slowmode) = req.slowmode_seconds
+      && !(0..=MAX_SLOWMODE_SECONDS).contains(&slowmode)
+    {
+      return Err(AppError::invalid_field(
+        "slowmode_seconds",
+        ErrorCode::InvalidFormat,
+        format!(
+          "Slowmode must be between 0 and {} seconds",
+          MAX_SLOWMODE_SECONDS
+        ),
+      ));
+    }
+
+    let mut tx = db.begin().await?;
+
+    let updated = sqlx::query_as::<_, Channel>(
+      r#"
+      UPDATE channels
+      SET
+        name = COALESCE($1, name),
+        position = COALESCE($2, position),
+        topic = COALESCE($3, topic),
+        slowmode_seconds = COALESCE($4, slowmode_seconds)
+      WHERE id = $5
+      RETURNING id, server_id, name, position, channel_type, topic, is_private, slowmode_seconds, created_at
+      "#,
+    )
+    .bind(req.name)
+    .bind(req.position)
+    .bind(req.topic)
+    .bind(req.slowmode_seconds)
+    .bind(channel_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::ChannelUpdate,
+      Some(channel_id),
+      AuditLogService::diff(Some(&channel), Some(&updated)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(updated)
+  }
+
+  pub async fn delete_channel(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<()> {
+    let channel = Self::get_channel_by_id(db, channel_id).await?;
+
+    let server_id = match channel.channel_type {
       ChannelType::Dm | ChannelType::GroupDm => {
         return Err(AppError::BadRequest(
+          ErrorCode::InvalidChannel,
           "Cannot delete DM channels".to_string(),
         ));
       }
@@ -357,21 +837,41 @@ impl ChannelService {
         if let Some(server_id) = channel.server_id {
           let server = ServerService::get_server_by_id(db, server_id).await?;
           if server.owner_id != user_id {
-            return Err(AppError::Unauthorized(
+            return Err(AppError::Forbidden(
+              ErrorCode::MissingPermissions,
               "Only the server owner can delete channels".to_string(),
             ));
           }
+          server_id
         } else {
-          return Err(AppError::BadRequest("Invalid channel".to_string()));
+          return Err(AppError::BadRequest(
+            ErrorCode::InvalidChannel,
+            "Invalid channel".to_string(),
+          ));
         }
       }
-    }
+    };
+
+    let mut tx = db.begin().await?;
 
     sqlx::query("DELETE FROM channels WHERE id = $1")
       .bind(channel_id)
-      .execute(db)
+      .execute(&mut *tx)
       .await?;
 
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::ChannelDelete,
+      Some(channel_id),
+      AuditLogService::diff(Some(&channel), None),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok(())
   }
 }
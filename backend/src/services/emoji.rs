@@ -0,0 +1,455 @@
+// backend/src/services/emoji.rs
+use crate::config::LimitsConfig;
+use crate::models::{AuditLogAction, CreateEmojiRequest, Emoji, EmojiImage, UpdateEmojiRequest};
+use crate::services::{AuditLogService, ServerService};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const MIN_EMOJI_NAME_LENGTH: usize = 2;
+const MAX_EMOJI_NAME_LENGTH: usize = 32;
+
+// Content types accepted for emoji images and the bytes their files start with
+const IMAGE_SIGNATURES: &[(&str, &[u8])] = &[
+  ("image/png", b"\x89PNG\r\n\x1a\n"),
+  ("image/jpeg", b"\xff\xd8\xff"),
+  ("image/gif", b"GIF87a"),
+  ("image/gif", b"GIF89a"),
+];
+
+pub struct EmojiService;
+
+impl EmojiService {
+  fn validate_name(name: &str) -> AppResult<()> {
+    let length = name.chars().count();
+    if !(MIN_EMOJI_NAME_LENGTH..=MAX_EMOJI_NAME_LENGTH).contains(&length)
+      || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::InvalidFormat,
+        format!(
+          "Emoji name must be {} to {} letters, digits or underscores",
+          MIN_EMOJI_NAME_LENGTH, MAX_EMOJI_NAME_LENGTH
+        ),
+      ));
+    }
+
+    Ok(())
+  }
+
+  fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    let webp = bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP";
+    if webp {
+      return Some("image/webp");
+    }
+
+    IMAGE_SIGNATURES
+      .iter()
+      .find(|(_, signature)| bytes.starts_with(signature))
+      .map(|(content_type, _)| *content_type)
+  }
+
+  // The declared type has to match the file itself, which decides the type it is served with
+  fn decode_image(limits: &LimitsConfig, image: &str) -> AppResult<(&'static str, Vec<u8>)> {
+    let invalid = || {
+      AppError::invalid_field(
+        "image",
+        ErrorCode::InvalidFormat,
+        "Image must be a base64 data URI of a PNG, JPEG, GIF or WebP image",
+      )
+    };
+
+    let (declared_type, data) = image
+      .strip_prefix("data:")
+      .and_then(|rest| rest.split_once(";base64,"))
+      .ok_or_else(invalid)?;
+
+    // Base64 is a third larger than what it encodes
+    if data.len() / 4 * 3 > limits.max_emoji_bytes + 3 {
+      return Err(Self::image_too_large(limits));
+    }
+
+    let bytes = STANDARD.decode(data.trim()).map_err(|_| invalid())?;
+    if bytes.len() > limits.max_emoji_bytes {
+      return Err(Self::image_too_large(limits));
+    }
+
+    match Self::sniff_content_type(&bytes) {
+      Some(content_type) if content_type.eq_ignore_ascii_case(declared_type) => {
+        Ok((content_type, bytes))
+      }
+      _ => Err(invalid()),
+    }
+  }
+
+  fn image_too_large(limits: &LimitsConfig) -> AppError {
+    AppError::invalid_field(
+      "image",
+      ErrorCode::FieldTooLong,
+      format!(
+        "Emoji images must be at most {} KiB",
+        limits.max_emoji_bytes / 1024
+      ),
+    )
+  }
+
+  fn name_taken(e: sqlx::Error) -> AppError {
+    match e {
+      sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+        AppError::Conflict(
+          ErrorCode::AlreadyExists,
+          "This server already has an emoji with that name".to_string(),
+        )
+      }
+      _ => AppError::from(e),
+    }
+  }
+
+  async fn require_owner(db: &PgPool, server_id: Uuid, user_id: Uuid) -> AppResult<()> {
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can manage emojis".to_string(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  async fn get_emoji(db: &PgPool, server_id: Uuid, emoji_id: Uuid) -> AppResult<Emoji> {
+    sqlx::query_as::<_, Emoji>(
+      r#"
+      SELECT id, server_id, creator_id, name, created_at, updated_at
+      FROM emojis
+      WHERE id = $1 AND server_id = $2
+      "#,
+    )
+    .bind(emoji_id)
+    .bind(server_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownEmoji, "Unknown emoji".to_string()))
+  }
+
+  pub async fn create_emoji(
+    db: &PgPool,
+    limits: &LimitsConfig,
+    server_id: Uuid,
+    user_id: Uuid,
+    req: CreateEmojiRequest,
+    reason: Option<&str>,
+  ) -> AppResult<Emoji> {
+    Self::require_owner(db, server_id, user_id).await?;
+
+    let name = req.name.trim();
+    Self::validate_name(name)?;
+    let (content_type, image) = Self::decode_image(limits, &req.image)?;
+
+    let mut tx = db.begin().await?;
+
+    // Holding the server row keeps concurrent uploads from going over the quota together
+    sqlx::query("SELECT id FROM servers WHERE id = $1 FOR UPDATE")
+      .bind(server_id)
+      .execute(&mut *tx)
+      .await?;
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM emojis WHERE server_id = $1")
+      .bind(server_id)
+      .fetch_one(&mut *tx)
+      .await?;
+
+    if count >= limits.max_emojis_per_server {
+      return Err(AppError::BadRequest(
+        ErrorCode::MaxEmojisReached,
+        format!(
+          "Servers can have at most {} emojis",
+          limits.max_emojis_per_server
+        ),
+      ));
+    }
+
+    let emoji = sqlx::query_as::<_, Emoji>(
+      r#"
+      INSERT INTO emojis (server_id, creator_id, name, content_type, image)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING id, server_id, creator_id, name, created_at, updated_at
+      "#,
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .bind(name)
+    .bind(content_type)
+    .bind(&image)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(Self::name_taken)?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::EmojiCreate,
+      Some(emoji.id),
+      AuditLogService::diff(None, Some(&emoji)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(emoji)
+  }
+
+  // Emojis are visible to members of their server only
+  pub async fn get_server_emojis(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Vec<Emoji>> {
+    if !ServerService::is_member(db, server_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You are not a member of this server".to_string(),
+      ));
+    }
+
+    let emojis = sqlx::query_as::<_, Emoji>(
+      r#"
+      SELECT id, server_id, creator_id, name, created_at, updated_at
+      FROM emojis
+      WHERE server_id = $1
+      ORDER BY name ASC
+      "#,
+    )
+    .bind(server_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(emojis)
+  }
+
+  pub async fn update_emoji(
+    db: &PgPool,
+    server_id: Uuid,
+    emoji_id: Uuid,
+    user_id: Uuid,
+    req: UpdateEmojiRequest,
+    reason: Option<&str>,
+  ) -> AppResult<Emoji> {
+    Self::require_owner(db, server_id, user_id).await?;
+    let emoji = Self::get_emoji(db, server_id, emoji_id).await?;
+
+    let name = req.name.trim();
+    Self::validate_name(name)?;
+
+    let mut tx = db.begin().await?;
+
+    let updated = sqlx::query_as::<_, Emoji>(
+      r#"
+      UPDATE emojis
+      SET name = $1, updated_at = NOW()
+      WHERE id = $2
+      RETURNING id, server_id, creator_id, name, created_at, updated_at
+      "#,
+    )
+    .bind(name)
+    .bind(emoji_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(Self::name_taken)?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::EmojiUpdate,
+      Some(emoji_id),
+      AuditLogService::diff(Some(&emoji), Some(&updated)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(updated)
+  }
+
+  pub async fn delete_emoji(
+    db: &PgPool,
+    server_id: Uuid,
+    emoji_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<()> {
+    Self::require_owner(db, server_id, user_id).await?;
+    let emoji = Self::get_emoji(db, server_id, emoji_id).await?;
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM emojis WHERE id = $1")
+      .bind(emoji_id)
+      .execute(&mut *tx)
+      .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::EmojiDelete,
+      Some(emoji_id),
+      AuditLogService::diff(Some(&emoji), None),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+
+  // Members only, other users get the same error as for an emoji that does not exist
+  pub async fn get_emoji_image(
+    db: &PgPool,
+    emoji_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<EmojiImage> {
+    sqlx::query_as::<_, EmojiImage>(
+      r#"
+      SELECT e.content_type, e.image
+      FROM emojis e
+      INNER JOIN server_members sm ON sm.server_id = e.server_id AND sm.user_id = $2
+      WHERE e.id = $1
+      "#,
+    )
+    .bind(emoji_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownEmoji, "Unknown emoji".to_string()))
+  }
+
+  // Finds <:name:id> references, returning the byte range, name and id of each
+  fn find_references(content: &str) -> Vec<(std::ops::Range<usize>, &str, Uuid)> {
+    let mut references = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = content[offset..].find("<:").map(|i| offset + i) {
+      offset = start + 2;
+
+      let Some(end) = content[offset..].find('>').map(|i| offset + i) else {
+        break;
+      };
+      let Some((name, id)) = content[offset..end].split_once(':') else {
+        continue;
+      };
+      if Self::validate_name(name).is_err() {
+        continue;
+      }
+      let Ok(id) = Uuid::parse_str(id) else {
+        continue;
+      };
+
+      references.push((start..end + 1, name, id));
+      offset = end + 1;
+    }
+
+    references
+  }
+
+  // Emoji images are only served to members of the emoji's server, so only emojis of the
+  // channel's own server are kept and the rest, including any in DMs, are written out as :name:
+  pub async fn resolve_content(
+    db: &PgPool,
+    content: &str,
+    server_id: Option<Uuid>,
+  ) -> AppResult<String> {
+    let references = Self::find_references(content);
+    if references.is_empty() {
+      return Ok(content.to_string());
+    }
+
+    let usable: Vec<Uuid> = match server_id {
+      Some(server_id) => {
+        let ids: Vec<Uuid> = references.iter().map(|(_, _, id)| *id).collect();
+        sqlx::query_scalar("SELECT id FROM emojis WHERE id = ANY($1) AND server_id = $2")
+          .bind(&ids)
+          .bind(server_id)
+          .fetch_all(db)
+          .await?
+      }
+      None => Vec::new(),
+    };
+
+    let mut resolved = String::with_capacity(content.len());
+    let mut last = 0;
+    for (range, name, id) in references {
+      if usable.contains(&id) {
+        continue;
+      }
+      resolved.push_str(&content[last..range.start]);
+      resolved.push(':');
+      resolved.push_str(name);
+      resolved.push(':');
+      last = range.end;
+    }
+    resolved.push_str(&content[last..]);
+
+    Ok(resolved)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const WAVE: &str = "0b7a5c6e-4d3f-4a2b-9c1d-8e7f6a5b4c3d";
+  const PARTY: &str = "5f4e3d2c-1b0a-4987-8654-3210fedcba98";
+
+  #[test]
+  fn finds_references_with_ranges() {
+    let content = format!("hi <:wave:{}> and <:party_1:{}>!", WAVE, PARTY);
+    let references = EmojiService::find_references(&content);
+
+    assert_eq!(references.len(), 2);
+
+    let (range, name, id) = &references[0];
+    assert_eq!(&content[range.clone()], format!("<:wave:{}>", WAVE));
+    assert_eq!(*name, "wave");
+    assert_eq!(*id, Uuid::parse_str(WAVE).unwrap());
+
+    let (range, name, id) = &references[1];
+    assert_eq!(&content[range.clone()], format!("<:party_1:{}>", PARTY));
+    assert_eq!(*name, "party_1");
+    assert_eq!(*id, Uuid::parse_str(PARTY).unwrap());
+  }
+
+  #[test]
+  fn skips_malformed_references() {
+    for content in [
+      "<:wave:not-a-uuid>".to_string(),
+      format!("<:bad name:{}>", WAVE),
+      format!("<:x:{}>", WAVE),
+      format!("<:wave:{}", WAVE),
+      format!("<wave:{}>", WAVE),
+      "<:wave>".to_string(),
+    ] {
+      assert!(
+        EmojiService::find_references(&content).is_empty(),
+        "{} should not match",
+        content
+      );
+    }
+  }
+
+  #[test]
+  fn recovers_after_a_malformed_reference() {
+    let content = format!("<:a b:c> <:wave:{}>", WAVE);
+    let references = EmojiService::find_references(&content);
+
+    assert_eq!(references.len(), 1);
+    assert_eq!(references[0].1, "wave");
+  }
+}
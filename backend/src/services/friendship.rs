@@ -3,7 +3,8 @@ use uuid::Uuid;
 
 use crate::{
   models::{Friendship, FullProfile, PaginatedResponse, Profile},
-  utils::{AppError, AppResult},
+  services::ApplicationService,
+  utils::{AppError, AppResult, ErrorCode},
 };
 
 pub struct FriendshipService;
@@ -32,7 +33,7 @@ impl FriendshipService {
     let friends = sqlx::query_as::<_, FullProfile>(
       r#"
       SELECT
-        u.id, u.username, p.display_name, p.bio, p.avatar_url, p.banner_url,
+        u.id, u.username, u.bot, p.display_name, p.bio, p.avatar_url, p.banner_url,
         p.status, p.custom_status, p.status_emoji, p.show_online_status,
         p.created_at
       FROM friendships f
@@ -86,6 +87,25 @@ impl FriendshipService {
     Ok(blocked)
   }
 
+  pub async fn are_friends(db: &PgPool, user_id: Uuid, other_id: Uuid) -> AppResult<bool> {
+    let friends: bool = sqlx::query_scalar(
+      r#"
+      SELECT EXISTS(
+        SELECT 1 FROM friendships
+        WHERE user_low = LEAST($1, $2)
+          AND user_high = GREATEST($1, $2)
+          AND status = 'accepted'
+      )
+      "#,
+    )
+    .bind(user_id)
+    .bind(other_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(friends)
+  }
+
   pub async fn get_incoming_requests(
     db: &PgPool,
     user_id: Uuid,
@@ -110,7 +130,7 @@ impl FriendshipService {
     let requests = sqlx::query_as::<_, FullProfile>(
       r#"
       SELECT 
-        u.id, u.username, p.display_name, p.bio, p.avatar_url, p.banner_url,
+        u.id, u.username, u.bot, p.display_name, p.bio, p.avatar_url, p.banner_url,
         p.status, p.custom_status, p.status_emoji, p.show_online_status,
         p.created_at
       FROM friendships f
@@ -161,7 +181,7 @@ impl FriendshipService {
     let requests = sqlx::query_as::<_, FullProfile>(
       r#"
       SELECT
-        u.id, u.username, p.display_name, p.bio, p.avatar_url, p.banner_url,
+        u.id, u.username, u.bot, p.display_name, p.bio, p.avatar_url, p.banner_url,
         p.status, p.custom_status, p.status_emoji, p.show_online_status,
         p.created_at
       FROM friendships f
@@ -199,12 +219,23 @@ impl FriendshipService {
   ) -> AppResult<Friendship> {
     if user_id == other_id {
       return Err(AppError::BadRequest(
+        ErrorCode::CannotFriendSelf,
         "You cannot send a friend request to yourself".to_string(),
       ));
     }
 
+    if ApplicationService::is_bot(db, user_id).await?
+      || ApplicationService::is_bot(db, other_id).await?
+    {
+      return Err(AppError::BadRequest(
+        ErrorCode::CannotFriendBot,
+        "Bots cannot have friends".to_string(),
+      ));
+    }
+
     if Self::user_is_blocked_by(db, user_id, other_id).await? {
       return Err(AppError::BadRequest(
+        ErrorCode::FriendRequestBlocked,
         "Unable to send friend request".to_string(),
       ));
     }
@@ -272,7 +303,10 @@ impl FriendshipService {
     .await?;
 
     if result.rows_affected() == 0 {
-      return Err(AppError::NotFound("Friend request not found".to_string()));
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownFriendRequest,
+        "Friend request not found".to_string(),
+      ));
     }
 
     Ok(())
@@ -293,7 +327,10 @@ impl FriendshipService {
     .await?;
 
     if result.rows_affected() == 0 {
-      return Err(AppError::NotFound("Friendship not found".to_string()));
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownFriendship,
+        "Friendship not found".to_string(),
+      ));
     }
 
     Ok(())
@@ -302,6 +339,7 @@ impl FriendshipService {
   pub async fn block_user(db: &PgPool, user_id: Uuid, other_id: Uuid) -> AppResult<Friendship> {
     if user_id == other_id {
       return Err(AppError::BadRequest(
+        ErrorCode::CannotBlockSelf,
         "You cannot block yourself".to_string(),
       ));
     }
@@ -347,7 +385,10 @@ impl FriendshipService {
     .await?;
 
     if result.rows_affected() == 0 {
-      return Err(AppError::NotFound("Block not found".to_string()));
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownBlock,
+        "Block not found".to_string(),
+      ));
     }
 
     Ok(())
@@ -378,7 +419,7 @@ impl FriendshipService {
       SELECT
         p.user_id, p.display_name, p.bio, p.avatar_url, p.banner_url,
         p.status, p.custom_status, p.status_emoji, p.show_online_status,
-        p.allow_dms, p.created_at, p.updated_at
+        p.dm_privacy, p.created_at, p.updated_at
       FROM friendships f
       JOIN profiles p
         ON p.user_id = CASE
@@ -417,7 +458,7 @@ impl FriendshipService {
       r#"
       SELECT COUNT(*)
       FROM users
-      WHERE username ILIKE $1
+      WHERE username ILIKE $1 AND deleted_at IS NULL
       "#,
     )
     .bind(format!("%{}%", username))
@@ -428,13 +469,13 @@ impl FriendshipService {
     let users = sqlx::query_as::<_, FullProfile>(
       r#"
       SELECT
-        u.id, u.username, p.display_name, p.bio, p.avatar_url, p.banner_url,
+        u.id, u.username, u.bot, p.display_name, p.bio, p.avatar_url, p.banner_url,
         p.status, p.custom_status, p.status_emoji, p.show_online_status,
-        p.allow_dms, p.created_at, p.updated_at
+        p.dm_privacy, p.created_at, p.updated_at
       FROM users u
       LEFT JOIN profiles p
       ON u.id = p.user_id
-      WHERE u.username ILIKE $1
+      WHERE u.username ILIKE $1 AND u.deleted_at IS NULL
       ORDER BY u.username ASC
       LIMIT $2 OFFSET $3
       "#,
@@ -460,7 +501,7 @@ impl FriendshipService {
       SELECT
         p.user_id, p.display_name, p.bio, p.avatar_url, p.banner_url,
         p.status, p.custom_status, p.status_emoji, p.show_online_status,
-        p.allow_dms, p.created_at, p.updated_at
+        p.dm_privacy, p.created_at, p.updated_at
       FROM users u
       LEFT JOIN profiles p
       ON u.id = p.user_id
@@ -470,7 +511,7 @@ impl FriendshipService {
     .bind(username)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))?;
 
     Ok(user)
   }
@@ -481,7 +522,7 @@ impl FriendshipService {
       SELECT
         user_id, display_name, bio, avatar_url, banner_url,
         status, custom_status, status_emoji, show_online_status,
-        allow_dms, created_at, updated_at
+        dm_privacy, created_at, updated_at
       FROM profiles
       WHERE user_id = $1
       "#,
@@ -489,7 +530,7 @@ impl FriendshipService {
     .bind(user_id)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))?;
 
     Ok(user)
   }
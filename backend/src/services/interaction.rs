@@ -0,0 +1,565 @@
+// backend/src/services/interaction.rs
+use crate::config::{JwtConfig, LimitsConfig};
+use crate::models::{
+  Application, ApplicationCommand, CommandOption, CommandOptionType, CreateCommandRequest,
+  CreateInteractionRequest, Interaction, InteractionCallbackRequest, InteractionCommand,
+  InteractionOptionValue, InteractionPayload, InteractionState, InteractionUser, MessageResponse,
+};
+use crate::services::{
+  ApplicationService, AuthService, ChannelService, EmojiService, MessageService, ServerService,
+};
+use crate::utils::{AppError, AppResult, ErrorCode, random_token};
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction, types::Json};
+use uuid::Uuid;
+
+const MAX_COMMAND_NAME_LENGTH: usize = 32;
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+const MAX_OPTIONS: usize = 25;
+// How long the application has to respond, including follow-ups to a deferred response
+const INTERACTION_TTL_MINUTES: i64 = 15;
+
+pub struct InteractionService;
+
+impl InteractionService {
+  // Lowercase letters, digits, dashes and underscores, so names can be typed after a slash
+  fn validate_name(field: &'static str, name: &str) -> AppResult<()> {
+    if name.is_empty() {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::FieldRequired,
+        "Name cannot be empty",
+      ));
+    }
+    if name.chars().count() > MAX_COMMAND_NAME_LENGTH {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::FieldTooLong,
+        "Name must be at most 32 characters",
+      ));
+    }
+    if !name
+      .chars()
+      .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'))
+    {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::InvalidFormat,
+        "Name can only contain lowercase letters, digits, dashes and underscores",
+      ));
+    }
+
+    Ok(())
+  }
+
+  fn validate_description(field: &'static str, description: &str) -> AppResult<()> {
+    if description.is_empty() {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::FieldRequired,
+        "Description cannot be empty",
+      ));
+    }
+    if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::FieldTooLong,
+        "Description must be at most 100 characters",
+      ));
+    }
+
+    Ok(())
+  }
+
+  fn validate_command(req: &CreateCommandRequest) -> AppResult<()> {
+    Self::validate_name("name", &req.name)?;
+    Self::validate_description("description", req.description.trim())?;
+
+    if req.options.len() > MAX_OPTIONS {
+      return Err(AppError::invalid_field(
+        "options",
+        ErrorCode::FieldTooLong,
+        "Commands can have at most 25 options",
+      ));
+    }
+
+    for (i, option) in req.options.iter().enumerate() {
+      Self::validate_name("options", &option.name)?;
+      Self::validate_description("options", option.description.trim())?;
+
+      if req.options[..i]
+        .iter()
+        .any(|other| other.name == option.name)
+      {
+        return Err(AppError::invalid_field(
+          "options",
+          ErrorCode::AlreadyExists,
+          format!("Option '{}' is defined twice", option.name),
+        ));
+      }
+    }
+
+    Ok(())
+  }
+
+  // Commands are managed by the application owner or by its own bot
+  async fn require_command_manager(
+    db: &PgPool,
+    application_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Application> {
+    let application = ApplicationService::get_application(db, application_id).await?;
+
+    if application.owner_id != user_id && application.bot_user_id != user_id {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownApplication,
+        "Application not found".to_string(),
+      ));
+    }
+
+    Ok(application)
+  }
+
+  pub async fn create_command(
+    db: &PgPool,
+    application_id: Uuid,
+    server_id: Option<Uuid>,
+    user_id: Uuid,
+    req: CreateCommandRequest,
+  ) -> AppResult<ApplicationCommand> {
+    let application = Self::require_command_manager(db, application_id, user_id).await?;
+
+    if let Some(server_id) = server_id
+      && !ServerService::is_member(db, server_id, application.bot_user_id).await?
+    {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownServer,
+        "The bot is not a member of this server".to_string(),
+      ));
+    }
+
+    Self::validate_command(&req)?;
+
+    let command = sqlx::query_as::<_, ApplicationCommand>(
+      r#"
+      INSERT INTO application_commands (application_id, server_id, name, description, options)
+      VALUES ($1, $2, $3, $4, $5)
+      ON CONFLICT (application_id, server_id, name) DO UPDATE
+      SET description = EXCLUDED.description, options = EXCLUDED.options, updated_at = NOW()
+      RETURNING id, application_id, server_id, name, description, options, created_at, updated_at
+      "#,
+    )
+    .bind(application_id)
+    .bind(server_id)
+    .bind(&req.name)
+    .bind(req.description.trim())
+    .bind(Json(&req.options))
+    .fetch_one(db)
+    .await?;
+
+    Ok(command)
+  }
+
+  pub async fn get_commands(
+    db: &PgPool,
+    application_id: Uuid,
+    server_id: Option<Uuid>,
+    user_id: Uuid,
+  ) -> AppResult<Vec<ApplicationCommand>> {
+    Self::require_command_manager(db, application_id, user_id).await?;
+
+    let commands = sqlx::query_as::<_, ApplicationCommand>(
+      r#"
+      SELECT id, application_id, server_id, name, description, options, created_at, updated_at
+      FROM application_commands
+      WHERE application_id = $1 AND server_id IS NOT DISTINCT FROM $2
+      ORDER BY name ASC
+      "#,
+    )
+    .bind(application_id)
+    .bind(server_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(commands)
+  }
+
+  pub async fn delete_command(
+    db: &PgPool,
+    application_id: Uuid,
+    command_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<()> {
+    Self::require_command_manager(db, application_id, user_id).await?;
+
+    let result =
+      sqlx::query("DELETE FROM application_commands WHERE id = $1 AND application_id = $2")
+        .bind(command_id)
+        .bind(application_id)
+        .execute(db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownCommand,
+        "Unknown command".to_string(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Global and server commands of every bot in the server
+  pub async fn get_server_commands(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Vec<ApplicationCommand>> {
+    if !ServerService::is_member(db, server_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You are not a member of this server".to_string(),
+      ));
+    }
+
+    let commands = sqlx::query_as::<_, ApplicationCommand>(
+      r#"
+      SELECT c.id, c.application_id, c.server_id, c.name, c.description, c.options, c.created_at, c.updated_at
+      FROM application_commands c
+      INNER JOIN applications a ON a.id = c.application_id
+      INNER JOIN server_members sm ON sm.user_id = a.bot_user_id AND sm.server_id = $1
+      WHERE c.server_id IS NULL OR c.server_id = $1
+      ORDER BY c.name ASC, c.application_id ASC
+      "#,
+    )
+    .bind(server_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(commands)
+  }
+
+  fn validate_options(
+    definitions: &[CommandOption],
+    values: Vec<InteractionOptionValue>,
+  ) -> AppResult<Vec<InteractionOptionValue>> {
+    for (i, value) in values.iter().enumerate() {
+      let Some(definition) = definitions.iter().find(|d| d.name == value.name) else {
+        return Err(AppError::invalid_field(
+          "options",
+          ErrorCode::InvalidFormat,
+          format!("Unknown option '{}'", value.name),
+        ));
+      };
+
+      if values[..i].iter().any(|other| other.name == value.name) {
+        return Err(AppError::invalid_field(
+          "options",
+          ErrorCode::InvalidFormat,
+          format!("Option '{}' was given twice", value.name),
+        ));
+      }
+
+      let valid = match definition.option_type {
+        CommandOptionType::String => value.value.is_string(),
+        CommandOptionType::Integer => value.value.is_i64() || value.value.is_u64(),
+        CommandOptionType::Number => value.value.is_number(),
+        CommandOptionType::Boolean => value.value.is_boolean(),
+        CommandOptionType::User | CommandOptionType::Channel => value
+          .value
+          .as_str()
+          .is_some_and(|id| Uuid::parse_str(id).is_ok()),
+      };
+      if !valid {
+        return Err(AppError::invalid_field(
+          "options",
+          ErrorCode::InvalidFormat,
+          format!("Option '{}' has the wrong type", value.name),
+        ));
+      }
+    }
+
+    if let Some(missing) = definitions
+      .iter()
+      .find(|d| d.required && !values.iter().any(|v| v.name == d.name))
+    {
+      return Err(AppError::invalid_field(
+        "options",
+        ErrorCode::FieldRequired,
+        format!("Option '{}' is required", missing.name),
+      ));
+    }
+
+    Ok(values)
+  }
+
+  // Records the invocation and returns what is sent to the application, token included
+  pub async fn create_interaction(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    req: CreateInteractionRequest,
+  ) -> AppResult<(Application, InteractionPayload)> {
+    if !ChannelService::user_has_access_to_channel(db, req.channel_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You don't have access to that channel".to_string(),
+      ));
+    }
+
+    let channel = ChannelService::get_channel_by_id(db, req.channel_id).await?;
+    let Some(server_id) = channel.server_id else {
+      return Err(AppError::BadRequest(
+        ErrorCode::InvalidChannel,
+        "Commands can only be used in server channels".to_string(),
+      ));
+    };
+
+    // Only commands of bots in the server, global or registered for it, can be run
+    let command = sqlx::query_as::<_, ApplicationCommand>(
+      r#"
+      SELECT c.id, c.application_id, c.server_id, c.name, c.description, c.options, c.created_at, c.updated_at
+      FROM application_commands c
+      INNER JOIN applications a ON a.id = c.application_id
+      INNER JOIN server_members sm ON sm.user_id = a.bot_user_id AND sm.server_id = $2
+      WHERE c.id = $1 AND (c.server_id IS NULL OR c.server_id = $2)
+      "#,
+    )
+    .bind(req.command_id)
+    .bind(server_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownCommand, "Unknown command".to_string()))?;
+
+    let options = Self::validate_options(&command.options.0, req.options)?;
+    let application = ApplicationService::get_application(db, command.application_id).await?;
+
+    let username: String = sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
+      .bind(user_id)
+      .fetch_one(db)
+      .await?;
+
+    sqlx::query("DELETE FROM interactions WHERE expires_at < NOW()")
+      .execute(db)
+      .await?;
+
+    let token = random_token();
+
+    let interaction_id: Uuid = sqlx::query_scalar(
+      r#"
+      INSERT INTO interactions (application_id, command_id, channel_id, user_id, token_hash, expires_at)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      RETURNING id
+      "#,
+    )
+    .bind(application.id)
+    .bind(command.id)
+    .bind(channel.id)
+    .bind(user_id)
+    .bind(AuthService::hash_user_token(jwt, &token))
+    .bind(Utc::now() + Duration::minutes(INTERACTION_TTL_MINUTES))
+    .fetch_one(db)
+    .await?;
+
+    let payload = InteractionPayload {
+      id: interaction_id,
+      token,
+      application_id: application.id,
+      command: InteractionCommand {
+        id: command.id,
+        name: command.name,
+      },
+      options,
+      channel_id: channel.id,
+      server_id,
+      user: InteractionUser {
+        id: user_id,
+        username,
+      },
+    };
+
+    Ok((application, payload))
+  }
+
+  // A wrong token is reported the same way as a missing or expired interaction
+  async fn lock_interaction(
+    tx: &mut Transaction<'_, Postgres>,
+    jwt: &JwtConfig,
+    interaction_id: Uuid,
+    token: &str,
+  ) -> AppResult<Interaction> {
+    sqlx::query_as::<_, Interaction>(
+      r#"
+      SELECT id, application_id, command_id, channel_id, user_id, state, ephemeral, expires_at, created_at
+      FROM interactions
+      WHERE id = $1 AND token_hash = $2 AND expires_at > NOW()
+      FOR UPDATE
+      "#,
+    )
+    .bind(interaction_id)
+    .bind(AuthService::hash_user_token(jwt, token))
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| {
+      AppError::NotFound(
+        ErrorCode::UnknownInteraction,
+        "Unknown interaction".to_string(),
+      )
+    })
+  }
+
+  async fn set_state(
+    tx: &mut Transaction<'_, Postgres>,
+    interaction_id: Uuid,
+    state: InteractionState,
+    ephemeral: bool,
+  ) -> AppResult<Interaction> {
+    let interaction = sqlx::query_as::<_, Interaction>(
+      r#"
+      UPDATE interactions
+      SET state = $2, ephemeral = $3
+      WHERE id = $1
+      RETURNING id, application_id, command_id, channel_id, user_id, state, ephemeral, expires_at, created_at
+      "#,
+    )
+    .bind(interaction_id)
+    .bind(state)
+    .bind(ephemeral)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(interaction)
+  }
+
+  // The first response to an interaction, either the content itself or a deferral
+  pub async fn acknowledge(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    limits: &LimitsConfig,
+    interaction_id: Uuid,
+    token: &str,
+    callback: &InteractionCallbackRequest,
+  ) -> AppResult<Interaction> {
+    let mut tx = db.begin().await?;
+
+    let interaction = Self::lock_interaction(&mut tx, jwt, interaction_id, token).await?;
+    if interaction.state != InteractionState::Pending {
+      return Err(AppError::Conflict(
+        ErrorCode::InteractionAlreadyAcknowledged,
+        "This interaction has already been responded to".to_string(),
+      ));
+    }
+
+    let interaction = match callback {
+      InteractionCallbackRequest::ChannelMessage { content, ephemeral } => {
+        MessageService::validate_content(limits, content)?;
+        Self::set_state(
+          &mut tx,
+          interaction.id,
+          InteractionState::Completed,
+          *ephemeral,
+        )
+        .await?
+      }
+      InteractionCallbackRequest::Deferred { ephemeral } => {
+        Self::set_state(
+          &mut tx,
+          interaction.id,
+          InteractionState::Deferred,
+          *ephemeral,
+        )
+        .await?
+      }
+    };
+
+    tx.commit().await?;
+
+    Ok(interaction)
+  }
+
+  // Supplies the content of a deferred response, shown the way the deferral asked for
+  pub async fn complete_deferred(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    limits: &LimitsConfig,
+    interaction_id: Uuid,
+    token: &str,
+    content: &str,
+  ) -> AppResult<Interaction> {
+    let mut tx = db.begin().await?;
+
+    let interaction = Self::lock_interaction(&mut tx, jwt, interaction_id, token).await?;
+    match interaction.state {
+      InteractionState::Deferred => {}
+      InteractionState::Pending => {
+        return Err(AppError::BadRequest(
+          ErrorCode::InteractionFailed,
+          "The interaction has not been deferred".to_string(),
+        ));
+      }
+      InteractionState::Completed => {
+        return Err(AppError::Conflict(
+          ErrorCode::InteractionAlreadyAcknowledged,
+          "This interaction has already been responded to".to_string(),
+        ));
+      }
+    }
+
+    MessageService::validate_content(limits, content)?;
+    let interaction = Self::set_state(
+      &mut tx,
+      interaction.id,
+      InteractionState::Completed,
+      interaction.ephemeral,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(interaction)
+  }
+
+  // Public responses are posted to the channel by the application's bot
+  pub async fn post_response_message(
+    db: &PgPool,
+    interaction: &Interaction,
+    bot_user_id: Uuid,
+    content: &str,
+  ) -> AppResult<MessageResponse> {
+    let server_id = ChannelService::get_channel_by_id(db, interaction.channel_id)
+      .await?
+      .server_id;
+    let content = EmojiService::resolve_content(db, content, server_id).await?;
+
+    let message = sqlx::query_as::<_, MessageResponse>(
+      r#"
+      WITH inserted AS (
+        INSERT INTO messages (channel_id, user_id, content)
+        VALUES ($1, $2, $3)
+        RETURNING *
+      )
+      SELECT
+        m.id,
+        m.channel_id,
+        m.user_id,
+        u.username,
+        m.webhook_id,
+        m.author_avatar_url AS avatar_url,
+        m.message_type,
+        m.content,
+        m.system_data,
+        m.created_at,
+        m.updated_at
+      FROM inserted m
+      INNER JOIN users u ON m.user_id = u.id
+      "#,
+    )
+    .bind(interaction.channel_id)
+    .bind(bot_user_id)
+    .bind(&content)
+    .fetch_one(db)
+    .await?;
+
+    Ok(message)
+  }
+}
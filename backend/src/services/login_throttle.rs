@@ -0,0 +1,310 @@
+// backend/src/services/login_throttle.rs
+use crate::utils::{AppError, AppResult};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+// Failures older than this no longer count towards a lockout
+const FAILURE_WINDOW_MINUTES: i64 = 15;
+
+// Shared by accounts (two-factor codes) and login identifiers (passwords)
+const ACCOUNT_FREE_ATTEMPTS: i32 = 5;
+const ACCOUNT_BASE_LOCKOUT_SECS: i64 = 30;
+
+// Higher because many users may share an address
+const IP_FREE_ATTEMPTS: i32 = 20;
+const IP_BASE_LOCKOUT_SECS: i64 = 60;
+
+const MAX_LOCKOUT_SECS: i64 = 60 * 60;
+
+pub struct LoginThrottleService;
+
+impl LoginThrottleService {
+  // Doubles the lockout for every failure past the free attempts
+  fn lockout(failures: i32, free_attempts: i32, base_secs: i64) -> Option<Duration> {
+    let over = failures - free_attempts;
+    if over < 0 {
+      return None;
+    }
+
+    let secs = base_secs
+      .saturating_mul(1i64 << over.min(20))
+      .min(MAX_LOCKOUT_SECS);
+    Some(Duration::seconds(secs))
+  }
+
+  fn retry_after(locked_until: DateTime<Utc>) -> AppError {
+    let secs = (locked_until - Utc::now()).num_seconds().max(1);
+    AppError::RateLimited(secs as u64)
+  }
+
+  pub async fn check_ip(db: &PgPool, ip: Option<IpAddr>) -> AppResult<()> {
+    let Some(ip) = ip else {
+      return Ok(());
+    };
+
+    let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+      "SELECT locked_until FROM login_ip_failures WHERE ip = $1 AND locked_until > NOW()",
+    )
+    .bind(ip.to_string())
+    .fetch_optional(db)
+    .await?
+    .flatten();
+
+    match locked_until {
+      Some(locked_until) => {
+        tracing::warn!(target: "security", %ip, "Login rejected, address is locked out");
+        Err(Self::retry_after(locked_until))
+      }
+      None => Ok(()),
+    }
+  }
+
+  pub async fn check_identifier(
+    db: &PgPool,
+    identifier: &str,
+    ip: Option<IpAddr>,
+  ) -> AppResult<()> {
+    let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+      "SELECT locked_until FROM login_identifier_failures WHERE identifier = $1 AND locked_until > NOW()",
+    )
+    .bind(identifier)
+    .fetch_optional(db)
+    .await?
+    .flatten();
+
+    match locked_until {
+      Some(locked_until) => {
+        tracing::warn!(target: "security", ?ip, "Login rejected, identifier is locked out");
+        Err(Self::retry_after(locked_until))
+      }
+      None => Ok(()),
+    }
+  }
+
+  pub async fn check_account(db: &PgPool, user_id: Uuid, ip: Option<IpAddr>) -> AppResult<()> {
+    let locked_until: Option<DateTime<Utc>> =
+      sqlx::query_scalar("SELECT locked_until FROM users WHERE id = $1 AND locked_until > NOW()")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .flatten();
+
+    match locked_until {
+      Some(locked_until) => {
+        tracing::warn!(target: "security", %user_id, ?ip, "Login rejected, account is locked out");
+        Err(Self::retry_after(locked_until))
+      }
+      None => Ok(()),
+    }
+  }
+
+  pub async fn record_failure(
+    db: &PgPool,
+    user_id: Option<Uuid>,
+    ip: Option<IpAddr>,
+  ) -> AppResult<()> {
+    if let Some(user_id) = user_id {
+      Self::record_account_failure(db, user_id, ip).await?;
+    }
+
+    Self::record_ip_failure(db, ip).await
+  }
+
+  async fn record_account_failure(db: &PgPool, user_id: Uuid, ip: Option<IpAddr>) -> AppResult<()> {
+    let failures: i32 = sqlx::query_scalar(
+      r#"
+      UPDATE users
+      SET
+        failed_login_attempts = CASE
+          WHEN last_failed_login_at > NOW() - make_interval(mins => $2) THEN failed_login_attempts + 1
+          ELSE 1
+        END,
+        last_failed_login_at = NOW()
+      WHERE id = $1
+      RETURNING failed_login_attempts
+      "#,
+    )
+    .bind(user_id)
+    .bind(FAILURE_WINDOW_MINUTES as i32)
+    .fetch_one(db)
+    .await?;
+
+    tracing::warn!(target: "security", %user_id, ?ip, failures, "Failed login attempt");
+
+    if let Some(lockout) = Self::lockout(failures, ACCOUNT_FREE_ATTEMPTS, ACCOUNT_BASE_LOCKOUT_SECS)
+    {
+      sqlx::query("UPDATE users SET locked_until = $1 WHERE id = $2")
+        .bind(Utc::now() + lockout)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+      tracing::warn!(
+        target: "security",
+        %user_id,
+        seconds = lockout.num_seconds(),
+        "Account locked after repeated login failures"
+      );
+    }
+
+    Ok(())
+  }
+
+  // Counted the same way for unknown identifiers as for existing accounts. An existing account
+  // is also charged, so its email and username do not each get their own budget
+  pub async fn record_identifier_failure(
+    db: &PgPool,
+    identifier: &str,
+    user_id: Option<Uuid>,
+    ip: Option<IpAddr>,
+  ) -> AppResult<()> {
+    let failures: i32 = sqlx::query_scalar(
+      r#"
+      INSERT INTO login_identifier_failures (identifier, failures, last_failed_at)
+      VALUES ($1, 1, NOW())
+      ON CONFLICT (identifier) DO UPDATE
+      SET
+        failures = CASE
+          WHEN login_identifier_failures.last_failed_at > NOW() - make_interval(mins => $2)
+            THEN login_identifier_failures.failures + 1
+          ELSE 1
+        END,
+        last_failed_at = NOW()
+      RETURNING failures
+      "#,
+    )
+    .bind(identifier)
+    .bind(FAILURE_WINDOW_MINUTES as i32)
+    .fetch_one(db)
+    .await?;
+
+    tracing::warn!(target: "security", ?ip, failures, "Failed login attempt");
+
+    if let Some(lockout) = Self::lockout(failures, ACCOUNT_FREE_ATTEMPTS, ACCOUNT_BASE_LOCKOUT_SECS)
+    {
+      sqlx::query("UPDATE login_identifier_failures SET locked_until = $1 WHERE identifier = $2")
+        .bind(Utc::now() + lockout)
+        .bind(identifier)
+        .execute(db)
+        .await?;
+
+      tracing::warn!(
+        target: "security",
+        ?ip,
+        seconds = lockout.num_seconds(),
+        "Identifier locked after repeated login failures"
+      );
+    }
+
+    Self::record_failure(db, user_id, ip).await
+  }
+
+  async fn record_ip_failure(db: &PgPool, ip: Option<IpAddr>) -> AppResult<()> {
+    if let Some(ip) = ip {
+      let failures: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO login_ip_failures (ip, failures, last_failed_at)
+        VALUES ($1, 1, NOW())
+        ON CONFLICT (ip) DO UPDATE
+        SET
+          failures = CASE
+            WHEN login_ip_failures.last_failed_at > NOW() - make_interval(mins => $2)
+              THEN login_ip_failures.failures + 1
+            ELSE 1
+          END,
+          last_failed_at = NOW()
+        RETURNING failures
+        "#,
+      )
+      .bind(ip.to_string())
+      .bind(FAILURE_WINDOW_MINUTES as i32)
+      .fetch_one(db)
+      .await?;
+
+      if let Some(lockout) = Self::lockout(failures, IP_FREE_ATTEMPTS, IP_BASE_LOCKOUT_SECS) {
+        sqlx::query("UPDATE login_ip_failures SET locked_until = $1 WHERE ip = $2")
+          .bind(Utc::now() + lockout)
+          .bind(ip.to_string())
+          .execute(db)
+          .await?;
+
+        tracing::warn!(
+          target: "security",
+          %ip,
+          seconds = lockout.num_seconds(),
+          "Address locked after repeated login failures"
+        );
+      }
+    }
+
+    Ok(())
+  }
+
+  // A correct password clears its identifier, the account counter waits for any two-factor code
+  pub async fn record_password_success(db: &PgPool, identifier: &str) -> AppResult<()> {
+    sqlx::query("DELETE FROM login_identifier_failures WHERE identifier = $1")
+      .bind(identifier)
+      .execute(db)
+      .await?;
+
+    Ok(())
+  }
+
+  // Address counters are left to decay so one valid login cannot reset them
+  pub async fn record_success(db: &PgPool, user_id: Uuid, ip: Option<IpAddr>) -> AppResult<()> {
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET failed_login_attempts = 0, last_failed_login_at = NULL, locked_until = NULL
+      WHERE id = $1
+      "#,
+    )
+    .bind(user_id)
+    .execute(db)
+    .await?;
+
+    tracing::info!(target: "security", %user_id, ?ip, "Successful login");
+
+    Ok(())
+  }
+
+  // Drops address and identifier counters that have both decayed and unlocked
+  pub fn spawn_cleanup(db: PgPool) {
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 10));
+      loop {
+        interval.tick().await;
+
+        if let Err(e) = sqlx::query(
+          r#"
+          DELETE FROM login_identifier_failures
+          WHERE last_failed_at < NOW() - make_interval(mins => $1)
+            AND (locked_until IS NULL OR locked_until < NOW())
+          "#,
+        )
+        .bind(FAILURE_WINDOW_MINUTES as i32)
+        .execute(&db)
+        .await
+        {
+          tracing::error!("Failed to clean up login failures: {}", e);
+        }
+
+        if let Err(e) = sqlx::query(
+          r#"
+          DELETE FROM login_ip_failures
+          WHERE last_failed_at < NOW() - make_interval(mins => $1)
+            AND (locked_until IS NULL OR locked_until < NOW())
+          "#,
+        )
+        .bind(FAILURE_WINDOW_MINUTES as i32)
+        .execute(&db)
+        .await
+        {
+          tracing::error!("Failed to clean up login failures: {}", e);
+        }
+      }
+    });
+  }
+}
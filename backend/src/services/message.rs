@@ -1,7 +1,13 @@
-use crate::models::{CreateMessageRequest, Message, MessageResponse};
-use crate::services::channel::ChannelService;
-use crate::utils::{AppError, AppResult};
-use sqlx::PgPool;
+use crate::config::LimitsConfig;
+use crate::models::{
+  AuditLogAction, AuditLogChange, Channel, ChannelType, CreateMessageRequest, Message,
+  MessageResponse, MessageType, SystemMessageData, UpdateMessageRequest,
+};
+use crate::services::{
+  AuditLogService, ChannelService, EmojiService, FriendshipService, ServerService,
+};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use sqlx::{PgPool, types::Json};
 use uuid::Uuid;
 
 pub struct MessageService;
@@ -9,38 +15,46 @@ pub struct MessageService;
 impl MessageService {
   pub async fn create_message(
     db: &PgPool,
+    limits: &LimitsConfig,
     channel_id: Uuid,
     user_id: Uuid,
     req: CreateMessageRequest,
   ) -> AppResult<MessageResponse> {
     if !ChannelService::user_has_access_to_channel(db, channel_id, user_id).await? {
-      return Err(AppError::Unauthorized(
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
         "You don't have access to that channel".to_string(),
       ));
     }
 
-    if req.content.trim().is_empty() {
-      return Err(AppError::ValidationError(
-        "Message content cannot be empty".to_string(),
-      ));
+    let channel = ChannelService::get_channel_by_id(db, channel_id).await?;
+    if channel.slowmode_seconds > 0 {
+      Self::check_slowmode(db, &channel, user_id).await?;
     }
 
-    if req.content.len() > 2000 {
-      return Err(AppError::ValidationError(
-        "Message cannot exceed 2000 characters".to_string(),
-      ));
+    if let ChannelType::Dm = channel.channel_type {
+      let recipient_id = ChannelService::get_dm_recipient(db, channel_id, user_id).await?;
+      if FriendshipService::user_is_blocked_by(db, user_id, recipient_id).await? {
+        return Err(AppError::Forbidden(
+          ErrorCode::CannotDmUser,
+          "Unable to send messages to this user".to_string(),
+        ));
+      }
     }
 
+    Self::validate_content(limits, &req.content)?;
+    let content = EmojiService::resolve_content(db, &req.content, channel.server_id).await?;
+
     let message = sqlx::query_as::<_, Message>(
       r#"
       INSERT INTO messages (channel_id, user_id, content)
       VALUES ($1, $2, $3)
-      RETURNING id, channel_id, user_id, content, created_at, updated_at
+      RETURNING id, channel_id, user_id, message_type, content, system_data, created_at, updated_at
       "#,
     )
     .bind(channel_id)
     .bind(user_id)
-    .bind(&req.content)
+    .bind(&content)
     .fetch_one(db)
     .await?;
 
@@ -49,19 +63,235 @@ impl MessageService {
       .fetch_one(db)
       .await?;
 
-    let message = MessageResponse {
-      id: message.id,
-      channel_id: message.channel_id,
-      user_id: message.user_id,
-      content: message.content,
-      created_at: message.created_at,
-      updated_at: message.updated_at,
-      username,
-    };
+    Ok(message.into_response(Some(username)))
+  }
+
+  pub fn validate_content(limits: &LimitsConfig, content: &str) -> AppResult<()> {
+    if content.trim().is_empty() {
+      return Err(AppError::invalid_field(
+        "content",
+        ErrorCode::FieldRequired,
+        "Message content cannot be empty",
+      ));
+    }
+
+    if content.chars().count() > limits.max_message_length {
+      return Err(AppError::invalid_field(
+        "content",
+        ErrorCode::MessageTooLong,
+        format!(
+          "Message cannot exceed {} characters",
+          limits.max_message_length
+        ),
+      ));
+    }
+
+    Ok(())
+  }
+
+  // The server owner is exempt, everyone else waits slowmode_seconds between messages
+  async fn check_slowmode(db: &PgPool, channel: &Channel, user_id: Uuid) -> AppResult<()> {
+    if let Some(server_id) = channel.server_id
+      && ServerService::get_server_by_id(db, server_id)
+        .await?
+        .owner_id
+        == user_id
+    {
+      return Ok(());
+    }
+
+    let wait: Option<f64> = sqlx::query_scalar(
+      r#"
+      SELECT EXTRACT(EPOCH FROM (MAX(created_at) + make_interval(secs => $3) - NOW()))::float8
+      FROM messages
+      WHERE channel_id = $1 AND user_id = $2
+      "#,
+    )
+    .bind(channel.id)
+    .bind(user_id)
+    .bind(channel.slowmode_seconds as f64)
+    .fetch_one(db)
+    .await?;
+
+    match wait {
+      Some(wait) if wait > 0.0 => Err(AppError::RateLimited(wait.ceil() as u64)),
+      _ => Ok(()),
+    }
+  }
+
+  pub async fn create_system_message(
+    db: &PgPool,
+    channel_id: Uuid,
+    data: SystemMessageData,
+  ) -> AppResult<MessageResponse> {
+    let message = sqlx::query_as::<_, Message>(
+      r#"
+      INSERT INTO messages (channel_id, message_type, content, system_data)
+      VALUES ($1, $2, $3, $4)
+      RETURNING id, channel_id, user_id, message_type, content, system_data, created_at, updated_at
+      "#,
+    )
+    .bind(channel_id)
+    .bind(data.message_type())
+    .bind(data.content())
+    .bind(Json(&data))
+    .fetch_one(db)
+    .await?;
+
+    Ok(message.into_response(None))
+  }
+
+  async fn get_message(db: &PgPool, channel_id: Uuid, message_id: Uuid) -> AppResult<Message> {
+    sqlx::query_as::<_, Message>(
+      r#"
+      SELECT id, channel_id, user_id, message_type, content, system_data, created_at, updated_at
+      FROM messages
+      WHERE id = $1 AND channel_id = $2
+      "#,
+    )
+    .bind(message_id)
+    .bind(channel_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownMessage, "Unknown message".to_string()))
+  }
+
+  // Only the author can edit, system and webhook messages cannot be edited
+  pub async fn update_message(
+    db: &PgPool,
+    limits: &LimitsConfig,
+    channel_id: Uuid,
+    message_id: Uuid,
+    user_id: Uuid,
+    req: UpdateMessageRequest,
+  ) -> AppResult<MessageResponse> {
+    if !ChannelService::user_has_access_to_channel(db, channel_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You don't have access to that channel".to_string(),
+      ));
+    }
+
+    let message = Self::get_message(db, channel_id, message_id).await?;
+    if message.user_id != Some(user_id) || message.message_type != MessageType::Default {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "You can only edit your own messages".to_string(),
+      ));
+    }
+
+    Self::validate_content(limits, &req.content)?;
+    let server_id = ChannelService::get_channel_by_id(db, channel_id)
+      .await?
+      .server_id;
+    let content = EmojiService::resolve_content(db, &req.content, server_id).await?;
+
+    let message = sqlx::query_as::<_, MessageResponse>(
+      r#"
+      WITH updated AS (
+        UPDATE messages
+        SET content = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING *
+      )
+      SELECT
+        m.id,
+        m.channel_id,
+        m.user_id,
+        COALESCE(u.username, m.author_name) AS username,
+        m.webhook_id,
+        m.author_avatar_url AS avatar_url,
+        m.message_type,
+        m.content,
+        m.system_data,
+        m.created_at,
+        m.updated_at
+      FROM updated m
+      LEFT JOIN users u ON m.user_id = u.id
+      "#,
+    )
+    .bind(&content)
+    .bind(message_id)
+    .fetch_one(db)
+    .await?;
 
     Ok(message)
   }
 
+  // Authors can delete their own messages, the server owner can delete any in the server and
+  // that is recorded in the audit log
+  pub async fn delete_message(
+    db: &PgPool,
+    channel_id: Uuid,
+    message_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<()> {
+    if !ChannelService::user_has_access_to_channel(db, channel_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You don't have access to that channel".to_string(),
+      ));
+    }
+
+    let message = Self::get_message(db, channel_id, message_id).await?;
+    let mut moderated_server_id = None;
+    if message.user_id != Some(user_id) {
+      let channel = ChannelService::get_channel_by_id(db, channel_id).await?;
+      if let Some(server_id) = channel.server_id
+        && ServerService::get_server_by_id(db, server_id)
+          .await?
+          .owner_id
+          == user_id
+      {
+        moderated_server_id = Some(server_id);
+      } else {
+        return Err(AppError::Forbidden(
+          ErrorCode::MissingPermissions,
+          "You can only delete your own messages".to_string(),
+        ));
+      }
+    }
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM messages WHERE id = $1")
+      .bind(message_id)
+      .execute(&mut *tx)
+      .await?;
+
+    // The content is not kept, only where the message was
+    if let Some(server_id) = moderated_server_id {
+      let changes = vec![
+        AuditLogChange {
+          key: "message_id".to_string(),
+          old_value: Some(serde_json::json!(message_id)),
+          new_value: None,
+        },
+        AuditLogChange {
+          key: "channel_id".to_string(),
+          old_value: Some(serde_json::json!(channel_id)),
+          new_value: None,
+        },
+      ];
+
+      AuditLogService::record(
+        &mut *tx,
+        server_id,
+        user_id,
+        AuditLogAction::MessageDelete,
+        message.user_id,
+        changes,
+        reason,
+      )
+      .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+
   pub async fn get_channel_messages(
     db: &PgPool,
     channel_id: Uuid,
@@ -75,12 +305,16 @@ impl MessageService {
           m.id,
           m.channel_id,
           m.user_id,
-          u.username,
+          COALESCE(u.username, m.author_name) AS username,
+          m.webhook_id,
+          m.author_avatar_url AS avatar_url,
+          m.message_type,
           m.content,
+          m.system_data,
           m.created_at,
           m.updated_at
         FROM messages m
-        INNER JOIN users u ON m.user_id = u.id
+        LEFT JOIN users u ON m.user_id = u.id
         WHERE m.channel_id = $1
           AND m.created_at < (SELECT created_at FROM messages WHERE id = $2)
         ORDER BY m.created_at ASC
@@ -99,12 +333,16 @@ impl MessageService {
           m.id,
           m.channel_id,
           m.user_id,
-          u.username,
+          COALESCE(u.username, m.author_name) AS username,
+          m.webhook_id,
+          m.author_avatar_url AS avatar_url,
+          m.message_type,
           m.content,
+          m.system_data,
           m.created_at,
           m.updated_at
         FROM messages m
-        INNER JOIN users u ON m.user_id = u.id
+        LEFT JOIN users u ON m.user_id = u.id
         WHERE m.channel_id = $1
         ORDER BY m.created_at ASC
         LIMIT $2
@@ -0,0 +1,364 @@
+// backend/src/services/mfa.rs
+use crate::config::JwtConfig;
+use crate::models::{User, UserTokenKind};
+use crate::services::{AuthService, LoginThrottleService};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use chrono::{Duration, Utc};
+use rand::{Rng, RngCore};
+use sqlx::PgPool;
+use std::net::IpAddr;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+const TOTP_ISSUER: &str = "Harmony";
+const TOTP_STEP: u64 = 30;
+const BACKUP_CODE_COUNT: usize = 10;
+
+pub struct MfaService;
+
+impl MfaService {
+  fn totp(secret: &str, username: &str) -> AppResult<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string())
+      .to_bytes()
+      .map_err(|e| AppError::InternalServerError(format!("Invalid TOTP secret: {:?}", e)))?;
+
+    // Skew is handled by verify_totp so each step can only be used once
+    TOTP::new(
+      Algorithm::SHA1,
+      6,
+      0,
+      TOTP_STEP,
+      bytes,
+      Some(TOTP_ISSUER.to_string()),
+      username.to_string(),
+    )
+    .map_err(|e| AppError::InternalServerError(format!("Failed to create TOTP: {}", e)))
+  }
+
+  // Returns the matching time step, allowing one step of clock drift either way
+  fn verify_totp(totp: &TOTP, code: &str, last_used_step: Option<i64>) -> Option<i64> {
+    let current = Utc::now().timestamp() / TOTP_STEP as i64;
+
+    (current - 1..=current + 1)
+      .filter(|step| last_used_step.is_none_or(|last| *step > last))
+      .find(|step| totp.check(code, *step as u64 * TOTP_STEP))
+  }
+
+  fn normalize_backup_code(code: &str) -> String {
+    code
+      .chars()
+      .filter(|c| c.is_ascii_alphanumeric())
+      .collect::<String>()
+      .to_lowercase()
+  }
+
+  pub async fn enroll(db: &PgPool, user_id: Uuid) -> AppResult<(String, String)> {
+    let (username, enabled): (String, bool) =
+      sqlx::query_as("SELECT username, totp_enabled_at IS NOT NULL FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))?;
+
+    if enabled {
+      return Err(AppError::Conflict(
+        ErrorCode::MfaAlreadyEnabled,
+        "Two-factor authentication is already enabled".to_string(),
+      ));
+    }
+
+    let mut bytes = [0u8; 20];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let Secret::Encoded(secret) = Secret::Raw(bytes.to_vec()).to_encoded() else {
+      unreachable!("to_encoded always returns an encoded secret");
+    };
+
+    let uri = Self::totp(&secret, &username)?.get_url();
+
+    sqlx::query("UPDATE users SET totp_secret = $1, totp_last_used_step = NULL WHERE id = $2")
+      .bind(&secret)
+      .bind(user_id)
+      .execute(db)
+      .await?;
+
+    Ok((secret, uri))
+  }
+
+  // Completes enrollment with a first code and hands out backup codes
+  pub async fn confirm(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    code: &str,
+  ) -> AppResult<Vec<String>> {
+    let (username, secret, enabled): (String, Option<String>, bool) = sqlx::query_as(
+      "SELECT username, totp_secret, totp_enabled_at IS NOT NULL FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))?;
+
+    if enabled {
+      return Err(AppError::Conflict(
+        ErrorCode::MfaAlreadyEnabled,
+        "Two-factor authentication is already enabled".to_string(),
+      ));
+    }
+
+    let secret = secret.ok_or_else(|| {
+      AppError::BadRequest(
+        ErrorCode::MfaEnrollmentNotStarted,
+        "Start two-factor enrollment first".to_string(),
+      )
+    })?;
+
+    let step =
+      Self::verify_totp(&Self::totp(&secret, &username)?, code.trim(), None).ok_or_else(|| {
+        AppError::BadRequest(
+          ErrorCode::InvalidMfaCode,
+          "Invalid two-factor code".to_string(),
+        )
+      })?;
+
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET totp_enabled_at = NOW(), totp_last_used_step = $1, updated_at = NOW()
+      WHERE id = $2
+      "#,
+    )
+    .bind(step)
+    .bind(user_id)
+    .execute(db)
+    .await?;
+
+    Self::generate_backup_codes(db, jwt, user_id).await
+  }
+
+  pub async fn disable(db: &PgPool, jwt: &JwtConfig, user_id: Uuid, code: &str) -> AppResult<()> {
+    Self::require_fresh_code(db, jwt, user_id, Some(code)).await?;
+
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_used_step = NULL, updated_at = NOW()
+      WHERE id = $1
+      "#,
+    )
+    .bind(user_id)
+    .execute(db)
+    .await?;
+
+    sqlx::query("DELETE FROM mfa_backup_codes WHERE user_id = $1")
+      .bind(user_id)
+      .execute(db)
+      .await?;
+
+    Ok(())
+  }
+
+  pub async fn regenerate_backup_codes(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    code: &str,
+  ) -> AppResult<Vec<String>> {
+    if !Self::is_enabled(db, user_id).await? {
+      return Err(AppError::BadRequest(
+        ErrorCode::MfaNotEnabled,
+        "Two-factor authentication is not enabled".to_string(),
+      ));
+    }
+
+    Self::require_fresh_code(db, jwt, user_id, Some(code)).await?;
+    Self::generate_backup_codes(db, jwt, user_id).await
+  }
+
+  async fn generate_backup_codes(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+  ) -> AppResult<Vec<String>> {
+    let codes: Vec<String> = {
+      let mut rng = rand::rngs::OsRng;
+      (0..BACKUP_CODE_COUNT)
+        .map(|_| {
+          let value: u64 = rng.gen_range(0..1u64 << 40);
+          let hex = format!("{:010x}", value);
+          format!("{}-{}", &hex[..5], &hex[5..])
+        })
+        .collect()
+    };
+
+    let hashes: Vec<String> = codes
+      .iter()
+      .map(|code| AuthService::hash_user_token(jwt, &Self::normalize_backup_code(code)))
+      .collect();
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM mfa_backup_codes WHERE user_id = $1")
+      .bind(user_id)
+      .execute(&mut *tx)
+      .await?;
+
+    sqlx::query(
+      r#"
+      INSERT INTO mfa_backup_codes (user_id, code_hash)
+      SELECT $1, UNNEST($2::text[])
+      "#,
+    )
+    .bind(user_id)
+    .bind(&hashes)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(codes)
+  }
+
+  pub async fn is_enabled(db: &PgPool, user_id: Uuid) -> AppResult<bool> {
+    let enabled: Option<bool> =
+      sqlx::query_scalar("SELECT totp_enabled_at IS NOT NULL FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(enabled.unwrap_or(false))
+  }
+
+  // Accepts a TOTP code that has not been used before, or an unused backup code
+  pub async fn verify_code(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    code: &str,
+  ) -> AppResult<bool> {
+    let row: Option<(String, Option<String>, Option<i64>)> = sqlx::query_as(
+      r#"
+      SELECT username, totp_secret, totp_last_used_step
+      FROM users
+      WHERE id = $1 AND totp_enabled_at IS NOT NULL
+      "#,
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+
+    let Some((username, Some(secret), last_used_step)) = row else {
+      return Ok(false);
+    };
+
+    let code = code.trim();
+
+    if let Some(step) = Self::verify_totp(&Self::totp(&secret, &username)?, code, last_used_step) {
+      // Guards against the same code being accepted twice by concurrent requests
+      let result = sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_last_used_step = $1
+        WHERE id = $2 AND (totp_last_used_step IS NULL OR totp_last_used_step < $1)
+        "#,
+      )
+      .bind(step)
+      .bind(user_id)
+      .execute(db)
+      .await?;
+
+      return Ok(result.rows_affected() == 1);
+    }
+
+    let result = sqlx::query(
+      r#"
+      UPDATE mfa_backup_codes
+      SET used_at = NOW()
+      WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+      "#,
+    )
+    .bind(user_id)
+    .bind(AuthService::hash_user_token(
+      jwt,
+      &Self::normalize_backup_code(code),
+    ))
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+  }
+
+  // Gate for sensitive actions, a no-op for users without two-factor authentication
+  pub async fn require_fresh_code(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+    code: Option<&str>,
+  ) -> AppResult<()> {
+    if !Self::is_enabled(db, user_id).await? {
+      return Ok(());
+    }
+
+    let required = || {
+      AppError::Forbidden(
+        ErrorCode::MfaRequired,
+        "A valid two-factor code is required".to_string(),
+      )
+    };
+
+    let Some(code) = code else {
+      return Err(required());
+    };
+
+    // Wrong codes count towards the same lockout as the login step
+    LoginThrottleService::check_account(db, user_id, None).await?;
+
+    if !Self::verify_code(db, jwt, user_id, code).await? {
+      LoginThrottleService::record_failure(db, Some(user_id), None).await?;
+      return Err(required());
+    }
+
+    Ok(())
+  }
+
+  pub async fn create_login_ticket(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    user_id: Uuid,
+  ) -> AppResult<String> {
+    AuthService::issue_user_token(
+      db,
+      jwt,
+      user_id,
+      UserTokenKind::MfaTicket,
+      Duration::minutes(5),
+    )
+    .await
+  }
+
+  // Second login step, the ticket stays valid until a correct code is given
+  // Wrong codes count towards a lockout of the account
+  pub async fn complete_login(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    ticket: &str,
+    code: &str,
+    ip: Option<IpAddr>,
+  ) -> AppResult<User> {
+    LoginThrottleService::check_ip(db, ip).await?;
+    let user_id = AuthService::peek_user_token(db, jwt, ticket, UserTokenKind::MfaTicket).await?;
+    LoginThrottleService::check_account(db, user_id, ip).await?;
+
+    if !Self::verify_code(db, jwt, user_id, code).await? {
+      LoginThrottleService::record_failure(db, Some(user_id), ip).await?;
+      return Err(AppError::Unauthorized(
+        ErrorCode::InvalidMfaCode,
+        "Invalid two-factor code".to_string(),
+      ));
+    }
+
+    AuthService::consume_user_token(db, jwt, ticket, UserTokenKind::MfaTicket).await?;
+    LoginThrottleService::record_success(db, user_id, ip).await?;
+    AuthService::get_user_by_id(db, user_id).await
+  }
+}
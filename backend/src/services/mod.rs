@@ -1,15 +1,45 @@
+pub mod account;
+pub mod application;
+pub mod audit_log;
 pub mod auth;
+pub mod ban;
 pub mod channel;
+pub mod emoji;
 pub mod friendship;
+pub mod interaction;
+pub mod login_throttle;
 pub mod message;
+pub mod mfa;
+pub mod notification;
+pub mod oidc;
 pub mod organization;
+pub mod outgoing_webhook;
+pub mod presence;
 pub mod profile;
+pub mod push;
 pub mod server;
+pub mod voice;
+pub mod webhook;
 
+pub use account::AccountService;
+pub use application::ApplicationService;
+pub use audit_log::AuditLogService;
 pub use auth::AuthService;
+pub use ban::BanService;
 pub use channel::ChannelService;
+pub use emoji::EmojiService;
 pub use friendship::FriendshipService;
+pub use interaction::InteractionService;
+pub use login_throttle::LoginThrottleService;
 pub use message::MessageService;
+pub use mfa::MfaService;
+pub use notification::NotificationService;
+pub use oidc::OidcService;
 pub use organization::OrganizationService;
+pub use outgoing_webhook::OutgoingWebhookService;
+pub use presence::PresenceService;
 pub use profile::ProfileService;
+pub use push::PushService;
 pub use server::ServerService;
+pub use voice::VoiceService;
+pub use webhook::WebhookService;
@@ -0,0 +1,207 @@
+// backend/src/services/notification.rs
+use crate::models::{
+  ChannelNotificationSettings, MessageNotificationTargets, MessageRecipientSettings,
+  MessageResponse, NotificationLevel, NotificationSettingsResponse, ServerNotificationSettings,
+  UpdateChannelNotificationSettingsRequest, UpdateServerNotificationSettingsRequest,
+};
+use crate::services::{ChannelService, ServerService};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct NotificationService;
+
+impl NotificationService {
+  pub async fn get_settings(db: &PgPool, user_id: Uuid) -> AppResult<NotificationSettingsResponse> {
+    let servers = sqlx::query_as::<_, ServerNotificationSettings>(
+      r#"
+      SELECT server_id, notification_level, muted_until, suppress_everyone
+      FROM server_organization
+      WHERE user_id = $1
+      "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    let channels = sqlx::query_as::<_, ChannelNotificationSettings>(
+      r#"
+      SELECT channel_id, notification_level, muted_until
+      FROM channel_notification_settings
+      WHERE user_id = $1
+      "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(NotificationSettingsResponse { servers, channels })
+  }
+
+  pub async fn update_server_settings(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+    req: UpdateServerNotificationSettingsRequest,
+  ) -> AppResult<ServerNotificationSettings> {
+    if !ServerService::is_member(db, server_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You must be a member of the server".to_string(),
+      ));
+    }
+
+    let settings = sqlx::query_as::<_, ServerNotificationSettings>(
+      r#"
+      UPDATE server_organization
+      SET
+        notification_level = COALESCE($1, notification_level),
+        muted_until = CASE WHEN $2 THEN $3 ELSE muted_until END,
+        suppress_everyone = COALESCE($4, suppress_everyone),
+        updated_at = NOW()
+      WHERE user_id = $5 AND server_id = $6
+      RETURNING server_id, notification_level, muted_until, suppress_everyone
+      "#,
+    )
+    .bind(req.notification_level)
+    .bind(req.muted_until.is_some())
+    .bind(req.muted_until.flatten())
+    .bind(req.suppress_everyone)
+    .bind(user_id)
+    .bind(server_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(settings)
+  }
+
+  pub async fn update_channel_settings(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+    req: UpdateChannelNotificationSettingsRequest,
+  ) -> AppResult<ChannelNotificationSettings> {
+    if !ChannelService::user_has_access_to_channel(db, channel_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You don't have access to this channel".to_string(),
+      ));
+    }
+
+    let settings = sqlx::query_as::<_, ChannelNotificationSettings>(
+      r#"
+      INSERT INTO channel_notification_settings (user_id, channel_id, notification_level, muted_until)
+      VALUES ($1, $2, $4, $6)
+      ON CONFLICT (user_id, channel_id) DO UPDATE
+      SET
+        notification_level = CASE WHEN $3 THEN $4 ELSE channel_notification_settings.notification_level END,
+        muted_until = CASE WHEN $5 THEN $6 ELSE channel_notification_settings.muted_until END,
+        updated_at = NOW()
+      RETURNING channel_id, notification_level, muted_until
+      "#,
+    )
+    .bind(user_id)
+    .bind(channel_id)
+    .bind(req.notification_level.is_some())
+    .bind(req.notification_level.flatten())
+    .bind(req.muted_until.is_some())
+    .bind(req.muted_until.flatten())
+    .fetch_one(db)
+    .await?;
+
+    Ok(settings)
+  }
+
+  // Works out who gets a mention or an unread badge for a new message
+  pub async fn get_message_targets(
+    db: &PgPool,
+    message: &MessageResponse,
+  ) -> AppResult<MessageNotificationTargets> {
+    let server_id: Option<Uuid> =
+      sqlx::query_scalar("SELECT server_id FROM channels WHERE id = $1")
+        .bind(message.channel_id)
+        .fetch_optional(db)
+        .await?
+        .flatten();
+
+    let recipients = sqlx::query_as::<_, MessageRecipientSettings>(
+      r#"
+      WITH recipients AS (
+        SELECT sm.user_id
+        FROM server_members sm
+        WHERE sm.server_id = $2
+        UNION
+        SELECT dp.user_id
+        FROM dm_participants dp
+        INNER JOIN dm_channels dc ON dp.dm_channel_id = dc.id
+        WHERE dc.channel_id = $1
+      )
+      SELECT
+        r.user_id,
+        so.notification_level AS server_level,
+        so.muted_until AS server_muted_until,
+        so.suppress_everyone,
+        cns.notification_level AS channel_level,
+        cns.muted_until AS channel_muted_until
+      FROM recipients r
+      LEFT JOIN server_organization so ON so.user_id = r.user_id AND so.server_id = $2
+      LEFT JOIN channel_notification_settings cns
+        ON cns.user_id = r.user_id AND cns.channel_id = $1
+      WHERE r.user_id IS DISTINCT FROM $3
+      "#,
+    )
+    .bind(message.channel_id)
+    .bind(server_id)
+    .bind(message.user_id)
+    .fetch_all(db)
+    .await?;
+
+    let mentions = parse_mentions(&message.content);
+    let mentions_everyone = message.content.contains("@everyone");
+    let now = Utc::now();
+
+    let mut targets = MessageNotificationTargets {
+      server_id,
+      ..Default::default()
+    };
+
+    for recipient in recipients {
+      let level = recipient
+        .channel_level
+        .or(recipient.server_level)
+        .unwrap_or(NotificationLevel::AllMessages);
+
+      if level == NotificationLevel::Nothing {
+        continue;
+      }
+
+      let muted = [recipient.server_muted_until, recipient.channel_muted_until]
+        .into_iter()
+        .flatten()
+        .any(|until| until > now);
+
+      let mentioned = mentions.contains(&recipient.user_id)
+        || (mentions_everyone && !recipient.suppress_everyone.unwrap_or(false));
+
+      // Muting hides unread badges but direct mentions still come through
+      if mentioned {
+        targets.mentioned.push(recipient.user_id);
+      } else if level == NotificationLevel::AllMessages && !muted {
+        targets.unread.push(recipient.user_id);
+      }
+    }
+
+    Ok(targets)
+  }
+}
+
+// Extracts user ids from `<@user_id>` mentions
+fn parse_mentions(content: &str) -> Vec<Uuid> {
+  content
+    .split("<@")
+    .skip(1)
+    .filter_map(|rest| rest.split_once('>'))
+    .filter_map(|(id, _)| Uuid::parse_str(id).ok())
+    .collect()
+}
@@ -0,0 +1,402 @@
+// backend/src/services/oidc.rs
+use crate::config::JwtConfig;
+use crate::models::{OidcCallbackOutcome, OidcFlow, OidcRegisterRequest, User, UserIdentity};
+use crate::oidc::{OidcClient, OidcError};
+use crate::services::AuthService;
+use crate::utils::{AppError, AppResult, ErrorCode, random_token};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const AUTH_REQUEST_TTL_MINUTES: i64 = 10;
+const PENDING_SIGNUP_TTL_MINUTES: i64 = 30;
+const MAX_USERNAME_LENGTH: usize = 32;
+
+pub struct OidcService;
+
+impl OidcService {
+  // Provider details stay in the logs
+  fn provider_error(e: OidcError) -> AppError {
+    match e {
+      OidcError::UnknownProvider(_) => AppError::NotFound(
+        ErrorCode::UnknownProvider,
+        "Unknown login provider".to_string(),
+      ),
+      e => {
+        tracing::warn!("OIDC login failed: {}", e);
+        AppError::BadRequest(
+          ErrorCode::OidcLoginFailed,
+          "Could not sign in with this provider".to_string(),
+        )
+      }
+    }
+  }
+
+  // Username suggestion from the provider profile, the user can still pick another
+  fn suggest_username(preferred: Option<&str>, email: Option<&str>) -> Option<String> {
+    let candidate = preferred.or_else(|| email.and_then(|email| email.split('@').next()))?;
+    let username: String = candidate
+      .trim()
+      .to_lowercase()
+      .chars()
+      .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+      .take(MAX_USERNAME_LENGTH)
+      .collect();
+
+    (!username.is_empty()).then_some(username)
+  }
+
+  // Starts a provider round trip and returns the provider URL
+  pub async fn start(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    oidc: &OidcClient,
+    provider: &str,
+    flow: OidcFlow,
+  ) -> AppResult<String> {
+    let request = oidc
+      .authorization_request(provider)
+      .await
+      .map_err(Self::provider_error)?;
+
+    sqlx::query("DELETE FROM oidc_auth_requests WHERE expires_at <= NOW()")
+      .execute(db)
+      .await?;
+
+    sqlx::query(
+      r#"
+      INSERT INTO oidc_auth_requests (state_hash, provider, code_verifier, nonce, user_id, reauthenticate, expires_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+    )
+    .bind(AuthService::hash_user_token(jwt, &request.state))
+    .bind(provider)
+    .bind(&request.code_verifier)
+    .bind(&request.nonce)
+    .bind(flow.user_id())
+    .bind(matches!(flow, OidcFlow::Reauthenticate(_)))
+    .bind(Utc::now() + Duration::minutes(AUTH_REQUEST_TTL_MINUTES))
+    .execute(db)
+    .await?;
+
+    Ok(request.url)
+  }
+
+  // The flow carries the signed in caller for connects and reauthentication, it must match
+  // whoever started it so neither can be completed in someone else's browser
+  pub async fn handle_callback(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    oidc: &OidcClient,
+    provider: &str,
+    code: &str,
+    state: &str,
+    flow: OidcFlow,
+  ) -> AppResult<OidcCallbackOutcome> {
+    // Each state is accepted once, for the provider it was issued for
+    let (code_verifier, nonce): (String, String) = sqlx::query_as(
+      r#"
+      DELETE FROM oidc_auth_requests
+      WHERE state_hash = $1 AND provider = $2 AND user_id IS NOT DISTINCT FROM $3
+        AND reauthenticate = $4 AND expires_at > NOW()
+      RETURNING code_verifier, nonce
+      "#,
+    )
+    .bind(AuthService::hash_user_token(jwt, state))
+    .bind(provider)
+    .bind(flow.user_id())
+    .bind(matches!(flow, OidcFlow::Reauthenticate(_)))
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| {
+      AppError::BadRequest(
+        ErrorCode::InvalidOrExpiredToken,
+        "Login request is invalid or has expired".to_string(),
+      )
+    })?;
+
+    let claims = oidc
+      .exchange_code(provider, code, &code_verifier, &nonce)
+      .await
+      .map_err(Self::provider_error)?;
+
+    let linked_user_id: Option<Uuid> = sqlx::query_scalar(
+      r#"
+      SELECT ui.user_id
+      FROM user_identities ui
+      JOIN users u ON u.id = ui.user_id
+      WHERE ui.provider = $1 AND ui.subject = $2 AND u.deleted_at IS NULL
+      "#,
+    )
+    .bind(provider)
+    .bind(&claims.sub)
+    .fetch_optional(db)
+    .await?;
+
+    if let OidcFlow::Reauthenticate(user_id) = flow {
+      if linked_user_id != Some(user_id) {
+        return Err(AppError::Forbidden(
+          ErrorCode::UnknownIdentity,
+          "This account is not connected to you".to_string(),
+        ));
+      }
+
+      return Ok(OidcCallbackOutcome::Reauthenticated(user_id));
+    }
+
+    if let OidcFlow::Connect(user_id) = flow {
+      if linked_user_id.is_some_and(|linked| linked != user_id) {
+        return Err(AppError::Conflict(
+          ErrorCode::IdentityAlreadyLinked,
+          "This account is already connected to another user".to_string(),
+        ));
+      }
+
+      let identity = sqlx::query_as::<_, UserIdentity>(
+        r#"
+        INSERT INTO user_identities (user_id, provider, subject, email)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (provider, subject) DO UPDATE SET email = EXCLUDED.email
+        RETURNING id, provider, email, created_at, last_login_at
+        "#,
+      )
+      .bind(user_id)
+      .bind(provider)
+      .bind(&claims.sub)
+      .bind(&claims.email)
+      .fetch_one(db)
+      .await
+      .map_err(|e| match e {
+        sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+          AppError::Conflict(
+            ErrorCode::IdentityAlreadyLinked,
+            "Another account from this provider is already connected".to_string(),
+          )
+        }
+        _ => AppError::from(e),
+      })?;
+
+      return Ok(OidcCallbackOutcome::Connected(identity));
+    }
+
+    if let Some(user_id) = linked_user_id {
+      sqlx::query(
+        r#"
+        UPDATE user_identities
+        SET email = $1, last_login_at = NOW()
+        WHERE provider = $2 AND subject = $3
+        "#,
+      )
+      .bind(&claims.email)
+      .bind(provider)
+      .bind(&claims.sub)
+      .execute(db)
+      .await?;
+
+      return Ok(OidcCallbackOutcome::SignedIn(user_id));
+    }
+
+    // Matching addresses are never linked automatically, the owner has to sign in and connect
+    if let Some(email) = &claims.email
+      && AuthService::get_user_by_email(db, email).await?.is_some()
+    {
+      return Err(AppError::Conflict(
+        ErrorCode::UsernameOrEmailTaken,
+        "An account with this email already exists, sign in and connect this provider from your settings".to_string(),
+      ));
+    }
+
+    sqlx::query("DELETE FROM oidc_pending_signups WHERE expires_at <= NOW()")
+      .execute(db)
+      .await?;
+
+    let registration_token = random_token();
+
+    sqlx::query(
+      r#"
+      INSERT INTO oidc_pending_signups (token_hash, provider, subject, email, email_verified, expires_at)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      "#,
+    )
+    .bind(AuthService::hash_user_token(jwt, &registration_token))
+    .bind(provider)
+    .bind(&claims.sub)
+    .bind(&claims.email)
+    .bind(claims.email_verified)
+    .bind(Utc::now() + Duration::minutes(PENDING_SIGNUP_TTL_MINUTES))
+    .execute(db)
+    .await?;
+
+    Ok(OidcCallbackOutcome::RegistrationRequired {
+      registration_token,
+      suggested_username: Self::suggest_username(
+        claims.preferred_username.as_deref(),
+        claims.email.as_deref(),
+      ),
+      email: claims.email,
+    })
+  }
+
+  // Creates the account for a pending signup, returns whether the email still needs verifying
+  pub async fn complete_registration(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    req: OidcRegisterRequest,
+  ) -> AppResult<(User, bool)> {
+    let username = req.username.trim().to_lowercase();
+    if username.is_empty() {
+      return Err(AppError::invalid_field(
+        "username",
+        ErrorCode::FieldRequired,
+        "Username is required",
+      ));
+    }
+
+    let mut tx = db.begin().await?;
+
+    // Rolled back with the transaction if the username is taken, so the user can try another
+    let (provider, subject, provider_email, email_verified): (
+      String,
+      String,
+      Option<String>,
+      bool,
+    ) = sqlx::query_as(
+      r#"
+        DELETE FROM oidc_pending_signups
+        WHERE token_hash = $1 AND expires_at > NOW()
+        RETURNING provider, subject, email, email_verified
+        "#,
+    )
+    .bind(AuthService::hash_user_token(jwt, &req.registration_token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| {
+      AppError::BadRequest(
+        ErrorCode::InvalidOrExpiredToken,
+        "Registration token is invalid or has expired".to_string(),
+      )
+    })?;
+
+    // An address the provider vouched for is used as is
+    let (email, email_verified) = match provider_email {
+      Some(email) => (email, email_verified),
+      None => {
+        let email = req
+          .email
+          .map(|email| email.trim().to_string())
+          .filter(|email| !email.is_empty())
+          .ok_or_else(|| {
+            AppError::invalid_field("email", ErrorCode::FieldRequired, "Email is required")
+          })?;
+        (email, false)
+      }
+    };
+
+    // Nobody knows this password, a reset link is how the user sets one
+    let password_hash = AuthService::hash_password(&random_token())?;
+
+    let user = sqlx::query_as::<_, User>(
+      r#"
+      INSERT INTO users (username, email, password_hash, password_set, email_verified_at)
+      VALUES ($1, $2, $3, FALSE, CASE WHEN $4 THEN NOW() END)
+      RETURNING id, username, email, password_hash, email_verified_at, token_version, totp_enabled_at, created_at, updated_at
+      "#,
+    )
+    .bind(&username)
+    .bind(&email)
+    .bind(&password_hash)
+    .bind(email_verified)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+      sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+        AppError::Conflict(ErrorCode::UsernameOrEmailTaken, "Username or email already exists".to_string())
+      }
+      _ => AppError::from(e),
+    })?;
+
+    sqlx::query(
+      r#"
+      INSERT INTO user_identities (user_id, provider, subject, email, last_login_at)
+      VALUES ($1, $2, $3, $4, NOW())
+      "#,
+    )
+    .bind(user.id)
+    .bind(&provider)
+    .bind(&subject)
+    .bind(&email)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+      sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+        AppError::Conflict(
+          ErrorCode::IdentityAlreadyLinked,
+          "This account is already connected to another user".to_string(),
+        )
+      }
+      _ => AppError::from(e),
+    })?;
+
+    tx.commit().await?;
+
+    Ok((user, !email_verified))
+  }
+
+  pub async fn get_identities(db: &PgPool, user_id: Uuid) -> AppResult<Vec<UserIdentity>> {
+    let identities = sqlx::query_as::<_, UserIdentity>(
+      r#"
+      SELECT id, provider, email, created_at, last_login_at
+      FROM user_identities
+      WHERE user_id = $1
+      ORDER BY created_at ASC
+      "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(identities)
+  }
+
+  pub async fn disconnect(db: &PgPool, user_id: Uuid, provider: &str) -> AppResult<()> {
+    let mut tx = db.begin().await?;
+
+    // Locks the user row so two disconnects cannot both pass the check
+    let password_set: bool =
+      sqlx::query_scalar("SELECT password_set FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))?;
+
+    let providers: Vec<String> =
+      sqlx::query_scalar("SELECT provider FROM user_identities WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    if !providers.iter().any(|linked| linked == provider) {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownIdentity,
+        "Provider is not connected".to_string(),
+      ));
+    }
+
+    if !password_set && providers.len() == 1 {
+      return Err(AppError::BadRequest(
+        ErrorCode::CannotRemoveLastLogin,
+        "Set a password before disconnecting your only login provider".to_string(),
+      ));
+    }
+
+    sqlx::query("DELETE FROM user_identities WHERE user_id = $1 AND provider = $2")
+      .bind(user_id)
+      .bind(provider)
+      .execute(&mut *tx)
+      .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+}
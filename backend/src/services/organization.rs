@@ -1,11 +1,11 @@
 // backend/src/services/organization.rs
 use crate::models::{
   BatchUpdateServerPositionsRequest, CreateFolderRequest, FolderResponse, OrganizedServersResponse,
-  ServerFolder, ServerOrganization, ServerResponse, UpdateFolderRequest,
+  Server, ServerFolder, ServerOrganization, ServerResponse, UpdateFolderRequest,
   UpdateServerOrganizationRequest,
 };
 use crate::services::ServerService;
-use crate::utils::{AppError, AppResult};
+use crate::utils::{AppError, AppResult, ErrorCode};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -19,17 +19,21 @@ impl OrganizationService {
     req: CreateFolderRequest,
   ) -> AppResult<ServerFolder> {
     if req.name.trim().is_empty() {
-      return Err(AppError::ValidationError(
-        "Folder name cannot be empty".to_string(),
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::FieldRequired,
+        "Folder name cannot be empty",
       ));
     }
 
-    if let Some(ref color) = req.color {
-      if !color.starts_with('#') || color.len() != 7 {
-        return Err(AppError::ValidationError(
-          "Color must be a hex code (e.g., #FF5733)".to_string(),
-        ));
-      }
+    if let Some(ref color) = req.color
+      && (!color.starts_with('#') || color.len() != 7)
+    {
+      return Err(AppError::invalid_field(
+        "color",
+        ErrorCode::InvalidFormat,
+        "Color must be a hex code (e.g., #FF5733)",
+      ));
     }
 
     let max_position: Option<i32> =
@@ -66,17 +70,20 @@ impl OrganizationService {
     let folder = Self::get_folder_by_id(db, folder_id).await?;
 
     if folder.user_id != user_id {
-      return Err(AppError::Unauthorized(
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
         "You can only update your own folders".to_string(),
       ));
     }
 
-    if let Some(ref color) = req.color {
-      if !color.starts_with('#') || color.len() != 7 {
-        return Err(AppError::ValidationError(
-          "Color must be a hex code (e.g., #FF5733)".to_string(),
-        ));
-      }
+    if let Some(ref color) = req.color
+      && (!color.starts_with('#') || color.len() != 7)
+    {
+      return Err(AppError::invalid_field(
+        "color",
+        ErrorCode::InvalidFormat,
+        "Color must be a hex code (e.g., #FF5733)",
+      ));
     }
 
     let folder = sqlx::query_as::<_, ServerFolder>(
@@ -105,7 +112,8 @@ impl OrganizationService {
     let folder = Self::get_folder_by_id(db, folder_id).await?;
 
     if folder.user_id != user_id {
-      return Err(AppError::Unauthorized(
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
         "You can only delete your own folders".to_string(),
       ));
     }
@@ -135,7 +143,7 @@ impl OrganizationService {
     .bind(folder_id)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("Folder not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownFolder, "Folder not found".to_string()))?;
 
     Ok(folder)
   }
@@ -149,7 +157,8 @@ impl OrganizationService {
   ) -> AppResult<ServerOrganization> {
     // Verify user is a member of the server
     if !ServerService::is_member(db, server_id, user_id).await? {
-      return Err(AppError::Unauthorized(
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
         "You must be a member of the server".to_string(),
       ));
     }
@@ -158,7 +167,8 @@ impl OrganizationService {
     if let Some(folder_id) = req.folder_id {
       let folder = Self::get_folder_by_id(db, folder_id).await?;
       if folder.user_id != user_id {
-        return Err(AppError::Unauthorized(
+        return Err(AppError::Forbidden(
+          ErrorCode::MissingAccess,
           "You can only add servers to your own folders".to_string(),
         ));
       }
@@ -257,9 +267,10 @@ impl OrganizationService {
 
     for folder in folders {
       // Get servers in this folder
-      let servers = sqlx::query_as::<_, (Uuid, String, Uuid, Option<Uuid>, i32)>(
+      let servers = sqlx::query_as::<_, Server>(
         r#"
-        SELECT s.id, s.name, s.owner_id, s.main_channel_id, so.position
+        SELECT s.id, s.name, s.owner_id, s.main_channel_id, s.public, s.description, s.tags,
+          s.member_count, s.created_at, s.updated_at
         FROM servers s
         INNER JOIN server_organization so ON s.id = so.server_id
         WHERE so.user_id = $1 AND so.folder_id = $2
@@ -273,14 +284,7 @@ impl OrganizationService {
 
       let server_responses: Vec<ServerResponse> = servers
         .into_iter()
-        .map(|(id, name, owner_id, main_channel_id, _)| ServerResponse {
-          id,
-          name,
-          owner_id,
-          main_channel_id,
-          is_owner: owner_id == user_id,
-          created_at: chrono::Utc::now(), // We could fetch this if needed
-        })
+        .map(|server| server.to_response(user_id))
         .collect();
 
       folder_responses.push(FolderResponse {
@@ -294,9 +298,10 @@ impl OrganizationService {
     }
 
     // Get ungrouped servers
-    let ungrouped = sqlx::query_as::<_, (Uuid, String, Uuid, Option<Uuid>)>(
+    let ungrouped = sqlx::query_as::<_, Server>(
       r#"
-      SELECT s.id, s.name, s.owner_id, s.main_channel_id
+      SELECT s.id, s.name, s.owner_id, s.main_channel_id, s.public, s.description, s.tags,
+        s.member_count, s.created_at, s.updated_at
       FROM servers s
       INNER JOIN server_organization so ON s.id = so.server_id
       WHERE so.user_id = $1 AND so.folder_id IS NULL
@@ -309,14 +314,7 @@ impl OrganizationService {
 
     let ungrouped_servers: Vec<ServerResponse> = ungrouped
       .into_iter()
-      .map(|(id, name, owner_id, main_channel_id)| ServerResponse {
-        id,
-        name,
-        owner_id,
-        is_owner: owner_id == user_id,
-        main_channel_id,
-        created_at: chrono::Utc::now(),
-      })
+      .map(|server| server.to_response(user_id))
       .collect();
 
     Ok(OrganizedServersResponse {
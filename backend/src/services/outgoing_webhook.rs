@@ -0,0 +1,452 @@
+// backend/src/services/outgoing_webhook.rs
+use crate::models::{
+  AuditLogAction, CreateOutgoingWebhookRequest, OutgoingWebhook, OutgoingWebhookDelivery,
+  OutgoingWebhookDeliveryLog, OutgoingWebhookEvent, OutgoingWebhookMember, OutgoingWebhookPayload,
+  UpdateOutgoingWebhookRequest,
+};
+use crate::outbox::Outbox;
+use crate::outgoing_webhooks::OutgoingWebhooks;
+use crate::services::{AuditLogService, ServerService};
+use crate::utils::{AppError, AppResult, ErrorCode, random_token};
+use chrono::Utc;
+use sqlx::{PgPool, types::Json};
+use std::time::Duration;
+use uuid::Uuid;
+
+const MAX_URL_LENGTH: usize = 2048;
+const DELIVERY_LOG_RETENTION: &str = "7 days";
+// Deliveries that exhausted their retries in a row before the webhook is disabled
+const DISABLE_AFTER_FAILURES: i32 = 5;
+
+pub struct OutgoingWebhookService;
+
+impl OutgoingWebhookService {
+  pub const OUTBOX: Outbox = Outbox {
+    name: "webhook",
+    table: "outgoing_webhook_outbox",
+    deliveries: r#"
+      SELECT c.id, c.webhook_id, c.delivery_id, c.event, w.url, w.secret, c.payload, c.attempts
+      FROM claimed c
+      INNER JOIN outgoing_webhooks w ON w.id = c.webhook_id
+      WHERE w.enabled
+    "#,
+    retention: "1 day",
+    max_attempts: 6,
+  };
+
+  fn validate_url(webhooks: &OutgoingWebhooks, url: &str) -> AppResult<()> {
+    if url.len() > MAX_URL_LENGTH {
+      return Err(AppError::invalid_field(
+        "url",
+        ErrorCode::FieldTooLong,
+        "URL must be at most 2048 characters",
+      ));
+    }
+
+    webhooks
+      .validate_url(url)
+      .map_err(|e| AppError::invalid_field("url", ErrorCode::InvalidFormat, e.to_string()))?;
+
+    Ok(())
+  }
+
+  fn validate_events(events: &mut Vec<OutgoingWebhookEvent>) -> AppResult<()> {
+    if events.is_empty() {
+      return Err(AppError::invalid_field(
+        "events",
+        ErrorCode::FieldRequired,
+        "At least one event must be selected",
+      ));
+    }
+
+    let mut unique = Vec::with_capacity(events.len());
+    for event in events.drain(..) {
+      if !unique.contains(&event) {
+        unique.push(event);
+      }
+    }
+    *events = unique;
+
+    Ok(())
+  }
+
+  async fn require_owner(db: &PgPool, server_id: Uuid, user_id: Uuid) -> AppResult<()> {
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can manage outgoing webhooks".to_string(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Returns the webhook and its signing secret
+  pub async fn create_webhook(
+    db: &PgPool,
+    webhooks: &OutgoingWebhooks,
+    server_id: Uuid,
+    user_id: Uuid,
+    mut req: CreateOutgoingWebhookRequest,
+    reason: Option<&str>,
+  ) -> AppResult<(OutgoingWebhook, String)> {
+    Self::require_owner(db, server_id, user_id).await?;
+
+    let url = req.url.trim();
+    Self::validate_url(webhooks, url)?;
+    Self::validate_events(&mut req.events)?;
+
+    let secret = random_token();
+
+    let mut tx = db.begin().await?;
+
+    let webhook = sqlx::query_as::<_, OutgoingWebhook>(
+      r#"
+      INSERT INTO outgoing_webhooks (server_id, creator_id, url, secret, events)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING id, server_id, creator_id, url, events, enabled, consecutive_failures, disabled_at, created_at, updated_at
+      "#,
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .bind(url)
+    .bind(&secret)
+    .bind(&req.events)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::OutgoingWebhookCreate,
+      Some(webhook.id),
+      AuditLogService::diff(None, Some(&webhook)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((webhook, secret))
+  }
+
+  pub async fn get_server_webhooks(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Vec<OutgoingWebhook>> {
+    Self::require_owner(db, server_id, user_id).await?;
+
+    let webhooks = sqlx::query_as::<_, OutgoingWebhook>(
+      r#"
+      SELECT id, server_id, creator_id, url, events, enabled, consecutive_failures, disabled_at, created_at, updated_at
+      FROM outgoing_webhooks
+      WHERE server_id = $1
+      ORDER BY created_at ASC
+      "#,
+    )
+    .bind(server_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(webhooks)
+  }
+
+  async fn get_managed_webhook(
+    db: &PgPool,
+    webhook_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<OutgoingWebhook> {
+    let webhook = sqlx::query_as::<_, OutgoingWebhook>(
+      r#"
+      SELECT id, server_id, creator_id, url, events, enabled, consecutive_failures, disabled_at, created_at, updated_at
+      FROM outgoing_webhooks
+      WHERE id = $1
+      "#,
+    )
+    .bind(webhook_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownWebhook, "Unknown webhook".to_string()))?;
+
+    Self::require_owner(db, webhook.server_id, user_id).await?;
+
+    Ok(webhook)
+  }
+
+  pub async fn update_webhook(
+    db: &PgPool,
+    webhooks: &OutgoingWebhooks,
+    webhook_id: Uuid,
+    user_id: Uuid,
+    req: UpdateOutgoingWebhookRequest,
+    reason: Option<&str>,
+  ) -> AppResult<OutgoingWebhook> {
+    let existing = Self::get_managed_webhook(db, webhook_id, user_id).await?;
+
+    let url = req.url.map(|url| url.trim().to_string());
+    if let Some(url) = &url {
+      Self::validate_url(webhooks, url)?;
+    }
+    let mut events = req.events;
+    if let Some(events) = &mut events {
+      Self::validate_events(events)?;
+    }
+
+    let mut tx = db.begin().await?;
+
+    let webhook = sqlx::query_as::<_, OutgoingWebhook>(
+      r#"
+      UPDATE outgoing_webhooks
+      SET
+        url = COALESCE($1, url),
+        events = COALESCE($2, events),
+        enabled = COALESCE($3, enabled),
+        consecutive_failures = CASE WHEN $3 THEN 0 ELSE consecutive_failures END,
+        disabled_at = CASE WHEN $3 IS NULL THEN disabled_at ELSE NULL END,
+        updated_at = NOW()
+      WHERE id = $4
+      RETURNING id, server_id, creator_id, url, events, enabled, consecutive_failures, disabled_at, created_at, updated_at
+      "#,
+    )
+    .bind(&url)
+    .bind(&events)
+    .bind(req.enabled)
+    .bind(webhook_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Nothing queued while disabled is sent later
+    if !webhook.enabled {
+      sqlx::query("DELETE FROM outgoing_webhook_outbox WHERE webhook_id = $1")
+        .bind(webhook_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    AuditLogService::record(
+      &mut *tx,
+      webhook.server_id,
+      user_id,
+      AuditLogAction::OutgoingWebhookUpdate,
+      Some(webhook_id),
+      AuditLogService::diff(Some(&existing), Some(&webhook)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(webhook)
+  }
+
+  pub async fn delete_webhook(
+    db: &PgPool,
+    webhook_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<()> {
+    let webhook = Self::get_managed_webhook(db, webhook_id, user_id).await?;
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM outgoing_webhooks WHERE id = $1")
+      .bind(webhook_id)
+      .execute(&mut *tx)
+      .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      webhook.server_id,
+      user_id,
+      AuditLogAction::OutgoingWebhookDelete,
+      Some(webhook_id),
+      AuditLogService::diff(Some(&webhook), None),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+
+  // Most recent attempts first
+  pub async fn get_deliveries(
+    db: &PgPool,
+    webhook_id: Uuid,
+    user_id: Uuid,
+    limit: i64,
+  ) -> AppResult<Vec<OutgoingWebhookDeliveryLog>> {
+    Self::get_managed_webhook(db, webhook_id, user_id).await?;
+
+    let deliveries = sqlx::query_as::<_, OutgoingWebhookDeliveryLog>(
+      r#"
+      SELECT id, delivery_id, event, attempt, success, status_code, error, duration_ms, created_at
+      FROM outgoing_webhook_deliveries
+      WHERE webhook_id = $1
+      ORDER BY created_at DESC, id DESC
+      LIMIT $2
+      "#,
+    )
+    .bind(webhook_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await?;
+
+    Ok(deliveries)
+  }
+
+  pub async fn get_member(db: &PgPool, user_id: Uuid) -> AppResult<OutgoingWebhookMember> {
+    sqlx::query_as::<_, OutgoingWebhookMember>(
+      "SELECT id AS user_id, username, bot FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))
+  }
+
+  // Queues the event for every enabled webhook of the server subscribed to it
+  pub async fn enqueue(
+    db: &PgPool,
+    server_id: Uuid,
+    event: OutgoingWebhookEvent,
+    data: serde_json::Value,
+  ) -> AppResult<u64> {
+    let payload = OutgoingWebhookPayload {
+      id: Uuid::new_v4(),
+      event,
+      server_id,
+      created_at: Utc::now(),
+      data,
+    };
+
+    let result = sqlx::query(
+      r#"
+      INSERT INTO outgoing_webhook_outbox (webhook_id, event, payload)
+      SELECT id, $2, $3
+      FROM outgoing_webhooks
+      WHERE server_id = $1 AND enabled AND $2 = ANY(events)
+      "#,
+    )
+    .bind(server_id)
+    .bind(event)
+    .bind(Json(&payload))
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  // Claims due deliveries so other workers skip them until claim_timeout has passed, a claimed
+  // delivery becomes visible again if its worker dies before finishing it
+  pub async fn record_attempt(
+    db: &PgPool,
+    delivery: &OutgoingWebhookDelivery,
+    success: bool,
+    status_code: Option<u16>,
+    error: Option<&str>,
+    duration_ms: i32,
+  ) -> AppResult<()> {
+    sqlx::query(
+      r#"
+      INSERT INTO outgoing_webhook_deliveries
+        (webhook_id, delivery_id, event, attempt, success, status_code, error, duration_ms)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+      "#,
+    )
+    .bind(delivery.webhook_id)
+    .bind(delivery.delivery_id)
+    .bind(delivery.event)
+    .bind(delivery.attempts + 1)
+    .bind(success)
+    .bind(status_code.map(i32::from))
+    .bind(error)
+    .bind(duration_ms)
+    .execute(db)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn complete_delivery(db: &PgPool, delivery: &OutgoingWebhookDelivery) -> AppResult<()> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM outgoing_webhook_outbox WHERE id = $1")
+      .bind(delivery.id)
+      .execute(&mut *tx)
+      .await?;
+
+    sqlx::query(
+      "UPDATE outgoing_webhooks SET consecutive_failures = 0 WHERE id = $1 AND consecutive_failures > 0",
+    )
+    .bind(delivery.webhook_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+
+  // Drops a delivery that will not be retried, returns whether the webhook got disabled
+  pub async fn fail_delivery(db: &PgPool, delivery: &OutgoingWebhookDelivery) -> AppResult<bool> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM outgoing_webhook_outbox WHERE id = $1")
+      .bind(delivery.id)
+      .execute(&mut *tx)
+      .await?;
+
+    let disabled: Option<bool> = sqlx::query_scalar(
+      r#"
+      UPDATE outgoing_webhooks
+      SET
+        consecutive_failures = consecutive_failures + 1,
+        enabled = consecutive_failures + 1 < $2,
+        disabled_at = CASE WHEN consecutive_failures + 1 >= $2 THEN NOW() ELSE disabled_at END,
+        updated_at = NOW()
+      WHERE id = $1 AND enabled
+      RETURNING NOT enabled
+      "#,
+    )
+    .bind(delivery.webhook_id)
+    .bind(DISABLE_AFTER_FAILURES)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let disabled = disabled.unwrap_or(false);
+    if disabled {
+      sqlx::query("DELETE FROM outgoing_webhook_outbox WHERE webhook_id = $1")
+        .bind(delivery.webhook_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(disabled)
+  }
+
+  pub fn spawn_cleanup(db: PgPool) {
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+      loop {
+        interval.tick().await;
+
+        if let Err(e) = sqlx::query(&format!(
+          "DELETE FROM outgoing_webhook_deliveries WHERE created_at < NOW() - INTERVAL '{}'",
+          DELIVERY_LOG_RETENTION
+        ))
+        .execute(&db)
+        .await
+        {
+          tracing::error!("Failed to clean up webhook delivery logs: {}", e);
+        }
+      }
+    });
+  }
+}
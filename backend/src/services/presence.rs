@@ -0,0 +1,126 @@
+// backend/src/services/presence.rs
+use crate::models::VoiceState;
+use crate::utils::AppResult;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// A node that missed this many seconds of heartbeats is considered gone
+const NODE_TIMEOUT_SECS: f64 = 90.0;
+
+// Tracks sockets across every node, ConnectionMap only knows the ones held locally
+pub struct PresenceService;
+
+impl PresenceService {
+  // Returns true when the node row had to be created, which besides startup means other nodes
+  // took this one for stopped and removed its sessions
+  pub async fn heartbeat(db: &PgPool, node_id: Uuid) -> AppResult<bool> {
+    // xmax is only zero for a freshly inserted row
+    let created: bool = sqlx::query_scalar(
+      r#"
+      INSERT INTO gateway_nodes (id)
+      VALUES ($1)
+      ON CONFLICT (id) DO UPDATE SET last_seen_at = NOW()
+      RETURNING xmax = 0
+      "#,
+    )
+    .bind(node_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(created)
+  }
+
+  // Sessions of removed nodes go with them, and their voice states with the sessions.
+  // Returns those voice states as left so members can be told
+  pub async fn remove_stale_nodes(db: &PgPool) -> AppResult<Vec<VoiceState>> {
+    let voice_states = sqlx::query_as::<_, VoiceState>(
+      r#"
+      WITH stale AS (
+        DELETE FROM gateway_nodes
+        WHERE last_seen_at < NOW() - make_interval(secs => $1)
+        RETURNING id
+      )
+      SELECT vs.user_id, vs.server_id, NULL::uuid AS channel_id, vs.self_mute, vs.self_deaf,
+        sm.voice_muted AS server_mute
+      FROM voice_states vs
+      INNER JOIN gateway_sessions s ON s.id = vs.session_id
+      INNER JOIN stale ON stale.id = s.node_id
+      INNER JOIN server_members sm ON sm.server_id = vs.server_id AND sm.user_id = vs.user_id
+      "#,
+    )
+    .bind(NODE_TIMEOUT_SECS)
+    .fetch_all(db)
+    .await?;
+
+    Ok(voice_states)
+  }
+
+  pub async fn add_session(
+    db: &PgPool,
+    node_id: Uuid,
+    session_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<()> {
+    sqlx::query("INSERT INTO gateway_sessions (id, user_id, node_id) VALUES ($1, $2, $3)")
+      .bind(session_id)
+      .bind(user_id)
+      .bind(node_id)
+      .execute(db)
+      .await?;
+
+    Ok(())
+  }
+
+  // Registers the sockets still open on a node again, sessions that exist are left alone
+  pub async fn restore_sessions(
+    db: &PgPool,
+    node_id: Uuid,
+    session_ids: &[Uuid],
+    user_ids: &[Uuid],
+  ) -> AppResult<()> {
+    sqlx::query(
+      r#"
+      INSERT INTO gateway_sessions (id, user_id, node_id)
+      SELECT s.id, s.user_id, $3
+      FROM UNNEST($1::uuid[], $2::uuid[]) AS s(id, user_id)
+      INNER JOIN users u ON u.id = s.user_id
+      ON CONFLICT (id) DO NOTHING
+      "#,
+    )
+    .bind(session_ids)
+    .bind(user_ids)
+    .bind(node_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_session(db: &PgPool, session_id: Uuid) -> AppResult<()> {
+    sqlx::query("DELETE FROM gateway_sessions WHERE id = $1")
+      .bind(session_id)
+      .execute(db)
+      .await?;
+
+    Ok(())
+  }
+
+  // Those of the given users with a socket open on any live node
+  pub async fn online_users(db: &PgPool, user_ids: &[Uuid]) -> AppResult<Vec<Uuid>> {
+    let online = sqlx::query_scalar(
+      r#"
+      SELECT DISTINCT s.user_id
+      FROM gateway_sessions s
+      INNER JOIN gateway_nodes n ON n.id = s.node_id
+      WHERE s.user_id = ANY($1)
+        AND n.last_seen_at >= NOW() - make_interval(secs => $2)
+      "#,
+    )
+    .bind(user_ids)
+    .bind(NODE_TIMEOUT_SECS)
+    .fetch_all(db)
+    .await?;
+
+    Ok(online)
+  }
+}
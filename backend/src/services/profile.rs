@@ -1,5 +1,5 @@
 use crate::models::{FullProfile, Profile, UpdateProfileRequest};
-use crate::utils::{AppError, AppResult};
+use crate::utils::{AppError, AppResult, ErrorCode};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -12,6 +12,7 @@ impl ProfileService {
       SELECT
         u.id,
         u.username,
+        u.bot,
         p.display_name,
         p.bio,
         p.avatar_url,
@@ -29,7 +30,7 @@ impl ProfileService {
     .bind(user_id)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))?;
 
     Ok(profile)
   }
@@ -40,6 +41,7 @@ impl ProfileService {
       SELECT
         u.id,
         u.username,
+        u.bot,
         p.display_name,
         p.bio,
         p.avatar_url,
@@ -57,7 +59,7 @@ impl ProfileService {
     .bind(username)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "User not found".to_string()))?;
 
     Ok(profile)
   }
@@ -67,20 +69,24 @@ impl ProfileService {
     user_id: Uuid,
     req: UpdateProfileRequest,
   ) -> AppResult<Profile> {
-    if let Some(ref display_name) = req.display_name {
-      if display_name.len() > 100 {
-        return Err(AppError::ValidationError(
-          "Display name cannot exceed 100 characters".to_string(),
-        ));
-      }
+    if let Some(ref display_name) = req.display_name
+      && display_name.len() > 100
+    {
+      return Err(AppError::invalid_field(
+        "display_name",
+        ErrorCode::FieldTooLong,
+        "Display name cannot exceed 100 characters",
+      ));
     }
 
-    if let Some(ref bio) = req.bio {
-      if bio.len() > 500 {
-        return Err(AppError::ValidationError(
-          "Bio cannot exceed 500 characters".to_string(),
-        ));
-      }
+    if let Some(ref bio) = req.bio
+      && bio.len() > 500
+    {
+      return Err(AppError::invalid_field(
+        "bio",
+        ErrorCode::FieldTooLong,
+        "Bio cannot exceed 500 characters",
+      ));
     }
 
     let profile = sqlx::query_as::<_, Profile>(
@@ -95,12 +101,12 @@ impl ProfileService {
         custom_status = COALESCE($7, custom_status),
         status_emoji = COALESCE($8, status_emoji),
         show_online_status = COALESCE($9, show_online_status),
-        allow_dms = COALESCE($10, allow_dms),
+        dm_privacy = COALESCE($10, dm_privacy),
         updated_at = NOW()
       WHERE user_id = $1
       RETURNING user_id, display_name, bio, avatar_url, banner_url,
         status, custom_status, status_emoji, show_online_status,
-        allow_dms, created_at, updated_at
+        dm_privacy, created_at, updated_at
       "#,
     )
     .bind(user_id)
@@ -111,8 +117,8 @@ impl ProfileService {
     .bind(&req.status)
     .bind(&req.custom_status)
     .bind(&req.status_emoji)
-    .bind(&req.show_online_status)
-    .bind(&req.allow_dms)
+    .bind(req.show_online_status)
+    .bind(req.dm_privacy)
     .fetch_one(db)
     .await?;
 
@@ -125,7 +131,7 @@ impl ProfileService {
       SELECT
         user_id, display_name, bio, avatar_url, banner_url,
         status, custom_status, status_emoji, show_online_status,
-        allow_dms, created_at, updated_at
+        dm_privacy, created_at, updated_at
       FROM profiles
       WHERE user_id = $1
       "#,
@@ -133,7 +139,7 @@ impl ProfileService {
     .bind(user_id)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("Profile not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownUser, "Profile not found".to_string()))?;
 
     Ok(profile)
   }
@@ -0,0 +1,152 @@
+// backend/src/services/push.rs
+use crate::config::PushConfig;
+use crate::models::{CreatePushSubscriptionRequest, PushPayload, PushSubscription};
+use crate::outbox::Outbox;
+use crate::push::encryption::SubscriptionKeys;
+use crate::utils::net::is_private_host;
+use crate::utils::{AppError, AppResult, ErrorCode};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct PushService;
+
+impl PushService {
+  pub const OUTBOX: Outbox = Outbox {
+    name: "push",
+    table: "push_outbox",
+    deliveries: r#"
+      SELECT c.id, c.subscription_id, ps.endpoint, ps.p256dh, ps.auth, c.payload, c.attempts
+      FROM claimed c
+      INNER JOIN push_subscriptions ps ON ps.id = c.subscription_id
+    "#,
+    // Deliveries a push service has not accepted within a day are no longer worth showing
+    retention: "1 day",
+    max_attempts: 5,
+  };
+
+  pub async fn create_subscription(
+    db: &PgPool,
+    config: &PushConfig,
+    user_id: Uuid,
+    req: CreatePushSubscriptionRequest,
+  ) -> AppResult<PushSubscription> {
+    let url = reqwest::Url::parse(&req.endpoint).map_err(|_| {
+      AppError::invalid_field(
+        "endpoint",
+        ErrorCode::InvalidFormat,
+        "Endpoint must be a valid URL",
+      )
+    })?;
+
+    if url.scheme() != "https" && !(url.scheme() == "http" && config.allow_insecure_endpoints) {
+      return Err(AppError::invalid_field(
+        "endpoint",
+        ErrorCode::InvalidFormat,
+        "Endpoint must use https",
+      ));
+    }
+
+    if !config.allow_insecure_endpoints && url.host_str().is_none_or(is_private_host) {
+      return Err(AppError::invalid_field(
+        "endpoint",
+        ErrorCode::InvalidFormat,
+        "Endpoint must not point at a local or private address",
+      ));
+    }
+
+    SubscriptionKeys::decode(&req.keys.p256dh, &req.keys.auth)
+      .map_err(|e| AppError::invalid_field("keys", ErrorCode::InvalidFormat, e.to_string()))?;
+
+    // Browsers reuse endpoints, so re-registering moves the subscription to the current user
+    let subscription = sqlx::query_as::<_, PushSubscription>(
+      r#"
+      INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (endpoint) DO UPDATE
+      SET user_id = $1, p256dh = $3, auth = $4, updated_at = NOW()
+      RETURNING id, endpoint, created_at
+      "#,
+    )
+    .bind(user_id)
+    .bind(&req.endpoint)
+    .bind(&req.keys.p256dh)
+    .bind(&req.keys.auth)
+    .fetch_one(db)
+    .await?;
+
+    Ok(subscription)
+  }
+
+  pub async fn get_user_subscriptions(
+    db: &PgPool,
+    user_id: Uuid,
+  ) -> AppResult<Vec<PushSubscription>> {
+    let subscriptions = sqlx::query_as::<_, PushSubscription>(
+      r#"
+      SELECT id, endpoint, created_at
+      FROM push_subscriptions
+      WHERE user_id = $1
+      ORDER BY created_at
+      "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(subscriptions)
+  }
+
+  pub async fn delete_subscription(
+    db: &PgPool,
+    subscription_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<()> {
+    let result = sqlx::query("DELETE FROM push_subscriptions WHERE id = $1 AND user_id = $2")
+      .bind(subscription_id)
+      .bind(user_id)
+      .execute(db)
+      .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownPushSubscription,
+        "Push subscription not found".to_string(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  pub async fn remove_subscription(db: &PgPool, subscription_id: Uuid) -> AppResult<()> {
+    sqlx::query("DELETE FROM push_subscriptions WHERE id = $1")
+      .bind(subscription_id)
+      .execute(db)
+      .await?;
+
+    Ok(())
+  }
+
+  // Queues a push for every subscription of the given users, skipping anyone on Do Not Disturb
+  pub async fn enqueue(db: &PgPool, user_ids: &[Uuid], payload: &PushPayload) -> AppResult<u64> {
+    if user_ids.is_empty() {
+      return Ok(0);
+    }
+
+    let result = sqlx::query(
+      r#"
+      INSERT INTO push_outbox (subscription_id, payload)
+      SELECT ps.id, $2
+      FROM push_subscriptions ps
+      LEFT JOIN profiles p ON p.user_id = ps.user_id
+      WHERE ps.user_id = ANY($1)
+        AND p.status IS DISTINCT FROM 'dnd'
+      "#,
+    )
+    .bind(user_ids)
+    .bind(sqlx::types::Json(payload))
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+}
@@ -1,21 +1,89 @@
 use crate::models::{
-  CreateServerRequest, FullProfile, PaginatedResponse, Profile, Server, UpdateServerRequest,
+  AuditLogAction, CreateServerRequest, DiscoverableServer, DiscoveryQuery, DiscoverySort,
+  FullProfile, PaginatedResponse, Server, UpdateServerRequest,
 };
-use crate::utils::{AppError, AppResult};
+use crate::services::AuditLogService;
+use crate::utils::{AppError, AppResult, ErrorCode};
 use sqlx::{Executor, PgPool, Postgres};
 use uuid::Uuid;
 
+const MAX_DESCRIPTION_LENGTH: usize = 300;
+const MAX_TAGS: usize = 5;
+const MIN_TAG_LENGTH: usize = 2;
+const MAX_TAG_LENGTH: usize = 24;
+
+// Servers the user is banned from are left out, they could not join them anyway
+const DISCOVERY_FILTER: &str = r#"
+  WHERE s.public
+    AND NOT EXISTS(SELECT 1 FROM server_bans b WHERE b.server_id = s.id AND b.user_id = $1)
+    AND ($2::text IS NULL OR s.search_vector @@ websearch_to_tsquery('simple', $2))
+    AND ($3::text IS NULL OR s.tags @> ARRAY[$3::text])
+"#;
+
 pub struct ServerService;
 
 impl ServerService {
+  // Blank descriptions become an empty string, which clears the stored one
+  fn normalize_description(description: String) -> AppResult<String> {
+    let description = description.trim().to_string();
+    if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+      return Err(AppError::invalid_field(
+        "description",
+        ErrorCode::FieldTooLong,
+        format!(
+          "Description must be at most {} characters",
+          MAX_DESCRIPTION_LENGTH
+        ),
+      ));
+    }
+
+    Ok(description)
+  }
+
+  // Tags are matched exactly by discovery, so they are lowercased and deduplicated
+  fn normalize_tags(tags: Vec<String>) -> AppResult<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+      let tag = tag.trim().to_lowercase();
+      let length = tag.chars().count();
+      if !(MIN_TAG_LENGTH..=MAX_TAG_LENGTH).contains(&length)
+        || !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+      {
+        return Err(AppError::invalid_field(
+          "tags",
+          ErrorCode::InvalidFormat,
+          format!(
+            "Tags must be {} to {} letters, digits or hyphens",
+            MIN_TAG_LENGTH, MAX_TAG_LENGTH
+          ),
+        ));
+      }
+      if !normalized.contains(&tag) {
+        normalized.push(tag);
+      }
+    }
+
+    if normalized.len() > MAX_TAGS {
+      return Err(AppError::invalid_field(
+        "tags",
+        ErrorCode::FieldTooLong,
+        format!("A server can have at most {} tags", MAX_TAGS),
+      ));
+    }
+
+    Ok(normalized)
+  }
+
   pub async fn create_server(
     db: &PgPool,
     user_id: Uuid,
     req: CreateServerRequest,
   ) -> AppResult<Server> {
     if req.name.trim().is_empty() {
-      return Err(AppError::ValidationError(
-        "Server name cannot be empty".to_string(),
+      return Err(AppError::invalid_field(
+        "name",
+        ErrorCode::FieldRequired,
+        "Server name cannot be empty",
       ));
     }
 
@@ -25,7 +93,8 @@ impl ServerService {
       r#"
       INSERT INTO servers (name, owner_id)
       VALUES ($1, $2)
-      RETURNING id, name, owner_id, main_channel_id, created_at, updated_at
+      RETURNING id, name, owner_id, main_channel_id, public, description, tags,
+        member_count, created_at, updated_at
       "#,
     )
     .bind(&req.name)
@@ -60,7 +129,8 @@ impl ServerService {
       UPDATE servers
       SET main_channel_id = $1
       WHERE id = $2
-      RETURNING id, name, owner_id, main_channel_id, created_at, updated_at
+      RETURNING id, name, owner_id, main_channel_id, public, description, tags,
+        member_count, created_at, updated_at
       "#,
     )
     .bind(channel_id)
@@ -76,7 +146,8 @@ impl ServerService {
   pub async fn get_user_servers(db: &PgPool, user_id: Uuid) -> AppResult<Vec<Server>> {
     let servers = sqlx::query_as::<_, Server>(
       r#"
-      SELECT s.id, s.name, s.owner_id, s.main_channel_id, s.created_at, s.updated_at
+      SELECT s.id, s.name, s.owner_id, s.main_channel_id, s.public, s.description, s.tags,
+        s.member_count, s.created_at, s.updated_at
       FROM servers s
       INNER JOIN server_members sm ON s.id = sm.server_id
       WHERE sm.user_id = $1
@@ -93,7 +164,8 @@ impl ServerService {
   pub async fn get_server_by_id(db: &PgPool, server_id: Uuid) -> AppResult<Server> {
     let server = sqlx::query_as::<_, Server>(
       r#"
-      SELECT id, name, owner_id, main_channel_id, created_at, updated_at
+      SELECT id, name, owner_id, main_channel_id, public, description, tags,
+        member_count, created_at, updated_at
       FROM servers
       WHERE id = $1
       "#,
@@ -101,7 +173,7 @@ impl ServerService {
     .bind(server_id)
     .fetch_optional(db)
     .await?
-    .ok_or_else(|| AppError::NotFound("Server not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownServer, "Server not found".to_string()))?;
 
     Ok(server)
   }
@@ -126,15 +198,46 @@ impl ServerService {
     Ok(result)
   }
 
-  pub async fn delete_server(db: &PgPool, server_id: Uuid, user_id: Uuid) -> AppResult<()> {
+  pub async fn share_server(db: &PgPool, user_id: Uuid, other_id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query_scalar::<_, bool>(
+      r#"
+      SELECT EXISTS(
+        SELECT 1 FROM server_members sm1
+        INNER JOIN server_members sm2 ON sm1.server_id = sm2.server_id
+        WHERE sm1.user_id = $1 AND sm2.user_id = $2
+      )
+      "#,
+    )
+    .bind(user_id)
+    .bind(other_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(result)
+  }
+
+  // Lets handlers reject non-owners before asking for a two-factor code
+  pub async fn require_owner(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+    action: &str,
+  ) -> AppResult<Server> {
     let server = Self::get_server_by_id(db, server_id).await?;
 
     if server.owner_id != user_id {
-      return Err(AppError::Unauthorized(
-        "Only the server owner can delete the server".to_string(),
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        format!("Only the server owner can {}", action),
       ));
     }
 
+    Ok(server)
+  }
+
+  pub async fn delete_server(db: &PgPool, server_id: Uuid, user_id: Uuid) -> AppResult<()> {
+    Self::require_owner(db, server_id, user_id, "delete the server").await?;
+
     sqlx::query("DELETE FROM servers WHERE id = $1")
       .bind(server_id)
       .execute(db)
@@ -143,16 +246,66 @@ impl ServerService {
     Ok(())
   }
 
+  pub async fn transfer_ownership(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+    new_owner_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<Server> {
+    let server = Self::require_owner(db, server_id, user_id, "transfer ownership").await?;
+
+    if !Self::is_member(db, server_id, new_owner_id).await? {
+      return Err(AppError::BadRequest(
+        ErrorCode::UnknownMember,
+        "The new owner must be a member of the server".to_string(),
+      ));
+    }
+
+    let mut tx = db.begin().await?;
+
+    let updated = sqlx::query_as::<_, Server>(
+      r#"
+      UPDATE servers
+      SET owner_id = $1, updated_at = NOW()
+      WHERE id = $2
+      RETURNING id, name, owner_id, main_channel_id, public, description, tags,
+        member_count, created_at, updated_at
+      "#,
+    )
+    .bind(new_owner_id)
+    .bind(server_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::ServerOwnershipTransfer,
+      Some(new_owner_id),
+      AuditLogService::diff(Some(&server), Some(&updated)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(updated)
+  }
+
   pub async fn update_server(
     db: &PgPool,
     server_id: Uuid,
     user_id: Uuid,
     req: UpdateServerRequest,
+    reason: Option<&str>,
   ) -> AppResult<Server> {
     let server = Self::get_server_by_id(db, server_id).await?;
 
     if server.owner_id != user_id {
-      return Err(AppError::Unauthorized(
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
         "Only the server owner can update the server".to_string(),
       ));
     }
@@ -166,29 +319,184 @@ impl ServerService {
 
       if channel_server_id != Some(server_id) {
         return Err(AppError::BadRequest(
+          ErrorCode::InvalidChannel,
           "Channel does not belong to this server".to_string(),
         ));
       }
     }
 
-    let server = sqlx::query_as::<_, Server>(
+    let description = req
+      .description
+      .map(Self::normalize_description)
+      .transpose()?;
+    let tags = req.tags.map(Self::normalize_tags).transpose()?;
+
+    let mut tx = db.begin().await?;
+
+    let updated = sqlx::query_as::<_, Server>(
       r#"
       UPDATE servers
       SET
         name = COALESCE($1, name),
         main_channel_id = COALESCE($2, main_channel_id),
+        public = COALESCE($3, public),
+        description = CASE WHEN $4::text IS NULL THEN description ELSE NULLIF($4, '') END,
+        tags = COALESCE($5, tags),
         updated_at = NOW()
-      WHERE id = $3
-      RETURNING id, name, owner_id, main_channel_id, created_at, updated_at
+      WHERE id = $6
+      RETURNING id, name, owner_id, main_channel_id, public, description, tags,
+        member_count, created_at, updated_at
       "#,
     )
     .bind(req.name)
     .bind(req.main_channel_id)
+    .bind(req.public)
+    .bind(description)
+    .bind(tags)
     .bind(server_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::ServerUpdate,
+      Some(server_id),
+      AuditLogService::diff(Some(&server), Some(&updated)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(updated)
+  }
+
+  pub async fn discover(
+    db: &PgPool,
+    user_id: Uuid,
+    query: DiscoveryQuery,
+  ) -> AppResult<PaginatedResponse<DiscoverableServer>> {
+    let limit = query.limit.clamp(1, 100);
+    let offset = query.offset.max(0);
+    let search = query
+      .q
+      .map(|q| q.trim().to_string())
+      .filter(|q| !q.is_empty());
+    let tag = query
+      .tag
+      .map(|tag| tag.trim().to_lowercase())
+      .filter(|tag| !tag.is_empty());
+
+    let order = match (query.sort, &search) {
+      (DiscoverySort::Newest, _) => "s.created_at DESC, s.id",
+      (DiscoverySort::Relevance, Some(_)) => {
+        "ts_rank(s.search_vector, websearch_to_tsquery('simple', $2)) DESC, s.member_count DESC, s.id"
+      }
+      _ => "s.member_count DESC, s.created_at DESC, s.id",
+    };
+
+    let total: i64 = sqlx::query_scalar(&format!(
+      "SELECT COUNT(*) FROM servers s {}",
+      DISCOVERY_FILTER
+    ))
+    .bind(user_id)
+    .bind(&search)
+    .bind(&tag)
     .fetch_one(db)
     .await?;
 
-    Ok(server)
+    let servers = sqlx::query_as::<_, DiscoverableServer>(&format!(
+      r#"
+      SELECT
+        s.id, s.name, s.description, s.tags, s.member_count, s.created_at,
+        EXISTS(
+          SELECT 1 FROM server_members sm WHERE sm.server_id = s.id AND sm.user_id = $1
+        ) AS is_member
+      FROM servers s
+      {}
+      ORDER BY {}
+      LIMIT $4 OFFSET $5
+      "#,
+      DISCOVERY_FILTER, order
+    ))
+    .bind(user_id)
+    .bind(&search)
+    .bind(&tag)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    Ok(PaginatedResponse {
+      data: servers,
+      total,
+      limit,
+      offset,
+      has_more: offset + limit < total,
+    })
+  }
+
+  // Public servers need no invite, bans still apply
+  pub async fn join_public_server(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Server> {
+    let mut tx = db.begin().await?;
+
+    // Holding the server keeps a ban issued meanwhile from missing the new membership
+    let public: Option<bool> =
+      sqlx::query_scalar("SELECT public FROM servers WHERE id = $1 FOR KEY SHARE")
+        .bind(server_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    if Self::is_member(&mut *tx, server_id, user_id).await? {
+      return Err(AppError::Conflict(
+        ErrorCode::AlreadyMember,
+        "You are already a member of this server".to_string(),
+      ));
+    }
+
+    // Private servers are not revealed to someone who cannot join them
+    if public != Some(true) {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownServer,
+        "Server not found".to_string(),
+      ));
+    }
+
+    let banned: bool = sqlx::query_scalar(
+      "SELECT EXISTS(SELECT 1 FROM server_bans WHERE server_id = $1 AND user_id = $2)",
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if banned {
+      return Err(AppError::Forbidden(
+        ErrorCode::BannedFromServer,
+        "You are banned from this server".to_string(),
+      ));
+    }
+
+    sqlx::query(
+      r#"
+      INSERT INTO server_members (server_id, user_id)
+      VALUES ($1, $2)
+      "#,
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Self::get_server_by_id(db, server_id).await
   }
 
   pub async fn get_server_members(
@@ -213,9 +521,9 @@ impl ServerService {
     let members = sqlx::query_as::<_, FullProfile>(
       r#"
       SELECT
-        u.id, u.username, p.display_name, p.bio, p.avatar_url, p.banner_url,
+        u.id, u.username, u.bot, p.display_name, p.bio, p.avatar_url, p.banner_url,
         p.status, p.custom_status, p.status_emoji, p.show_online_status,
-        p.allow_dms, p.created_at, p.updated_at
+        p.dm_privacy, p.created_at, p.updated_at
       FROM users u
       LEFT JOIN profiles p ON u.id = p.user_id
       INNER JOIN server_members sm ON u.id = sm.user_id
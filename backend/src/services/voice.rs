@@ -0,0 +1,349 @@
+// backend/src/services/voice.rs
+use crate::models::{AuditLogAction, AuditLogChange, ChannelType, VoiceState};
+use crate::services::{AuditLogService, ChannelService, ServerService};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+// Every participant connects to every other one, which only works for small calls
+pub const MAX_VOICE_PARTICIPANTS: i64 = 8;
+
+// The server mute of a voice state lives on the membership
+const SELECT_VOICE_STATE: &str = r#"
+  SELECT vs.user_id, vs.server_id, vs.channel_id, vs.self_mute, vs.self_deaf,
+    sm.voice_muted AS server_mute
+  FROM voice_states vs
+  INNER JOIN server_members sm ON sm.server_id = vs.server_id AND sm.user_id = vs.user_id
+"#;
+
+pub struct VoiceService;
+
+impl VoiceService {
+  async fn get_state(db: &PgPool, user_id: Uuid) -> AppResult<Option<VoiceState>> {
+    let state =
+      sqlx::query_as::<_, VoiceState>(&format!("{} WHERE vs.user_id = $1", SELECT_VOICE_STATE))
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(state)
+  }
+
+  // Returns the state the user had before, which may be in another server, and the new one
+  pub async fn join(
+    db: &PgPool,
+    user_id: Uuid,
+    session_id: Uuid,
+    channel_id: Uuid,
+    self_mute: bool,
+    self_deaf: bool,
+  ) -> AppResult<(Option<VoiceState>, VoiceState)> {
+    let channel = ChannelService::get_channel_by_id(db, channel_id).await?;
+    let (ChannelType::Voice, Some(server_id)) = (channel.channel_type, channel.server_id) else {
+      return Err(AppError::BadRequest(
+        ErrorCode::InvalidChannel,
+        "Not a voice channel".to_string(),
+      ));
+    };
+
+    if !ServerService::is_member(db, server_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You don't have access to that channel".to_string(),
+      ));
+    }
+
+    let previous = Self::get_state(db, user_id).await?;
+
+    let mut tx = db.begin().await?;
+
+    // Holding the channel row keeps concurrent joins from going over the limit together
+    sqlx::query("SELECT id FROM channels WHERE id = $1 FOR UPDATE")
+      .bind(channel_id)
+      .execute(&mut *tx)
+      .await?;
+
+    let participants: i64 = sqlx::query_scalar(
+      "SELECT COUNT(*) FROM voice_states WHERE channel_id = $1 AND user_id <> $2",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if participants >= MAX_VOICE_PARTICIPANTS {
+      return Err(AppError::BadRequest(
+        ErrorCode::VoiceChannelFull,
+        format!(
+          "Voice channels can have at most {} participants",
+          MAX_VOICE_PARTICIPANTS
+        ),
+      ));
+    }
+
+    let state = sqlx::query_as::<_, VoiceState>(
+      r#"
+      WITH joined AS (
+        INSERT INTO voice_states (user_id, server_id, channel_id, session_id, self_mute, self_deaf)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id) DO UPDATE
+        SET
+          server_id = EXCLUDED.server_id,
+          channel_id = EXCLUDED.channel_id,
+          session_id = EXCLUDED.session_id,
+          self_mute = EXCLUDED.self_mute,
+          self_deaf = EXCLUDED.self_deaf,
+          joined_at = NOW()
+        RETURNING *
+      )
+      SELECT vs.user_id, vs.server_id, vs.channel_id, vs.self_mute, vs.self_deaf,
+        sm.voice_muted AS server_mute
+      FROM joined vs
+      INNER JOIN server_members sm ON sm.server_id = vs.server_id AND sm.user_id = vs.user_id
+      "#,
+    )
+    .bind(user_id)
+    .bind(server_id)
+    .bind(channel_id)
+    .bind(session_id)
+    .bind(self_mute)
+    .bind(self_deaf)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((previous, state))
+  }
+
+  // With a session only that socket's state is removed, another one may have joined since
+  pub async fn leave(
+    db: &PgPool,
+    user_id: Uuid,
+    session_id: Option<Uuid>,
+  ) -> AppResult<Option<VoiceState>> {
+    let Some(mut state) = Self::get_state(db, user_id).await? else {
+      return Ok(None);
+    };
+
+    let result = sqlx::query(
+      "DELETE FROM voice_states WHERE user_id = $1 AND ($2::uuid IS NULL OR session_id = $2)",
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+      return Ok(None);
+    }
+
+    state.channel_id = None;
+    Ok(Some(state))
+  }
+
+  // For a member being removed, runs before the membership the server mute is read from is gone
+  pub async fn leave_server<'e, E>(
+    db: E,
+    server_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Option<VoiceState>>
+  where
+    E: Executor<'e, Database = Postgres>,
+  {
+    let state = sqlx::query_as::<_, VoiceState>(
+      r#"
+      WITH removed AS (
+        DELETE FROM voice_states
+        WHERE server_id = $1 AND user_id = $2
+        RETURNING *
+      )
+      SELECT vs.user_id, vs.server_id, NULL::uuid AS channel_id, vs.self_mute, vs.self_deaf,
+        sm.voice_muted AS server_mute
+      FROM removed vs
+      INNER JOIN server_members sm ON sm.server_id = vs.server_id AND sm.user_id = vs.user_id
+      "#,
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(state)
+  }
+
+  pub async fn update(
+    db: &PgPool,
+    user_id: Uuid,
+    session_id: Uuid,
+    self_mute: Option<bool>,
+    self_deaf: Option<bool>,
+  ) -> AppResult<VoiceState> {
+    let result = sqlx::query(
+      r#"
+      UPDATE voice_states
+      SET self_mute = COALESCE($1, self_mute), self_deaf = COALESCE($2, self_deaf)
+      WHERE user_id = $3 AND session_id = $4
+      "#,
+    )
+    .bind(self_mute)
+    .bind(self_deaf)
+    .bind(user_id)
+    .bind(session_id)
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+      return Err(Self::not_in_voice());
+    }
+
+    Self::get_state(db, user_id)
+      .await?
+      .ok_or_else(Self::not_in_voice)
+  }
+
+  fn not_in_voice() -> AppError {
+    AppError::BadRequest(
+      ErrorCode::NotInVoiceChannel,
+      "You are not in a voice channel".to_string(),
+    )
+  }
+
+  pub async fn get_channel_states(db: &PgPool, channel_id: Uuid) -> AppResult<Vec<VoiceState>> {
+    let states = sqlx::query_as::<_, VoiceState>(&format!(
+      "{} WHERE vs.channel_id = $1 ORDER BY vs.joined_at ASC",
+      SELECT_VOICE_STATE
+    ))
+    .bind(channel_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(states)
+  }
+
+  pub async fn get_server_states(
+    db: &PgPool,
+    server_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Vec<VoiceState>> {
+    if !ServerService::is_member(db, server_id, user_id).await? {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingAccess,
+        "You are not a member of this server".to_string(),
+      ));
+    }
+
+    let states = sqlx::query_as::<_, VoiceState>(&format!(
+      "{} WHERE vs.server_id = $1 ORDER BY vs.joined_at ASC",
+      SELECT_VOICE_STATE
+    ))
+    .bind(server_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(states)
+  }
+
+  // Signals are only relayed from the socket in the call to another participant of it
+  pub async fn can_signal(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+    session_id: Uuid,
+    target_id: Uuid,
+  ) -> AppResult<bool> {
+    let allowed: bool = sqlx::query_scalar(
+      r#"
+      SELECT
+        EXISTS(
+          SELECT 1 FROM voice_states
+          WHERE user_id = $2 AND session_id = $3 AND channel_id = $1
+        )
+        AND EXISTS(SELECT 1 FROM voice_states WHERE user_id = $4 AND channel_id = $1)
+      "#,
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .bind(session_id)
+    .bind(target_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(allowed && user_id != target_id)
+  }
+
+  // Returns the member's voice state in the server if they are in a call there
+  pub async fn set_server_mute(
+    db: &PgPool,
+    server_id: Uuid,
+    member_id: Uuid,
+    user_id: Uuid,
+    server_mute: bool,
+    reason: Option<&str>,
+  ) -> AppResult<Option<VoiceState>> {
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can mute members".to_string(),
+      ));
+    }
+
+    let mut tx = db.begin().await?;
+
+    let previous: Option<bool> = sqlx::query_scalar(
+      r#"
+      SELECT voice_muted FROM server_members
+      WHERE server_id = $1 AND user_id = $2
+      FOR UPDATE
+      "#,
+    )
+    .bind(server_id)
+    .bind(member_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(previous) = previous else {
+      return Err(AppError::NotFound(
+        ErrorCode::UnknownMember,
+        "Unknown member".to_string(),
+      ));
+    };
+
+    if previous != server_mute {
+      sqlx::query(
+        "UPDATE server_members SET voice_muted = $1 WHERE server_id = $2 AND user_id = $3",
+      )
+      .bind(server_mute)
+      .bind(server_id)
+      .bind(member_id)
+      .execute(&mut *tx)
+      .await?;
+
+      let changes = vec![AuditLogChange {
+        key: "server_mute".to_string(),
+        old_value: Some(serde_json::json!(previous)),
+        new_value: Some(serde_json::json!(server_mute)),
+      }];
+
+      AuditLogService::record(
+        &mut *tx,
+        server_id,
+        user_id,
+        AuditLogAction::MemberVoiceUpdate,
+        Some(member_id),
+        changes,
+        reason,
+      )
+      .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(
+      Self::get_state(db, member_id)
+        .await?
+        .filter(|state| state.server_id == server_id),
+    )
+  }
+}
@@ -0,0 +1,259 @@
+// backend/src/services/webhook.rs
+use crate::config::{JwtConfig, LimitsConfig};
+use crate::models::{
+  AuditLogAction, CreateWebhookRequest, ExecuteWebhookRequest, MessageResponse, Webhook,
+};
+use crate::services::{
+  AuditLogService, AuthService, ChannelService, EmojiService, MessageService, ServerService,
+};
+use crate::utils::{AppError, AppResult, ErrorCode, random_token};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const MAX_WEBHOOK_NAME_LENGTH: usize = 80;
+const MAX_AVATAR_URL_LENGTH: usize = 2048;
+
+pub struct WebhookService;
+
+impl WebhookService {
+  fn validate_name(field: &'static str, name: &str) -> AppResult<()> {
+    if name.is_empty() {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::FieldRequired,
+        "Webhook name cannot be empty",
+      ));
+    }
+    if name.chars().count() > MAX_WEBHOOK_NAME_LENGTH {
+      return Err(AppError::invalid_field(
+        field,
+        ErrorCode::FieldTooLong,
+        "Webhook name must be at most 80 characters",
+      ));
+    }
+
+    Ok(())
+  }
+
+  fn validate_avatar_url(avatar_url: &str) -> AppResult<()> {
+    if !(avatar_url.starts_with("https://") || avatar_url.starts_with("http://"))
+      || avatar_url.len() > MAX_AVATAR_URL_LENGTH
+    {
+      return Err(AppError::invalid_field(
+        "avatar_url",
+        ErrorCode::InvalidFormat,
+        "Avatar must be an http or https URL",
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Webhooks belong to server channels and are managed by the server owner
+  // Returns the channel's server
+  async fn require_manager(db: &PgPool, channel_id: Uuid, user_id: Uuid) -> AppResult<Uuid> {
+    let channel = ChannelService::get_channel_by_id(db, channel_id).await?;
+
+    let Some(server_id) = channel.server_id else {
+      return Err(AppError::BadRequest(
+        ErrorCode::InvalidChannel,
+        "Webhooks can only be added to server channels".to_string(),
+      ));
+    };
+
+    let server = ServerService::get_server_by_id(db, server_id).await?;
+    if server.owner_id != user_id {
+      return Err(AppError::Forbidden(
+        ErrorCode::MissingPermissions,
+        "Only the server owner can manage webhooks".to_string(),
+      ));
+    }
+
+    Ok(server_id)
+  }
+
+  // Returns the webhook and its token, which is not stored in plain text
+  pub async fn create_webhook(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    channel_id: Uuid,
+    user_id: Uuid,
+    req: CreateWebhookRequest,
+    reason: Option<&str>,
+  ) -> AppResult<(Webhook, String)> {
+    let server_id = Self::require_manager(db, channel_id, user_id).await?;
+
+    let name = req.name.trim();
+    Self::validate_name("name", name)?;
+    if let Some(avatar_url) = &req.avatar_url {
+      Self::validate_avatar_url(avatar_url)?;
+    }
+
+    let token = random_token();
+
+    let mut tx = db.begin().await?;
+
+    let webhook = sqlx::query_as::<_, Webhook>(
+      r#"
+      INSERT INTO webhooks (channel_id, creator_id, name, avatar_url, token_hash)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING id, channel_id, creator_id, name, avatar_url, created_at, updated_at
+      "#,
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .bind(name)
+    .bind(&req.avatar_url)
+    .bind(AuthService::hash_user_token(jwt, &token))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::WebhookCreate,
+      Some(webhook.id),
+      AuditLogService::diff(None, Some(&webhook)),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((webhook, token))
+  }
+
+  pub async fn get_channel_webhooks(
+    db: &PgPool,
+    channel_id: Uuid,
+    user_id: Uuid,
+  ) -> AppResult<Vec<Webhook>> {
+    Self::require_manager(db, channel_id, user_id).await?;
+
+    let webhooks = sqlx::query_as::<_, Webhook>(
+      r#"
+      SELECT id, channel_id, creator_id, name, avatar_url, created_at, updated_at
+      FROM webhooks
+      WHERE channel_id = $1
+      ORDER BY created_at ASC
+      "#,
+    )
+    .bind(channel_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(webhooks)
+  }
+
+  pub async fn delete_webhook(
+    db: &PgPool,
+    webhook_id: Uuid,
+    user_id: Uuid,
+    reason: Option<&str>,
+  ) -> AppResult<()> {
+    let webhook = sqlx::query_as::<_, Webhook>(
+      r#"
+      SELECT id, channel_id, creator_id, name, avatar_url, created_at, updated_at
+      FROM webhooks
+      WHERE id = $1
+      "#,
+    )
+    .bind(webhook_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownWebhook, "Unknown webhook".to_string()))?;
+
+    let server_id = Self::require_manager(db, webhook.channel_id, user_id).await?;
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM webhooks WHERE id = $1")
+      .bind(webhook_id)
+      .execute(&mut *tx)
+      .await?;
+
+    AuditLogService::record(
+      &mut *tx,
+      server_id,
+      user_id,
+      AuditLogAction::WebhookDelete,
+      Some(webhook_id),
+      AuditLogService::diff(Some(&webhook), None),
+      reason,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+
+  // A wrong token is reported the same way as a missing webhook
+  pub async fn execute_webhook(
+    db: &PgPool,
+    jwt: &JwtConfig,
+    limits: &LimitsConfig,
+    webhook_id: Uuid,
+    token: &str,
+    req: ExecuteWebhookRequest,
+  ) -> AppResult<MessageResponse> {
+    let webhook = sqlx::query_as::<_, Webhook>(
+      r#"
+      SELECT id, channel_id, creator_id, name, avatar_url, created_at, updated_at
+      FROM webhooks
+      WHERE id = $1 AND token_hash = $2
+      "#,
+    )
+    .bind(webhook_id)
+    .bind(AuthService::hash_user_token(jwt, token))
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::UnknownWebhook, "Unknown webhook".to_string()))?;
+
+    MessageService::validate_content(limits, &req.content)?;
+    let server_id = ChannelService::get_channel_by_id(db, webhook.channel_id)
+      .await?
+      .server_id;
+    let content = EmojiService::resolve_content(db, &req.content, server_id).await?;
+
+    let username = req
+      .username
+      .map(|username| username.trim().to_string())
+      .filter(|username| !username.is_empty());
+    if let Some(username) = &username {
+      Self::validate_name("username", username)?;
+    }
+    if let Some(avatar_url) = &req.avatar_url {
+      Self::validate_avatar_url(avatar_url)?;
+    }
+
+    let message = sqlx::query_as::<_, MessageResponse>(
+      r#"
+      INSERT INTO messages (channel_id, webhook_id, author_name, author_avatar_url, content)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING
+        id,
+        channel_id,
+        user_id,
+        author_name AS username,
+        webhook_id,
+        author_avatar_url AS avatar_url,
+        message_type,
+        content,
+        system_data,
+        created_at,
+        updated_at
+      "#,
+    )
+    .bind(webhook.channel_id)
+    .bind(webhook.id)
+    .bind(username.unwrap_or(webhook.name))
+    .bind(req.avatar_url.or(webhook.avatar_url))
+    .bind(&content)
+    .fetch_one(db)
+    .await?;
+
+    Ok(message)
+  }
+}
@@ -1,27 +1,161 @@
 use std::fmt;
 
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::{
+  Json,
+  http::{HeaderMap, HeaderValue, StatusCode, header},
+  response::IntoResponse,
+};
 use serde::Serialize;
 
+// Stable machine-readable codes, clients should match on these rather than on messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+  // General
+  InternalError,
+  RateLimited,
+
+  // Authentication
+  MissingToken,
+  InvalidToken,
+  TokenExpired,
+  TokenMalformed,
+  InvalidTokenSignature,
+  SessionInvalidated,
+  InvalidCredentials,
+  InvalidPassword,
+  InvalidOrExpiredToken,
+  MfaRequired,
+  InvalidMfaCode,
+  MfaAlreadyEnabled,
+  MfaNotEnabled,
+  MfaEnrollmentNotStarted,
+  OidcLoginFailed,
+  ReauthenticationRequired,
+
+  // Unknown resources
+  UnknownUser,
+  UnknownServer,
+  UnknownChannel,
+  UnknownMember,
+  UnknownFolder,
+  UnknownFriendRequest,
+  UnknownFriendship,
+  UnknownBlock,
+  UnknownPushSubscription,
+  UnknownProvider,
+  UnknownIdentity,
+  UnknownApplication,
+  UnknownWebhook,
+  UnknownMessage,
+  UnknownCommand,
+  UnknownInteraction,
+  UnknownEmoji,
+  UnknownBan,
+
+  // Permissions
+  MissingAccess,
+  MissingPermissions,
+  BotNotAllowed,
+  BannedFromServer,
+
+  // Conflicts
+  AlreadyExists,
+  UsernameOrEmailTaken,
+  IdentityAlreadyLinked,
+  AlreadyMember,
+  InteractionAlreadyAcknowledged,
+
+  // Validation
+  FieldRequired,
+  FieldTooLong,
+  InvalidFormat,
+  MessageTooLong,
+  PasswordTooShort,
+  GroupDmFull,
+
+  // Requests not allowed in the current state
+  InvalidChannel,
+  CannotDmSelf,
+  CannotDmUser,
+  NotFriends,
+  CannotFriendSelf,
+  FriendRequestBlocked,
+  CannotBlockSelf,
+  CannotRemoveSelf,
+  UsernameChangeCooldown,
+  PushDisabled,
+  CannotRemoveLastLogin,
+  CannotFriendBot,
+  InteractionFailed,
+  MaxEmojisReached,
+  VoiceChannelFull,
+  NotInVoiceChannel,
+  CannotBanOwner,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+  pub field: &'static str,
+  pub code: ErrorCode,
+  pub message: String,
+}
+
 #[derive(Debug)]
 pub enum AppError {
   DatabaseError(sqlx::Error),
-  NotFound(String),
-  Unauthorized(String),
-  BadRequest(String),
+  NotFound(ErrorCode, String),
+  Unauthorized(ErrorCode, String),
+  Forbidden(ErrorCode, String),
+  Conflict(ErrorCode, String),
+  BadRequest(ErrorCode, String),
   InternalServerError(String),
-  ValidationError(String),
+  ValidationError(Vec<FieldError>),
+  // Seconds until the request may be retried
+  RateLimited(u64),
+}
+
+impl AppError {
+  pub fn invalid_field(field: &'static str, code: ErrorCode, message: impl Into<String>) -> Self {
+    AppError::ValidationError(vec![FieldError {
+      field,
+      code,
+      message: message.into(),
+    }])
+  }
+}
+
+impl ErrorCode {
+  // Failures that mean the presented bearer token itself is unusable
+  fn is_token_error(self) -> bool {
+    matches!(
+      self,
+      ErrorCode::InvalidToken
+        | ErrorCode::TokenExpired
+        | ErrorCode::TokenMalformed
+        | ErrorCode::InvalidTokenSignature
+        | ErrorCode::SessionInvalidated
+    )
+  }
 }
 
 impl fmt::Display for AppError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
       AppError::DatabaseError(e) => write!(f, "Database error: {}", e),
-      AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
-      AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
-      AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+      AppError::NotFound(_, msg) => write!(f, "Not found: {}", msg),
+      AppError::Unauthorized(_, msg) => write!(f, "Unauthorized: {}", msg),
+      AppError::Forbidden(_, msg) => write!(f, "Forbidden: {}", msg),
+      AppError::Conflict(_, msg) => write!(f, "Conflict: {}", msg),
+      AppError::BadRequest(_, msg) => write!(f, "Bad request: {}", msg),
       AppError::InternalServerError(msg) => write!(f, "Internal server error: {}", msg),
-      AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
+      AppError::ValidationError(errors) => {
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        write!(f, "Validation error: {}", messages.join(", "))
+      }
+      AppError::RateLimited(retry_after) => {
+        write!(f, "Rate limited: retry after {} seconds", retry_after)
+      }
     }
   }
 }
@@ -30,42 +164,108 @@ impl std::error::Error for AppError {}
 
 impl From<sqlx::Error> for AppError {
   fn from(error: sqlx::Error) -> Self {
-    AppError::DatabaseError(error)
+    // Services map the unique violations they expect to specific codes, this catches the rest
+    match &error {
+      sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+        AppError::Conflict(
+          ErrorCode::AlreadyExists,
+          "Resource already exists".to_string(),
+        )
+      }
+      _ => AppError::DatabaseError(error),
+    }
   }
 }
 
 #[derive(Serialize)]
 struct ErrorResponse {
+  code: ErrorCode,
   error: String,
   message: String,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  errors: Vec<FieldError>,
 }
 
 impl IntoResponse for AppError {
   fn into_response(self) -> axum::response::Response {
-    let (status, error_message) = match self {
+    let mut headers = HeaderMap::new();
+    match &self {
+      AppError::RateLimited(retry_after) => {
+        if let Ok(value) = HeaderValue::from_str(&retry_after.to_string()) {
+          headers.insert(header::RETRY_AFTER, value);
+        }
+      }
+      // RFC 6750 challenge, only token failures carry an error so clients know to sign in again
+      AppError::Unauthorized(code, msg) => {
+        let challenge = if code.is_token_error() {
+          format!(
+            r#"Bearer realm="api", error="invalid_token", error_description="{}""#,
+            msg.replace('"', "'")
+          )
+        } else {
+          r#"Bearer realm="api""#.to_string()
+        };
+        if let Ok(value) = HeaderValue::from_str(&challenge) {
+          headers.insert(header::WWW_AUTHENTICATE, value);
+        }
+      }
+      _ => {}
+    }
+
+    let mut errors = Vec::new();
+    let (status, code, error_message) = match self {
       AppError::DatabaseError(e) => {
         tracing::error!("Database error: {:?}", e);
         (
           StatusCode::INTERNAL_SERVER_ERROR,
+          ErrorCode::InternalError,
           "Database error occurred".to_string(),
         )
       }
-      AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-      AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
-      AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-      AppError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-      AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg),
+      AppError::NotFound(code, msg) => (StatusCode::NOT_FOUND, code, msg),
+      AppError::Unauthorized(code, msg) => (StatusCode::UNAUTHORIZED, code, msg),
+      AppError::Forbidden(code, msg) => (StatusCode::FORBIDDEN, code, msg),
+      AppError::Conflict(code, msg) => (StatusCode::CONFLICT, code, msg),
+      AppError::BadRequest(code, msg) => (StatusCode::BAD_REQUEST, code, msg),
+      // The message is for operators, it can name internals the client should not see
+      AppError::InternalServerError(msg) => {
+        tracing::error!("Internal server error: {}", msg);
+        (
+          StatusCode::INTERNAL_SERVER_ERROR,
+          ErrorCode::InternalError,
+          "Internal server error".to_string(),
+        )
+      }
+      // The first field error doubles as the top-level code and message
+      AppError::ValidationError(field_errors) => {
+        let (code, msg) = field_errors
+          .first()
+          .map(|e| (e.code, e.message.clone()))
+          .unwrap_or((ErrorCode::InvalidFormat, "Invalid request".to_string()));
+        errors = field_errors;
+        (StatusCode::BAD_REQUEST, code, msg)
+      }
+      AppError::RateLimited(retry_after) => (
+        StatusCode::TOO_MANY_REQUESTS,
+        ErrorCode::RateLimited,
+        format!(
+          "You are being rate limited, try again in {} seconds",
+          retry_after
+        ),
+      ),
     };
 
     let body = Json(ErrorResponse {
+      code,
       error: status
         .canonical_reason()
         .unwrap_or("Unknown error")
         .to_string(),
       message: error_message,
+      errors,
     });
 
-    (status, body).into_response()
+    (status, headers, body).into_response()
   }
 }
 
@@ -1,3 +1,6 @@
 pub mod error;
+pub mod net;
+pub mod token;
 
-pub use error::{AppError, AppResult};
+pub use error::{AppError, AppResult, ErrorCode, FieldError};
+pub use token::random_token;
@@ -0,0 +1,184 @@
+use std::{
+  net::{IpAddr, Ipv4Addr},
+  sync::Arc,
+  time::Duration,
+};
+
+use reqwest::{
+  dns::{Addrs, Name, Resolve, Resolving},
+  redirect,
+};
+
+// Client for requests to URLs chosen by users. Redirects are not followed, they could lead
+// past the URL checks. Names are resolved here rather than by a proxy so the addresses
+// actually connected to can be checked
+pub fn outbound_client(timeout: Duration, allow_private: bool) -> reqwest::Client {
+  let mut builder = reqwest::Client::builder()
+    .timeout(timeout)
+    .redirect(redirect::Policy::none())
+    .no_proxy();
+  if !allow_private {
+    builder = builder.dns_resolver(Arc::new(PublicResolver));
+  }
+
+  builder
+    .build()
+    .expect("Failed to build outbound http client.")
+}
+
+// Drops local and private addresses when resolving, so a name that resolves to one at
+// request time is refused just like a literal address
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+  fn resolve(&self, name: Name) -> Resolving {
+    Box::pin(async move {
+      let host = name.as_str().to_string();
+      let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), 0))
+        .await?
+        .filter(|addr| !is_private_ip(addr.ip()))
+        .collect();
+
+      if addrs.is_empty() {
+        return Err(format!("{} does not resolve to a public address", host).into());
+      }
+
+      let addrs: Addrs = Box::new(addrs.into_iter());
+      Ok(addrs)
+    })
+  }
+}
+
+// Catches localhost and literal addresses, names are checked again when resolved
+pub fn is_private_host(host: &str) -> bool {
+  let host = host.trim_start_matches('[').trim_end_matches(']');
+
+  if host.eq_ignore_ascii_case("localhost") || host.to_ascii_lowercase().ends_with(".localhost") {
+    return true;
+  }
+
+  host.parse::<IpAddr>().is_ok_and(is_private_ip)
+}
+
+pub fn is_private_ip(ip: IpAddr) -> bool {
+  match ip {
+    IpAddr::V4(ip) => is_private_ipv4(ip),
+    IpAddr::V6(ip) => {
+      let segment = ip.segments()[0];
+      ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local fc00::/7 and link-local fe80::/10
+        || (segment & 0xfe00) == 0xfc00
+        || (segment & 0xffc0) == 0xfe80
+        // IPv4-mapped and IPv4-compatible addresses reach the embedded address
+        || ip.to_ipv4().is_some_and(is_private_ipv4)
+    }
+  }
+}
+
+fn is_private_ipv4(ip: Ipv4Addr) -> bool {
+  let [a, b, ..] = ip.octets();
+  ip.is_loopback()
+    || ip.is_private()
+    // Includes 169.254.169.254, the cloud metadata service
+    || ip.is_link_local()
+    || ip.is_unspecified()
+    || ip.is_broadcast()
+    || ip.is_documentation()
+    || ip.is_multicast()
+    // 0.0.0.0/8, carrier-grade NAT 100.64.0.0/10, benchmarking 198.18.0.0/15 and reserved 240.0.0.0/4
+    || a == 0
+    || (a == 100 && (b & 0xc0) == 64)
+    || (a == 198 && (b & 0xfe) == 18)
+    || a >= 240
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+  }
+
+  #[test]
+  fn private_ipv4_ranges() {
+    for address in [
+      "127.0.0.1",
+      "10.1.2.3",
+      "172.16.0.1",
+      "192.168.1.1",
+      "169.254.169.254",
+      "0.0.0.0",
+      "0.1.2.3",
+      "100.64.0.1",
+      "100.127.255.255",
+      "198.18.0.1",
+      "224.0.0.1",
+      "240.0.0.1",
+      "255.255.255.255",
+    ] {
+      assert!(is_private_ip(ip(address)), "{} should be private", address);
+    }
+  }
+
+  #[test]
+  fn public_ipv4_addresses() {
+    for address in [
+      "1.1.1.1",
+      "8.8.8.8",
+      "100.63.255.255",
+      "100.128.0.1",
+      "198.20.0.1",
+    ] {
+      assert!(!is_private_ip(ip(address)), "{} should be public", address);
+    }
+  }
+
+  #[test]
+  fn private_ipv6_ranges() {
+    for address in [
+      "::1",
+      "::",
+      "fc00::1",
+      "fd12:3456::1",
+      "fe80::1",
+      "ff02::1",
+      "::ffff:127.0.0.1",
+      "::ffff:169.254.169.254",
+      "::ffff:10.0.0.1",
+    ] {
+      assert!(is_private_ip(ip(address)), "{} should be private", address);
+    }
+
+    assert!(!is_private_ip(ip("2606:4700:4700::1111")));
+    assert!(!is_private_ip(ip("::ffff:1.1.1.1")));
+  }
+
+  #[test]
+  fn private_hosts() {
+    for host in [
+      "localhost",
+      "LOCALHOST",
+      "x.localhost",
+      "api.X.Localhost",
+      "[::1]",
+      "[::ffff:127.0.0.1]",
+      "127.0.0.1",
+      "169.254.169.254",
+      "100.64.0.1",
+    ] {
+      assert!(is_private_host(host), "{} should be private", host);
+    }
+
+    for host in [
+      "example.com",
+      "localhost.example.com",
+      "1.1.1.1",
+      "[2606:4700::1111]",
+    ] {
+      assert!(!is_private_host(host), "{} should be public", host);
+    }
+  }
+}
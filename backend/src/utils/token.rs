@@ -0,0 +1,9 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use rand::{RngCore, rngs::OsRng};
+
+// 256 random bits, url-safe so it can go in paths and query strings as is
+pub fn random_token() -> String {
+  let mut bytes = [0u8; 32];
+  OsRng.fill_bytes(&mut bytes);
+  URL_SAFE_NO_PAD.encode(bytes)
+}
@@ -0,0 +1,21 @@
+// Application close codes, 4000-4999 is reserved for private use by RFC 6455
+use crate::utils::{AppError, ErrorCode};
+
+pub const UNKNOWN_ERROR: u16 = 4000;
+pub const AUTHENTICATION_FAILED: u16 = 4001;
+pub const TOKEN_EXPIRED: u16 = 4002;
+pub const SESSION_INVALIDATED: u16 = 4003;
+pub const RATE_LIMITED: u16 = 4008;
+
+// Close reasons are limited to 123 bytes so the error code is sent rather than the message
+pub fn for_error(error: &AppError) -> (u16, &'static str) {
+  match error {
+    AppError::Unauthorized(ErrorCode::TokenExpired, _) => (TOKEN_EXPIRED, "TOKEN_EXPIRED"),
+    AppError::Unauthorized(ErrorCode::SessionInvalidated, _) => {
+      (SESSION_INVALIDATED, "SESSION_INVALIDATED")
+    }
+    AppError::Unauthorized(_, _) => (AUTHENTICATION_FAILED, "AUTHENTICATION_FAILED"),
+    AppError::RateLimited(_) => (RATE_LIMITED, "RATE_LIMITED"),
+    _ => (UNKNOWN_ERROR, "UNKNOWN_ERROR"),
+  }
+}
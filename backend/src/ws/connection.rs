@@ -1,11 +1,27 @@
-use axum::extract::ws::{Message, WebSocket};
+use axum::extract::ws::{CloseFrame, Message, WebSocket};
 use futures::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, mpsc};
 use uuid::Uuid;
 
+use crate::models::{
+  DmChannelResponse, DmParticipantInfo, FriendshipStatus, FullProfile, InteractionPayload,
+  MessageType, OrganizedServersResponse, ServerResponse, SystemMessageData, VoiceSignal,
+  VoiceState,
+};
+use crate::services::{ChannelService, PresenceService};
+use crate::ws::close_code;
+use crate::ws::pubsub::{Envelope, PubSub, Target};
+use crate::ws::voice;
+
+// Client frames allowed per window, sockets sending twice that are closed
+const FRAME_LIMIT: u32 = 50;
+const FRAME_WINDOW: Duration = Duration::from_secs(10);
+
 pub type Tx = mpsc::UnboundedSender<Message>;
 
 // user_id -> list of connections
@@ -17,19 +33,84 @@ pub type ChannelSubscriptions = Arc<RwLock<HashMap<Uuid, HashSet<Uuid>>>>;
 pub struct ConnectionHandle {
   pub tx: Tx,
   pub subscriptions: Arc<RwLock<HashSet<Uuid>>>,
+  pub session_id: Uuid,
 }
 
 pub struct ConnectionMap {
   pub users: UserConnections,
   pub channels: ChannelSubscriptions,
+  pub pubsub: PubSub,
+  // Identifies this process in gateway_sessions
+  pub node_id: Uuid,
 }
 
-impl Default for ConnectionMap {
-  fn default() -> Self {
+impl ConnectionMap {
+  pub fn new(pubsub: PubSub) -> Self {
     Self {
       users: Arc::new(RwLock::new(HashMap::new())),
       channels: Arc::new(RwLock::new(HashMap::new())),
+      pubsub,
+      node_id: Uuid::new_v4(),
+    }
+  }
+
+  pub async fn send_to_user(
+    &self,
+    user_id: Uuid,
+    message: WsMessage,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    self.send_to_users(vec![user_id], message).await
+  }
+
+  pub async fn send_to_users(
+    &self,
+    user_ids: Vec<Uuid>,
+    message: WsMessage,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    if user_ids.is_empty() {
+      return Ok(());
     }
+
+    self
+      .pubsub
+      .publish(Envelope {
+        target: Target::Users { user_ids },
+        exclude_user: None,
+        message,
+      })
+      .await
+  }
+
+  // Drops the user's subscription to a channel they lost access to, on every node
+  pub async fn unsubscribe_user(
+    &self,
+    user_id: Uuid,
+    channel_id: Uuid,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    self
+      .send_to_user(user_id, WsMessage::Unsubscribed { channel_id })
+      .await
+  }
+
+  pub async fn broadcast_to_server(
+    &self,
+    db: &PgPool,
+    server_id: Uuid,
+    message: WsMessage,
+    exclude_user: Option<Uuid>,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    let member_ids: Vec<Uuid> =
+      sqlx::query_scalar("SELECT user_id FROM server_members WHERE server_id = $1")
+        .bind(server_id)
+        .fetch_all(db)
+        .await?;
+
+    let user_ids = member_ids
+      .into_iter()
+      .filter(|id| exclude_user != Some(*id))
+      .collect();
+
+    self.send_to_users(user_ids, message).await
   }
 }
 
@@ -38,6 +119,8 @@ impl Clone for ConnectionMap {
     Self {
       users: Arc::clone(&self.users),
       channels: Arc::clone(&self.channels),
+      pubsub: self.pubsub.clone(),
+      node_id: self.node_id,
     }
   }
 }
@@ -56,16 +139,123 @@ pub enum WsMessage {
     channel_id: Uuid,
     content: String,
   },
+  // Joining while in another voice channel moves there
+  JoinVoice {
+    channel_id: Uuid,
+    #[serde(default)]
+    self_mute: bool,
+    #[serde(default)]
+    self_deaf: bool,
+  },
+  LeaveVoice,
+  UpdateVoiceState {
+    self_mute: Option<bool>,
+    self_deaf: Option<bool>,
+  },
+  // user_id is the participant the signal is for
+  VoiceSignal {
+    channel_id: Uuid,
+    user_id: Uuid,
+    signal: VoiceSignal,
+  },
 
   // Server -> Client
   MessageCreated {
     id: Uuid,
     channel_id: Uuid,
-    user_id: Uuid,
-    username: String,
+    user_id: Option<Uuid>,
+    username: Option<String>,
+    webhook_id: Option<Uuid>,
+    avatar_url: Option<String>,
+    message_type: MessageType,
     content: String,
+    system_data: Option<SystemMessageData>,
     created_at: String,
   },
+  MessageUpdated {
+    id: Uuid,
+    channel_id: Uuid,
+    content: String,
+    updated_at: String,
+  },
+  MessageDeleted {
+    id: Uuid,
+    channel_id: Uuid,
+  },
+  Mentioned {
+    channel_id: Uuid,
+    server_id: Option<Uuid>,
+    message_id: Uuid,
+  },
+  UnreadMessage {
+    channel_id: Uuid,
+    server_id: Option<Uuid>,
+    message_id: Uuid,
+  },
+  FriendRequestReceived {
+    user: FullProfile,
+  },
+  FriendshipUpdated {
+    user_id: Uuid,
+    status: Option<FriendshipStatus>,
+  },
+  DmCreated {
+    dm: DmChannelResponse,
+  },
+  DmUpdated {
+    dm: DmChannelResponse,
+  },
+  DmRecipientAdded {
+    channel_id: Uuid,
+    user: DmParticipantInfo,
+  },
+  DmRecipientRemoved {
+    channel_id: Uuid,
+    user_id: Uuid,
+  },
+  ServerJoined {
+    server: ServerResponse,
+  },
+  // Sent to a member who was removed from the server
+  ServerLeft {
+    server_id: Uuid,
+  },
+  // Sent to the bot of an application without an interactions URL
+  InteractionCreated {
+    interaction: InteractionPayload,
+  },
+  // Sent only to the user who ran the command
+  InteractionDeferred {
+    interaction_id: Uuid,
+    application_id: Uuid,
+    channel_id: Uuid,
+  },
+  EphemeralMessage {
+    interaction_id: Uuid,
+    application_id: Uuid,
+    channel_id: Uuid,
+    content: String,
+  },
+  // Sent to the joining socket with everyone already in the channel, who it should send offers to
+  VoiceJoined {
+    channel_id: Uuid,
+    voice_states: Vec<VoiceState>,
+  },
+  // Sent to the members of the voice state's server
+  VoiceStateUpdated {
+    voice_state: VoiceState,
+  },
+  // user_id is the participant the signal is from
+  VoiceSignalReceived {
+    channel_id: Uuid,
+    user_id: Uuid,
+    signal: VoiceSignal,
+  },
+  OrganizationUpdated {
+    organization: OrganizedServersResponse,
+  },
+  // Sent before the server closes every socket of a user whose tokens were revoked
+  SessionInvalidated,
   Error {
     message: String,
   },
@@ -79,16 +269,21 @@ pub enum WsMessage {
 
 pub struct Connection {
   pub user_id: Uuid,
+  // Identifies this socket, a user's voice state belongs to the socket that joined
+  pub session_id: Uuid,
   pub socket: WebSocket,
+  pub db: PgPool,
   pub connection_map: ConnectionMap,
   pub subscriptions: Arc<RwLock<HashSet<Uuid>>>,
 }
 
 impl Connection {
-  pub fn new(user_id: Uuid, socket: WebSocket, connection_map: ConnectionMap) -> Self {
+  pub fn new(user_id: Uuid, socket: WebSocket, db: PgPool, connection_map: ConnectionMap) -> Self {
     Self {
       user_id,
+      session_id: Uuid::new_v4(),
       socket,
+      db,
       connection_map,
       subscriptions: Arc::new(RwLock::new(HashSet::new())),
     }
@@ -101,6 +296,7 @@ impl Connection {
     let connection_handle = ConnectionHandle {
       tx: tx.clone(),
       subscriptions: Arc::clone(&self.subscriptions),
+      session_id: self.session_id,
     };
 
     {
@@ -111,6 +307,17 @@ impl Connection {
         .push(connection_handle);
     }
 
+    if let Err(e) = PresenceService::add_session(
+      &self.db,
+      self.connection_map.node_id,
+      self.session_id,
+      self.user_id,
+    )
+    .await
+    {
+      tracing::error!("Failed to record gateway session: {}", e);
+    }
+
     tracing::info!(
       "WebSocket connection established for user: {}",
       self.user_id
@@ -126,14 +333,62 @@ impl Connection {
 
     let connection_map_clone = self.connection_map.clone();
     let user_id = self.user_id;
+    let session_id = self.session_id;
+    let db = self.db.clone();
     let subscriptions = Arc::clone(&self.subscriptions);
 
     let mut recv_task = tokio::spawn(async move {
+      let mut window_start = Instant::now();
+      let mut frames = 0u32;
+
       while let Some(Ok(msg)) = receiver.next().await {
+        if window_start.elapsed() >= FRAME_WINDOW {
+          window_start = Instant::now();
+          frames = 0;
+        }
+        frames += 1;
+
+        if frames > FRAME_LIMIT * 2 {
+          tracing::warn!(
+            "Closing WebSocket for user {}: frame limit exceeded",
+            user_id
+          );
+          let _ = tx.send(Message::Close(Some(CloseFrame {
+            code: close_code::RATE_LIMITED,
+            reason: "RATE_LIMITED".into(),
+          })));
+          break;
+        }
+
+        if frames > FRAME_LIMIT {
+          if frames == FRAME_LIMIT + 1 {
+            let response = WsMessage::Error {
+              message: "You are sending messages too quickly".to_string(),
+            };
+            if let Ok(json) = serde_json::to_string(&response) {
+              let _ = tx.send(Message::Text(json.into()));
+            }
+          }
+          continue;
+        }
+
         if let Message::Text(text) = msg {
           if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
             match ws_msg {
               WsMessage::Subscribe { channel_id } => {
+                match ChannelService::user_has_access_to_channel(&db, channel_id, user_id).await {
+                  Ok(true) => {}
+                  Ok(false) | Err(_) => {
+                    let response = WsMessage::Error {
+                      message: "You do not have access to this channel".to_string(),
+                    };
+                    if let Ok(json) = serde_json::to_string(&response) {
+                      let _ = tx.send(Message::Text(json.into()));
+                    }
+                    continue;
+                  }
+                }
+
                 {
                   let mut subs = subscriptions.write().await;
                   subs.insert(channel_id);
@@ -178,12 +433,10 @@ impl Connection {
                     false
                   };
 
-                  if !still_subscribed {
-                    if let Some(channel_subs) = channels.get_mut(&channel_id) {
-                      channel_subs.remove(&user_id);
-                      if channel_subs.is_empty() {
-                        channels.remove(&channel_id);
-                      }
+                  if !still_subscribed && let Some(channel_subs) = channels.get_mut(&channel_id) {
+                    channel_subs.remove(&user_id);
+                    if channel_subs.is_empty() {
+                      channels.remove(&channel_id);
                     }
                   }
                 }
@@ -195,6 +448,13 @@ impl Connection {
                   let _ = tx.send(Message::Text(json.into()));
                 }
               }
+              WsMessage::JoinVoice { .. }
+              | WsMessage::LeaveVoice
+              | WsMessage::UpdateVoiceState { .. }
+              | WsMessage::VoiceSignal { .. } => {
+                voice::handle_message(&db, &connection_map_clone, user_id, session_id, &tx, ws_msg)
+                  .await;
+              }
               _ => {
                 tracing::warn!("Unexpected message type reeived via WebSocket");
               }
@@ -245,17 +505,21 @@ impl Connection {
           false
         };
 
-        if !still_subscribed {
-          if let Some(channel_subs) = channels.get_mut(&channel_id) {
-            channel_subs.remove(&user_id);
-            if channel_subs.is_empty() {
-              channels.remove(&channel_id);
-            }
+        if !still_subscribed && let Some(channel_subs) = channels.get_mut(&channel_id) {
+          channel_subs.remove(&user_id);
+          if channel_subs.is_empty() {
+            channels.remove(&channel_id);
           }
         }
       }
     }
 
+    voice::disconnect(&self.db, &self.connection_map, user_id, self.session_id).await;
+
+    if let Err(e) = PresenceService::remove_session(&self.db, self.session_id).await {
+      tracing::error!("Failed to remove gateway session: {}", e);
+    }
+
     tracing::info!("WebSocket connection closed for user: {}", user_id);
   }
 }
@@ -266,20 +530,39 @@ pub async fn broadcast_to_channel(
   message: WsMessage,
   exclude_user: Option<Uuid>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-  let json = serde_json::to_string(&message)?;
+  connection_map
+    .pubsub
+    .publish(Envelope {
+      target: Target::Channel { channel_id },
+      exclude_user,
+      message,
+    })
+    .await
+}
+
+pub async fn deliver_to_channel(
+  connection_map: &ConnectionMap,
+  channel_id: Uuid,
+  message: &WsMessage,
+  exclude_user: Option<Uuid>,
+) {
+  let json = match serde_json::to_string(message) {
+    Ok(json) => json,
+    Err(e) => {
+      tracing::error!("Failed to serialize message: {}", e);
+      return;
+    }
+  };
   let ws_message = Message::Text(json.into());
 
   let subscribed_users = {
     let channels = connection_map.channels.read().await;
-    channels
-      .get(&channel_id)
-      .map(|users| users.clone())
-      .unwrap_or_default()
+    channels.get(&channel_id).cloned().unwrap_or_default()
   };
 
   if subscribed_users.is_empty() {
     tracing::debug!("No users subscribed to channel {}", channel_id);
-    return Ok(());
+    return;
   }
 
   tracing::debug!(
@@ -292,10 +575,8 @@ pub async fn broadcast_to_channel(
   let mut sent_count = 0;
 
   for user_id in subscribed_users {
-    if let Some(excluded) = exclude_user {
-      if user_id == excluded {
-        continue;
-      }
+    if exclude_user == Some(user_id) {
+      continue;
     }
 
     if let Some(user_conns) = users.get(&user_id) {
@@ -305,10 +586,8 @@ pub async fn broadcast_to_channel(
           subs.contains(&channel_id)
         };
 
-        if is_subscribed {
-          if conn.tx.send(ws_message.clone()).is_ok() {
-            sent_count += 1;
-          }
+        if is_subscribed && conn.tx.send(ws_message.clone()).is_ok() {
+          sent_count += 1;
         }
       }
     }
@@ -319,6 +598,63 @@ pub async fn broadcast_to_channel(
     sent_count,
     channel_id
   );
+}
+
+pub async fn deliver_to_users(
+  connection_map: &ConnectionMap,
+  user_ids: &[Uuid],
+  message: &WsMessage,
+) {
+  let json = match serde_json::to_string(message) {
+    Ok(json) => json,
+    Err(e) => {
+      tracing::error!("Failed to serialize message: {}", e);
+      return;
+    }
+  };
+  let ws_message = Message::Text(json.into());
 
-  Ok(())
+  let users = connection_map.users.read().await;
+  let mut sent_count = 0;
+
+  // Sent by the server when access was lost, the subscription is dropped before telling the client
+  if let WsMessage::Unsubscribed { channel_id } = message {
+    for user_id in user_ids {
+      for conn in users.get(user_id).into_iter().flatten() {
+        conn.subscriptions.write().await.remove(channel_id);
+      }
+    }
+
+    let mut channels = connection_map.channels.write().await;
+    if let Some(channel_subs) = channels.get_mut(channel_id) {
+      for user_id in user_ids {
+        channel_subs.remove(user_id);
+      }
+      if channel_subs.is_empty() {
+        channels.remove(channel_id);
+      }
+    }
+  }
+
+  for user_id in user_ids {
+    if let Some(user_conns) = users.get(user_id) {
+      for conn in user_conns {
+        if conn.tx.send(ws_message.clone()).is_ok() {
+          sent_count += 1;
+        }
+        if matches!(message, WsMessage::SessionInvalidated) {
+          let _ = conn.tx.send(Message::Close(Some(CloseFrame {
+            code: close_code::SESSION_INVALIDATED,
+            reason: "SESSION_INVALIDATED".into(),
+          })));
+        }
+      }
+    }
+  }
+
+  tracing::debug!(
+    "Sent message to {} connections for {} users",
+    sent_count,
+    user_ids.len()
+  );
 }
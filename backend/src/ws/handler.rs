@@ -1,38 +1,90 @@
 use crate::AppState;
+use crate::middleware::authenticate;
 use crate::services::AuthService;
-use crate::utils::{AppError, AppResult};
+use crate::utils::{AppError, AppResult, ErrorCode};
+use crate::ws::close_code;
 use crate::ws::connection::{Connection, ConnectionMap};
 use axum::{
   extract::{
     Query, State,
-    ws::{WebSocket, WebSocketUpgrade},
+    ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
   },
-  response::IntoResponse,
+  http::{HeaderMap, header},
+  response::{IntoResponse, Response},
 };
 use serde::Deserialize;
+use sqlx::PgPool;
 use uuid::Uuid;
 
+// Clients only send small control frames
+const MAX_MESSAGE_SIZE: usize = 16 * 1024;
+
 #[derive(Deserialize)]
 pub struct WsQuery {
-  token: String,
+  token: Option<String>,
 }
 
 pub async fn ws_handler(
   ws: WebSocketUpgrade,
   Query(query): Query<WsQuery>,
+  headers: HeaderMap,
   State(state): State<AppState>,
-) -> AppResult<impl IntoResponse> {
-  let claims = AuthService::verify_token(&query.token)?;
+) -> AppResult<Response> {
+  // Browsers pass their session token in the query, bots send an Authorization header
+  let authorization = headers
+    .get(header::AUTHORIZATION)
+    .and_then(|header| header.to_str().ok());
+  let result = match (&query.token, authorization) {
+    (Some(token), _) => AuthService::authenticate_token(&state.db, &state.config.jwt, token).await,
+    (None, Some(authorization)) => authenticate(&state, Some(authorization))
+      .await
+      .map(|user| user.id),
+    (None, None) => Err(AppError::Unauthorized(
+      ErrorCode::MissingToken,
+      "Missing token".to_string(),
+    )),
+  };
 
-  let user_id = Uuid::parse_str(&claims.sub)
-    .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
+  // Browsers cannot read the status of a failed upgrade, so auth failures are reported as a close code
+  let user_id = match result {
+    Ok(user_id) => user_id,
+    Err(e @ AppError::Unauthorized(..)) => {
+      let (code, reason) = close_code::for_error(&e);
+      tracing::debug!("Rejecting WebSocket connection: {}", e);
+      return Ok(
+        ws.on_upgrade(move |socket| reject_socket(socket, code, reason))
+          .into_response(),
+      );
+    }
+    Err(e) => return Err(e),
+  };
 
   tracing::info!("WebSocket upgrade request from user: {}", user_id);
 
-  Ok(ws.on_upgrade(move |socket| handle_socket(socket, user_id, state.connections.clone())))
+  Ok(
+    ws.max_message_size(MAX_MESSAGE_SIZE)
+      .on_upgrade(move |socket| {
+        handle_socket(socket, user_id, state.db.clone(), state.connections.clone())
+      })
+      .into_response(),
+  )
+}
+
+async fn reject_socket(mut socket: WebSocket, code: u16, reason: &'static str) {
+  let _ = socket
+    .send(Message::Close(Some(CloseFrame {
+      code,
+      reason: reason.into(),
+    })))
+    .await;
 }
 
-async fn handle_socket(socket: WebSocket, user_id: Uuid, connection_map: ConnectionMap) {
-  let connection = Connection::new(user_id, socket, connection_map);
+async fn handle_socket(
+  socket: WebSocket,
+  user_id: Uuid,
+  db: PgPool,
+  connection_map: ConnectionMap,
+) {
+  let connection = Connection::new(user_id, socket, db, connection_map);
   connection.handle().await;
 }
@@ -1,5 +1,10 @@
+pub mod close_code;
 pub mod connection;
 pub mod handler;
+pub mod presence;
+pub mod pubsub;
+pub mod voice;
 
 pub use connection::{ConnectionMap, WsMessage, broadcast_to_channel};
 pub use handler::ws_handler;
+pub use pubsub::PubSub;
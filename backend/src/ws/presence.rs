@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::PresenceService;
+use crate::ws::connection::ConnectionMap;
+use crate::ws::voice::broadcast_voice_state;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+// Keeps this node's sessions alive and cleans up after nodes that stopped, including this
+// node's own previous run
+pub fn spawn_heartbeat(db: PgPool, connection_map: ConnectionMap) {
+  tokio::spawn(async move {
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+      interval.tick().await;
+
+      match PresenceService::heartbeat(&db, connection_map.node_id).await {
+        Ok(true) => restore_sessions(&db, &connection_map).await,
+        Ok(false) => {}
+        Err(e) => tracing::error!("Failed to record gateway heartbeat: {}", e),
+      }
+
+      match PresenceService::remove_stale_nodes(&db).await {
+        Ok(voice_states) => {
+          for state in voice_states {
+            broadcast_voice_state(&db, &connection_map, state).await;
+          }
+        }
+        Err(e) => tracing::error!("Failed to remove stale gateway nodes: {}", e),
+      }
+    }
+  });
+}
+
+// After missed heartbeats the other nodes drop this node's sessions even though its sockets are
+// still open. Voice states went with them and members were told, clients have to rejoin
+async fn restore_sessions(db: &PgPool, connection_map: &ConnectionMap) {
+  let (session_ids, user_ids): (Vec<Uuid>, Vec<Uuid>) = {
+    let users = connection_map.users.read().await;
+    users
+      .iter()
+      .flat_map(|(user_id, handles)| handles.iter().map(|h| (h.session_id, *user_id)))
+      .unzip()
+  };
+
+  if session_ids.is_empty() {
+    return;
+  }
+
+  tracing::warn!(
+    "Gateway node was taken for stopped, restoring {} sessions",
+    session_ids.len()
+  );
+
+  if let Err(e) =
+    PresenceService::restore_sessions(db, connection_map.node_id, &session_ids, &user_ids).await
+  {
+    tracing::error!("Failed to restore gateway sessions: {}", e);
+  }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, postgres::PgListener};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::ws::connection::{ConnectionMap, WsMessage, deliver_to_channel, deliver_to_users};
+
+const NOTIFY_CHANNEL: &str = "harmony_gateway";
+
+// Postgres rejects NOTIFY payloads of 8000 bytes or more
+const MAX_NOTIFY_PAYLOAD: usize = 7900;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Target {
+  Channel { channel_id: Uuid },
+  Users { user_ids: Vec<Uuid> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+  pub target: Target,
+  pub exclude_user: Option<Uuid>,
+  pub message: WsMessage,
+}
+
+// Payload sent over NOTIFY, large envelopes are stored in gateway_events
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Notification {
+  Inline(Box<Envelope>),
+  Stored { event_id: i64 },
+}
+
+// The memory receiver is taken by the listener, unbounded so no event is ever dropped
+#[derive(Clone)]
+pub enum PubSub {
+  Memory(
+    mpsc::UnboundedSender<Envelope>,
+    Arc<Mutex<Option<mpsc::UnboundedReceiver<Envelope>>>>,
+  ),
+  Postgres(PgPool),
+}
+
+impl PubSub {
+  pub fn memory() -> Self {
+    let (tx, rx) = mpsc::unbounded_channel();
+    PubSub::Memory(tx, Arc::new(Mutex::new(Some(rx))))
+  }
+
+  pub fn postgres(db: PgPool) -> Self {
+    PubSub::Postgres(db)
+  }
+
+  pub async fn publish(&self, envelope: Envelope) -> Result<(), Box<dyn std::error::Error>> {
+    match self {
+      PubSub::Memory(tx, _) => {
+        // Queued until the listener starts
+        let _ = tx.send(envelope);
+      }
+      PubSub::Postgres(db) => {
+        let mut payload = serde_json::to_string(&Notification::Inline(Box::new(envelope)))?;
+
+        if payload.len() > MAX_NOTIFY_PAYLOAD {
+          let event_id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO gateway_events (payload)
+            VALUES ($1::jsonb)
+            RETURNING id
+            "#,
+          )
+          .bind(&payload)
+          .fetch_one(db)
+          .await?;
+
+          sqlx::query("DELETE FROM gateway_events WHERE created_at < NOW() - INTERVAL '5 minutes'")
+            .execute(db)
+            .await?;
+
+          payload = serde_json::to_string(&Notification::Stored { event_id })?;
+        }
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+          .bind(NOTIFY_CHANNEL)
+          .bind(&payload)
+          .execute(db)
+          .await?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // Starts delivering published envelopes to the sockets connected to this node
+  pub async fn listen(&self, connection_map: ConnectionMap) -> Result<(), sqlx::Error> {
+    match self {
+      PubSub::Memory(_, rx) => {
+        let Some(mut rx) = rx.lock().ok().and_then(|mut rx| rx.take()) else {
+          tracing::warn!("Gateway listener is already running");
+          return Ok(());
+        };
+
+        tokio::spawn(async move {
+          while let Some(envelope) = rx.recv().await {
+            deliver(&connection_map, envelope).await;
+          }
+        });
+      }
+      PubSub::Postgres(db) => {
+        let db = db.clone();
+        let mut listener = PgListener::connect_with(&db).await?;
+        listener.listen(NOTIFY_CHANNEL).await?;
+
+        tokio::spawn(async move {
+          loop {
+            let notification = match listener.recv().await {
+              Ok(notification) => notification,
+              Err(e) => {
+                tracing::error!("Gateway listener error: {}", e);
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                continue;
+              }
+            };
+
+            match resolve_notification(&db, notification.payload()).await {
+              Ok(Some(envelope)) => deliver(&connection_map, envelope).await,
+              Ok(None) => {}
+              Err(e) => tracing::error!("Failed to decode gateway event: {}", e),
+            }
+          }
+        });
+
+        tracing::info!("Listening for gateway events on '{}'", NOTIFY_CHANNEL);
+      }
+    }
+
+    Ok(())
+  }
+}
+
+async fn resolve_notification(
+  db: &PgPool,
+  payload: &str,
+) -> Result<Option<Envelope>, Box<dyn std::error::Error + Send + Sync>> {
+  match serde_json::from_str::<Notification>(payload)? {
+    Notification::Inline(envelope) => Ok(Some(*envelope)),
+    Notification::Stored { event_id } => {
+      let payload: Option<serde_json::Value> =
+        sqlx::query_scalar("SELECT payload FROM gateway_events WHERE id = $1")
+          .bind(event_id)
+          .fetch_optional(db)
+          .await?;
+
+      match payload.map(serde_json::from_value::<Notification>) {
+        Some(Ok(Notification::Inline(envelope))) => Ok(Some(*envelope)),
+        Some(Ok(Notification::Stored { .. })) => Ok(None),
+        Some(Err(e)) => Err(e.into()),
+        None => {
+          tracing::warn!("Gateway event {} no longer exists", event_id);
+          Ok(None)
+        }
+      }
+    }
+  }
+}
+
+async fn deliver(connection_map: &ConnectionMap, envelope: Envelope) {
+  match envelope.target {
+    Target::Channel { channel_id } => {
+      deliver_to_channel(
+        connection_map,
+        channel_id,
+        &envelope.message,
+        envelope.exclude_user,
+      )
+      .await
+    }
+    Target::Users { user_ids } => {
+      deliver_to_users(connection_map, &user_ids, &envelope.message).await
+    }
+  }
+}
@@ -0,0 +1,140 @@
+use axum::extract::ws::Message;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::VoiceState;
+use crate::services::VoiceService;
+use crate::utils::{AppError, AppResult, ErrorCode};
+use crate::ws::connection::{ConnectionMap, Tx, WsMessage};
+
+fn reply(tx: &Tx, message: &WsMessage) {
+  if let Ok(json) = serde_json::to_string(message) {
+    let _ = tx.send(Message::Text(json.into()));
+  }
+}
+
+pub async fn broadcast_voice_state(db: &PgPool, connection_map: &ConnectionMap, state: VoiceState) {
+  let server_id = state.server_id;
+  let message = WsMessage::VoiceStateUpdated { voice_state: state };
+  if let Err(e) = connection_map
+    .broadcast_to_server(db, server_id, message, None)
+    .await
+  {
+    tracing::error!("Failed to broadcast voice state: {}", e);
+  }
+}
+
+// Failures are reported to the socket that sent the message
+pub async fn handle_message(
+  db: &PgPool,
+  connection_map: &ConnectionMap,
+  user_id: Uuid,
+  session_id: Uuid,
+  tx: &Tx,
+  message: WsMessage,
+) {
+  if let Err(e) = handle(db, connection_map, user_id, session_id, tx, message).await {
+    let message = match e {
+      AppError::DatabaseError(_) | AppError::InternalServerError(_) => {
+        tracing::error!("Voice request from user {} failed: {}", user_id, e);
+        "Something went wrong".to_string()
+      }
+      e => e.to_string(),
+    };
+    reply(tx, &WsMessage::Error { message });
+  }
+}
+
+async fn handle(
+  db: &PgPool,
+  connection_map: &ConnectionMap,
+  user_id: Uuid,
+  session_id: Uuid,
+  tx: &Tx,
+  message: WsMessage,
+) -> AppResult<()> {
+  match message {
+    WsMessage::JoinVoice {
+      channel_id,
+      self_mute,
+      self_deaf,
+    } => {
+      let (previous, state) =
+        VoiceService::join(db, user_id, session_id, channel_id, self_mute, self_deaf).await?;
+
+      // Members of the server that was left would not see the move otherwise
+      if let Some(mut previous) = previous
+        && previous.server_id != state.server_id
+      {
+        previous.channel_id = None;
+        broadcast_voice_state(db, connection_map, previous).await;
+      }
+
+      let voice_states = VoiceService::get_channel_states(db, channel_id)
+        .await?
+        .into_iter()
+        .filter(|state| state.user_id != user_id)
+        .collect();
+      reply(
+        tx,
+        &WsMessage::VoiceJoined {
+          channel_id,
+          voice_states,
+        },
+      );
+
+      broadcast_voice_state(db, connection_map, state).await;
+    }
+    WsMessage::LeaveVoice => {
+      if let Some(state) = VoiceService::leave(db, user_id, Some(session_id)).await? {
+        broadcast_voice_state(db, connection_map, state).await;
+      }
+    }
+    WsMessage::UpdateVoiceState {
+      self_mute,
+      self_deaf,
+    } => {
+      let state = VoiceService::update(db, user_id, session_id, self_mute, self_deaf).await?;
+      broadcast_voice_state(db, connection_map, state).await;
+    }
+    WsMessage::VoiceSignal {
+      channel_id,
+      user_id: target_id,
+      signal,
+    } => {
+      if !VoiceService::can_signal(db, channel_id, user_id, session_id, target_id).await? {
+        return Err(AppError::BadRequest(
+          ErrorCode::NotInVoiceChannel,
+          "You and that user are not in this voice channel".to_string(),
+        ));
+      }
+
+      // Every socket of the target gets it, only the one in the call acts on it
+      let message = WsMessage::VoiceSignalReceived {
+        channel_id,
+        user_id,
+        signal,
+      };
+      if let Err(e) = connection_map.send_to_user(target_id, message).await {
+        tracing::error!("Failed to relay voice signal: {}", e);
+      }
+    }
+    _ => {}
+  }
+
+  Ok(())
+}
+
+// Called when a socket closes, the user leaves voice if this socket had joined
+pub async fn disconnect(
+  db: &PgPool,
+  connection_map: &ConnectionMap,
+  user_id: Uuid,
+  session_id: Uuid,
+) {
+  match VoiceService::leave(db, user_id, Some(session_id)).await {
+    Ok(Some(state)) => broadcast_voice_state(db, connection_map, state).await,
+    Ok(None) => {}
+    Err(e) => tracing::error!("Failed to clear voice state of user {}: {}", user_id, e),
+  }
+}
@@ -6,12 +6,15 @@ import {
 	ErrorResponseSchema,
 	FriendshipSchema,
 	FullProfileSchema,
+	LoginResponseSchema,
 	MessageSchema,
 	PaginatedResponse,
 	ProfileSchema,
 	ServerSchema,
 	type ErrorResponse,
+	type FieldError,
 	type LoginRequest,
+	type MfaLoginRequest,
 	type RegisterRequest
 } from './types'
 import { browser, dev } from '$app/environment'
@@ -21,7 +24,9 @@ export const API_URL = env.PUBLIC_API_URL || ''
 export class ApiError extends Error {
 	constructor(
 		public status: number,
-		message: string
+		message: string,
+		public code?: string,
+		public errors: FieldError[] = []
 	) {
 		super(message)
 		this.name = 'ApiError'
@@ -29,6 +34,8 @@ export class ApiError extends Error {
 }
 
 class Api {
+	private emojiUrls = new Map<string, Promise<string>>()
+
 	private async fetch<T>(
 		endpoint: string,
 		options: RequestInit = {},
@@ -49,7 +56,12 @@ class Api {
 				.json()
 				.then((j) => ErrorResponseSchema.parse(j))
 				.catch<ErrorResponse>(() => ({ message: 'An error occurred' }))
-			throw new ApiError(response.status, error.message || error.error || 'An error occurred')
+			throw new ApiError(
+				response.status,
+				error.message || error.error || 'An error occurred',
+				error.code,
+				error.errors
+			)
 		}
 
 		const json = await response.json()
@@ -63,6 +75,25 @@ class Api {
 		throw new ApiError(response.status, 'Unexpected response')
 	}
 
+	// Emoji images are members only, so they are fetched with the token and shown as object URLs
+	getEmojiUrl(id: string) {
+		let url = this.emojiUrls.get(id)
+
+		if (!url) {
+			const token = localStorage.getItem('token')
+			url = fetch(`${API_URL}/api/emojis/${id}/image`, {
+				headers: token ? { Authorization: `Bearer ${token}` } : {}
+			}).then(async (response) => {
+				if (!response.ok) throw new ApiError(response.status, 'Failed to load emoji')
+				return URL.createObjectURL(await response.blob())
+			})
+			url.catch(() => this.emojiUrls.delete(id))
+			this.emojiUrls.set(id, url)
+		}
+
+		return url
+	}
+
 	async register(data: RegisterRequest) {
 		return this.fetch(
 			'/api/auth/register',
@@ -75,6 +106,14 @@ class Api {
 		return this.fetch(
 			'/api/auth/login',
 			{ method: 'POST', body: JSON.stringify(data) },
+			LoginResponseSchema
+		)
+	}
+
+	async loginMfa(data: MfaLoginRequest) {
+		return this.fetch(
+			'/api/auth/login/mfa',
+			{ method: 'POST', body: JSON.stringify(data) },
 			AuthResponseSchema
 		)
 	}
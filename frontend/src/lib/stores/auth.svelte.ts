@@ -1,4 +1,10 @@
-import { UserSchema, type User, type LoginRequest, type RegisterRequest } from '$lib/types'
+import {
+	UserSchema,
+	type User,
+	type LoginRequest,
+	type MfaLoginRequest,
+	type RegisterRequest
+} from '$lib/types'
 import debug from 'debug'
 import { websocket } from './websocket.svelte'
 import { goto } from '$app/navigation'
@@ -13,6 +19,8 @@ class AuthStore {
 	token = $state<string | null>(null)
 	loading = $state(false)
 	error = $state<string | null>(null)
+	// Set when the password was accepted but a two-factor code is still needed
+	mfaTicket = $state<string | null>(null)
 
 	get isAuthenticated() {
 		return !!this.user && !!this.token
@@ -40,6 +48,29 @@ class AuthStore {
 
 		try {
 			const response = await api.login(data)
+
+			if ('mfa_required' in response) {
+				this.mfaTicket = response.ticket
+				return
+			}
+
+			this.setAuth(response.user, response.token)
+			await goto(resolve('/app'))
+		} catch (error) {
+			this.error = ensureError(error).message || 'Login failed'
+			throw error
+		} finally {
+			this.loading = false
+		}
+	}
+
+	async loginMfa(data: MfaLoginRequest) {
+		this.loading = true
+		this.error = null
+
+		try {
+			const response = await api.loginMfa(data)
+			this.mfaTicket = null
 			this.setAuth(response.user, response.token)
 			await goto(resolve('/app'))
 		} catch (error) {
@@ -70,6 +101,7 @@ class AuthStore {
 		this.user = null
 		this.token = null
 		this.error = null
+		this.mfaTicket = null
 
 		localStorage.removeItem('token')
 		localStorage.removeItem('user')
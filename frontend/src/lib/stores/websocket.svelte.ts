@@ -8,6 +8,9 @@ export type MessageHandler = (message: WsMessage) => void
 
 const log = debug('harmony:websocket-store')
 
+// Close codes sent when the token is rejected, reconnecting with it again is pointless
+const AUTH_CLOSE_CODES = new Set([4001, 4002, 4003])
+
 class WebSocketStore {
 	private ws = $state<WebSocket | null>(null)
 	private reconnectTimeout: ReturnType<typeof setTimeout> | null = null
@@ -41,9 +44,10 @@ class WebSocketStore {
 				}
 			}
 
-			this.ws.onclose = () => {
-				log('Disconnected')
+			this.ws.onclose = (event) => {
+				log('Disconnected', event.code, event.reason)
 				this.connected = false
+				if (AUTH_CLOSE_CODES.has(event.code)) return
 				this.scheduleReconnect(token)
 			}
 
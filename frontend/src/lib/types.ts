@@ -1,16 +1,27 @@
 import z from 'zod'
 
+export const FieldErrorSchema = z.object({
+	field: z.string(),
+	code: z.string(),
+	message: z.string()
+})
+
 export const ErrorResponseSchema = z.object({
+	code: z.string().optional(),
 	error: z.string().optional(),
-	message: z.string().optional()
+	message: z.string().optional(),
+	errors: z.array(FieldErrorSchema).optional()
 })
 
+export type FieldError = z.infer<typeof FieldErrorSchema>
 export type ErrorResponse = z.infer<typeof ErrorResponseSchema>
 
 export const UserSchema = z.object({
 	id: z.uuid(),
 	username: z.string(),
 	email: z.email(),
+	email_verified: z.boolean().optional(),
+	mfa_enabled: z.boolean().optional(),
 	created_at: z.iso.datetime()
 })
 
@@ -45,16 +56,36 @@ export const AuthResponseSchema = z.object({
 
 export type AuthResponse = z.infer<typeof AuthResponseSchema>
 
+export const MfaRequiredResponseSchema = z.object({
+	mfa_required: z.literal(true),
+	ticket: z.string()
+})
+
+export const LoginResponseSchema = z.union([AuthResponseSchema, MfaRequiredResponseSchema])
+
+export type LoginResponse = z.infer<typeof LoginResponseSchema>
+
+export const MfaLoginRequestSchema = z.object({
+	ticket: z.string(),
+	code: z.string().trim().min(6, 'Enter the code from your authenticator app')
+})
+
+export type MfaLoginRequest = z.infer<typeof MfaLoginRequestSchema>
+
 export const ServerSchema = z.object({
 	id: z.uuid(),
 	name: z.string(),
 	owner_id: z.uuid(),
 	main_channel_id: z.uuid().optional().nullable(),
+	public: z.boolean().optional(),
+	description: z.string().optional().nullable(),
+	tags: z.array(z.string()).optional(),
+	member_count: z.number().optional(),
 	is_owner: z.boolean(),
 	created_at: z.iso.datetime()
 })
 
-export type Server = z.infer<typeof ServerSchema>
+export type Server =z.infer<typeof ServerSchema>
 
 export const ChannelTypeSchema = z.enum(['text', 'voice', 'dm', 'group_dm'])
 export type ChannelType = z.infer<typeof ChannelTypeSchema>
@@ -67,17 +98,35 @@ export const ChannelSchema = z.object({
 	channel_type: ChannelTypeSchema,
 	topic: z.string().optional().nullable(),
 	is_private: z.boolean(),
+	slowmode_seconds: z.number().optional(),
 	created_at: z.iso.datetime()
 })
 
 export type Channel = z.infer<typeof ChannelSchema>
 
+export const MessageTypeSchema = z.enum([
+	'default',
+	'member_join',
+	'member_leave',
+	'channel_pinned',
+	'recipient_add',
+	'recipient_remove',
+	'group_rename',
+	'group_icon_change',
+	'group_owner_change'
+])
+export type MessageType = z.infer<typeof MessageTypeSchema>
+
 export const MessageSchema = z.object({
 	id: z.uuid(),
 	channel_id: z.uuid(),
-	user_id: z.uuid(),
-	username: z.string(),
+	user_id: z.uuid().optional().nullable(),
+	username: z.string().optional().nullable(),
+	webhook_id: z.uuid().optional().nullable(),
+	avatar_url: z.string().optional().nullable(),
+	message_type: MessageTypeSchema.optional(),
 	content: z.string(),
+	system_data: z.record(z.string(), z.unknown()).optional().nullable(),
 	created_at: z.iso.datetime(),
 	updated_at: z.iso.datetime()
 })
@@ -97,12 +146,28 @@ export const WsMessageSchema = z.discriminatedUnion('type', [
 		type: z.literal('message_created'),
 		id: z.uuid(),
 		channel_id: z.uuid(),
-		user_id: z.uuid(),
-		username: z.string(),
+		user_id: z.uuid().optional().nullable(),
+		username: z.string().optional().nullable(),
+		webhook_id: z.uuid().optional().nullable(),
+		avatar_url: z.string().optional().nullable(),
+		message_type: MessageTypeSchema.optional(),
 		content: z.string(),
+		system_data: z.record(z.string(), z.unknown()).optional().nullable(),
 		created_at: z.iso.datetime(),
 		updated_at: z.iso.datetime()
 	}),
+	z.object({
+		type: z.literal('message_updated'),
+		id: z.uuid(),
+		channel_id: z.uuid(),
+		content: z.string(),
+		updated_at: z.iso.datetime()
+	}),
+	z.object({
+		type: z.literal('message_deleted'),
+		id: z.uuid(),
+		channel_id: z.uuid()
+	}),
 	z.object({
 		type: z.literal('subscribed'),
 		channel_id: z.uuid()
@@ -129,7 +194,7 @@ export const ProfileSchema = z.object({
 	custom_status: z.string().optional().nullable(),
 	status_emoji: z.string().optional().nullable(),
 	show_online_status: z.boolean(),
-	allow_dms: z.boolean(),
+	dm_privacy: z.enum(['everyone', 'server_members', 'friends']),
 	created_at: z.iso.datetime(),
 	updated_at: z.iso.datetime()
 })
@@ -139,6 +204,7 @@ export type Profile = z.infer<typeof ProfileSchema>
 export const FullProfileSchema = z.object({
 	id: z.uuid(),
 	username: z.string(),
+	bot: z.boolean().optional(),
 	display_name: z.string().optional().nullable(),
 	bio: z.string().optional().nullable(),
 	avatar_url: z.string().optional().nullable(),